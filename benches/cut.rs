@@ -0,0 +1,91 @@
+use ansi_cut::AnsiCut;
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use owo_colors::{colors::*, OwoColorize};
+
+fn colored_line() -> String {
+    (0..200)
+        .map(|i| match i % 3 {
+            0 => format!("{} ", "column".fg::<Red>()),
+            1 => format!("{} ", "column".fg::<Blue>().bg::<White>()),
+            _ => format!("{} ", "column".bold()),
+        })
+        .collect()
+}
+
+// a long colored line which is formatted but not collected into a string
+struct ColoredLine;
+
+impl std::fmt::Display for ColoredLine {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for i in 0..200 {
+            match i % 3 {
+                0 => write!(f, "{} ", "column".fg::<Red>())?,
+                1 => write!(f, "{} ", "column".fg::<Blue>().bg::<White>())?,
+                _ => write!(f, "{} ", "column".bold())?,
+            }
+        }
+
+        Ok(())
+    }
+}
+
+fn cut_many(c: &mut Criterion) {
+    let line = colored_line();
+    let ranges = (0..20).map(|i| i * 50..i * 50 + 10).collect::<Vec<_>>();
+
+    let mut group = c.benchmark_group("cut_many");
+    group.bench_function("cut_many", |b| {
+        b.iter(|| ansi_cut::cut_many(black_box(&line), black_box(&ranges)))
+    });
+    group.bench_function("cut_loop", |b| {
+        b.iter(|| {
+            black_box(&ranges)
+                .iter()
+                .map(|range| black_box(&line).cut(range.clone()))
+                .collect::<Vec<_>>()
+        })
+    });
+    group.finish();
+}
+
+fn chunks(c: &mut Criterion) {
+    let line = colored_line().repeat(20);
+    let chars = "column ".len() * 200 * 20;
+
+    let mut group = c.benchmark_group("chunks");
+    group.sample_size(10);
+    group.bench_function("chunks", |b| {
+        b.iter(|| ansi_cut::chunks(black_box(&line), black_box(80)))
+    });
+    // the way chunks used to be made
+    group.bench_function("cut_loop", |b| {
+        b.iter(|| {
+            (0..chars)
+                .step_by(80)
+                .map(|i| black_box(&line).cut_strict(i..std::cmp::min(i + 80, chars)))
+                .collect::<Vec<_>>()
+        })
+    });
+    group.finish();
+}
+
+fn cut_display(c: &mut Criterion) {
+    let mut group = c.benchmark_group("cut_display");
+    for (name, range) in [("head", 0..80), ("middle", 700..780)] {
+        group.bench_function(format!("cut_display_{}", name), |b| {
+            b.iter(|| ansi_cut::cut_display(black_box(ColoredLine), black_box(range.clone())))
+        });
+        // the way a value had to be cut before
+        group.bench_function(format!("to_string_cut_{}", name), |b| {
+            b.iter(|| {
+                black_box(ColoredLine)
+                    .to_string()
+                    .cut(black_box(range.clone()))
+            })
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, cut_many, chunks, cut_display);
+criterion_main!(benches);
@@ -0,0 +1,29 @@
+#![no_main]
+
+use ansi_cut::AnsiCut;
+use libfuzzer_sys::fuzz_target;
+
+// The first byte is a number of chars to cut at, the rest is a string.
+//
+// Cutting must never panic and it must keep the visible chars,
+// every chunk of `chunks(s, 1)` is exactly one char.
+fuzz_target!(|data: &[u8]| {
+    let (n, data) = match data.split_first() {
+        Some((&n, data)) => (usize::from(n), data),
+        None => return,
+    };
+
+    let s = String::from_utf8_lossy(data).into_owned();
+    let chars = ansi_cut::chunks(&s, 1).len();
+    let n = n.min(chars);
+
+    let head = s.take_visible(n);
+    let tail = s.skip_visible(n);
+    assert_eq!(ansi_cut::chunks(&head, 1).len(), n);
+    assert_eq!(ansi_cut::chunks(&tail, 1).len(), chars - n);
+
+    for size in 1..4 {
+        let _ = ansi_cut::chunks(&s, size);
+        let _ = ansi_cut::rchunks(&s, size);
+    }
+});
@@ -0,0 +1,1688 @@
+//! Splitting a string into chunks.
+
+#[cfg(feature = "unicode-width")]
+use crate::{char_width, push_spaces, WideCharPolicy};
+use crate::{
+    complete_ansi_sequences, control_replacement, is_style_escape, open_ansi_sequences, parse_ansi,
+    prune_escapes, split_first_token, srip_ansi_sequences, update_ansi_state_by_escape,
+    AnsiCutError, AnsiIndexedStr, AnsiState, ControlPolicy, CutOptions, PadStyle, Style, Token,
+};
+use std::ops::Range;
+
+/// Returns an Vec over chunk_size elements of string, starting at the beginning of the slice.
+/// It uses chars but not bytes!
+///
+/// The chunks are vectors and do not overlap.
+/// If chunk_size does not divide the length of the slice, then the last chunk will not have length chunk_size.
+///
+/// Each chunk starts only with a style which is active at its beginning,
+/// escape sequences of previous chunks are not copied.
+/// And each chunk closes all styles at its end,
+/// even if the string itself leaves them open.
+///
+/// Escape sequences between 2 chunks belong to the second one,
+/// only ones after the last char of the string go to the last chunk.
+///
+/// Every chunk has at least 1 char in it, so there's no chunk made only of escape sequences
+/// (like ones [`AnsiCut::cut`](crate::AnsiCut::cut) may give), a string with no text has no chunks.
+///
+/// See [`chunks::Chunks`](crate::chunks::Chunks) for more options.
+///
+/// # Panics
+///
+/// Panics if chunk_size is 0.
+///
+/// # Examples
+///
+/// ```rust
+/// use owo_colors::{OwoColorize, colors::*};
+/// let colored_text = format!("{} {} {}", "A".fg::<Black>(), "Colored".fg::<Red>(), "Text".fg::<Blue>()).bg::<Yellow>().to_string();
+/// let chunks = ansi_cut::chunks(&colored_text, 3);
+/// for chunk in &chunks {
+///     println!("{}", chunk);
+/// }
+/// ```
+pub fn chunks(s: &str, chunk_size: usize) -> Vec<String> {
+    chunks_iter(s, chunk_size).collect()
+}
+
+/// Returns an Vec over chunk_size elements of string, starting at the end of the slice.
+/// It uses chars but not bytes!
+///
+/// The first chunk is the last chunk_size chars of the string,
+/// only the last chunk may be shorter than chunk_size, like in [`slice::rchunks`].
+///
+/// Each chunk starts with a style which is active at its beginning.
+///
+/// # Panics
+///
+/// Panics if chunk_size is 0.
+///
+/// # Examples
+///
+/// ```rust
+/// use owo_colors::{OwoColorize, colors::*};
+/// let colored_text = format!("{}{}", "1234".fg::<Red>(), "5678".fg::<Blue>());
+/// assert_eq!(
+///     ansi_cut::rchunks(&colored_text, 3),
+///     vec![
+///         "678".fg::<Blue>().to_string(),
+///         format!("{}{}", "34".fg::<Red>(), "5".fg::<Blue>()),
+///         "12".fg::<Red>().to_string(),
+///     ],
+/// );
+/// ```
+pub fn rchunks(s: &str, chunk_size: usize) -> Vec<String> {
+    let mut iter = chunks_iter(s, chunk_size);
+
+    let mut chunks = Vec::with_capacity(iter.len());
+    let first_chunk_size = iter.chars_left % chunk_size;
+    if first_chunk_size > 0 {
+        chunks.push(iter.next_chunk(first_chunk_size).text);
+    }
+
+    chunks.extend(iter);
+    chunks.reverse();
+
+    chunks
+}
+
+/// Returns an Vec over chunks of string which are at most max_bytes bytes long,
+/// including their escape sequences.
+///
+/// Chunks are as long as possible, they never split a UTF-8 character or an escape sequence.
+/// Each chunk starts with a style active at its beginning and closes it at the end.
+///
+/// # Errors
+///
+/// Returns [`AnsiCutError::BudgetTooSmall`] if a single char together with
+/// escape sequences it requires doesn't fit in max_bytes.
+///
+/// # Examples
+///
+/// ```rust
+/// use owo_colors::{OwoColorize, colors::*};
+/// let colored_text = "When the night".fg::<Red>().to_string();
+/// let chunks = ansi_cut::chunks_bytes_budget(&colored_text, 18).unwrap();
+/// assert_eq!(
+///     chunks,
+///     vec![
+///         "When the".fg::<Red>().to_string(),
+///         " night".fg::<Red>().to_string(),
+///     ],
+/// );
+/// assert!(chunks.iter().all(|chunk| chunk.len() <= 18));
+///
+/// assert!(ansi_cut::chunks_bytes_budget(&colored_text, 10).is_err());
+/// ```
+pub fn chunks_bytes_budget(s: &str, max_bytes: usize) -> Result<Vec<String>, AnsiCutError> {
+    let mut iter = chunks_iter(s, 1);
+
+    let mut chunks = Vec::new();
+    while iter.chars_left > 0 {
+        // a char takes at least 1 byte
+        let mut lo = 0;
+        let mut hi = std::cmp::min(iter.chars_left, max_bytes);
+        let mut found = None;
+        while lo < hi {
+            let chars = lo + (hi - lo).div_ceil(2);
+            let mut probe = iter.clone();
+            let chunk = probe.next_chunk(chars).text;
+            if chunk.len() <= max_bytes {
+                lo = chars;
+                found = Some((chunk, probe));
+            } else {
+                hi = chars - 1;
+            }
+        }
+
+        match found {
+            Some((chunk, probe)) => {
+                chunks.push(chunk);
+                iter = probe;
+            }
+            None => {
+                let index = iter.index;
+                return Err(AnsiCutError::BudgetTooSmall { index, max_bytes });
+            }
+        }
+    }
+
+    Ok(chunks)
+}
+
+/// Returns an Vec over chunks of at most chunk_size chars of string, which are not split in the middle of a word.
+///
+/// A chunk is broken at whitespace if there's any in its chars,
+/// only a word which is longer than chunk_size is split.
+/// Whitespace at a break is dropped, but the next chunk still starts with a style active after it.
+///
+/// # Panics
+///
+/// Panics if chunk_size is 0.
+///
+/// # Examples
+///
+/// ```rust
+/// use owo_colors::{OwoColorize, colors::*};
+/// let colored_text = format!("{} {}", "It's".fg::<Red>(), "important".fg::<Blue>());
+/// assert_eq!(
+///     ansi_cut::chunks_words(&colored_text, 7),
+///     vec![
+///         "It's".fg::<Red>().to_string(),
+///         "importa".fg::<Blue>().to_string(),
+///         "nt".fg::<Blue>().to_string(),
+///     ],
+/// );
+/// ```
+pub fn chunks_words(s: &str, chunk_size: usize) -> Vec<String> {
+    let mut iter = chunks_iter(s, chunk_size);
+    let chars = srip_ansi_sequences(s).chars().collect::<Vec<_>>();
+
+    let mut chunks = Vec::new();
+    let mut start = 0;
+    while start < chars.len() {
+        let mut end = start + chunk_size;
+        let mut next = end;
+        if end >= chars.len() {
+            end = chars.len();
+            next = end;
+        } else if let Some(i) = (start + 1..=end)
+            .rev()
+            .find(|&i| chars[i].is_whitespace() && !chars[i - 1].is_whitespace())
+        {
+            // a break at the end of a word
+            end = i;
+            next = i;
+            while next < chars.len() && chars[next].is_whitespace() {
+                next += 1;
+            }
+        }
+
+        chunks.push(iter.next_chunk(end - start).text);
+        if next > end {
+            // whitespace is dropped but its escape sequences are taken into account
+            iter.next_chunk(next - end);
+        }
+
+        start = next;
+    }
+
+    chunks
+}
+
+/// Returns an Vec over chunk_size elements of string, starting at the beginning of the slice.
+/// It uses chars but not bytes!
+///
+/// It's the same as [`chunks()`] but the last chunk is padded by `pad` chars,
+/// so all chunks have exactly chunk_size chars.
+///
+/// The padding goes after the closing sequences of the chunk so it's not colored.
+///
+/// # Panics
+///
+/// Panics if chunk_size is 0.
+///
+/// # Examples
+///
+/// ```rust
+/// use owo_colors::{OwoColorize, colors::*};
+/// let colored_text = "When the night".fg::<Red>().to_string();
+/// assert_eq!(
+///     ansi_cut::chunks_exact(&colored_text, 5, '.'),
+///     vec![
+///         "When ".fg::<Red>().to_string(),
+///         "the n".fg::<Red>().to_string(),
+///         format!("{}.", "ight".fg::<Red>()),
+///     ],
+/// );
+/// ```
+pub fn chunks_exact(s: &str, chunk_size: usize, pad: char) -> Vec<String> {
+    chunks_exact_with(s, chunk_size, pad, PadStyle::Plain)
+}
+
+/// The same as [`chunks_exact`] but with a control of how the padding is styled.
+///
+/// # Panics
+///
+/// Panics if chunk_size is 0.
+///
+/// # Examples
+///
+/// ```rust
+/// use owo_colors::{OwoColorize, colors::*};
+/// use ansi_cut::PadStyle;
+/// let colored_text = "When".bg::<Blue>().to_string();
+/// assert_eq!(
+///     ansi_cut::chunks_exact_with(&colored_text, 3, ' ', PadStyle::Background),
+///     vec![
+///         "Whe".bg::<Blue>().to_string(),
+///         format!("{}{}", "n".bg::<Blue>(), "  ".bg::<Blue>()),
+///     ],
+/// );
+/// ```
+pub fn chunks_exact_with(s: &str, chunk_size: usize, pad: char, style: PadStyle) -> Vec<String> {
+    let mut chunks = chunks(s, chunk_size);
+
+    if let Some(last) = chunks.last_mut() {
+        let stripped = srip_ansi_sequences(last);
+        let count = stripped.chars().count();
+        if count < chunk_size {
+            let mut padding_state = AnsiState::default();
+            if style == PadStyle::Background {
+                // a style of the last char
+                let last_char = stripped.char_indices().last().map_or(0, |(i, _)| i);
+                if let Some(style) = AnsiIndexedStr::new(last).style_at(last_char) {
+                    padding_state.bg_color = style.state.bg_color;
+                }
+            }
+
+            open_ansi_sequences(&padding_state, last);
+            for _ in count..chunk_size {
+                last.push(pad);
+            }
+            complete_ansi_sequences(&padding_state, last);
+        }
+    }
+
+    chunks
+}
+
+/// Returns an iterator over all windows of `size` chars of a string,
+/// the windows overlap and each next one starts one char further.
+///
+/// If the string is shorter than `size`, the iterator returns no values,
+/// like [`slice::windows`].
+///
+/// The string is parsed only once,
+/// each window starts with a style which is active at its beginning.
+///
+/// # Panics
+///
+/// Panics if size is 0.
+///
+/// # Examples
+///
+/// ```rust
+/// use owo_colors::{OwoColorize, colors::*};
+/// let colored_text = format!("{}{}", "ab".fg::<Red>(), "cd".fg::<Blue>());
+/// let mut windows = ansi_cut::windows(&colored_text, 3);
+/// assert_eq!(windows.next(), Some(format!("{}{}", "ab".fg::<Red>(), "c".fg::<Blue>())));
+/// assert_eq!(windows.next(), Some(format!("{}{}", "b".fg::<Red>(), "cd".fg::<Blue>())));
+/// assert_eq!(windows.next(), None);
+/// ```
+pub fn windows(s: &str, size: usize) -> AnsiWindows<'_> {
+    assert!(size > 0);
+
+    AnsiWindows {
+        text: AnsiIndexedStr::new(s),
+        size,
+        start: 0,
+    }
+}
+
+/// AnsiWindows is an iterator over overlapping windows of a string,
+/// which is created by [`windows`].
+#[derive(Debug, Clone)]
+pub struct AnsiWindows<'a> {
+    text: AnsiIndexedStr<'a>,
+    size: usize,
+    // a char index of the next window
+    start: usize,
+}
+
+impl Iterator for AnsiWindows<'_> {
+    type Item = String;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let end = self.start + self.size;
+        if end > self.text.end.chars {
+            return None;
+        }
+
+        let lower_bound = self.text.chars_to_bytes(self.start);
+        let upper_bound = self.text.chars_to_bytes(end);
+        let options = CutOptions::default().minimal(true).strict(true);
+        let window = self.text.cut_bytes(lower_bound, Some(upper_bound), options);
+
+        self.start += 1;
+
+        Some(window)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let count = (self.text.end.chars + 1).saturating_sub(self.start + self.size);
+        (count, Some(count))
+    }
+}
+
+impl ExactSizeIterator for AnsiWindows<'_> {}
+
+impl std::iter::FusedIterator for AnsiWindows<'_> {}
+
+/// Returns an Vec over chunk_size elements of string together with a style
+/// which is active at the end of each chunk.
+///
+/// It's the same as [`chunks()`], the style is the one the next chunk starts with,
+/// so the style of the last chunk tells whether the string leaves any style open.
+///
+/// # Panics
+///
+/// Panics if chunk_size is 0.
+///
+/// # Examples
+///
+/// ```rust
+/// use ansi_cut::Color;
+/// use owo_colors::{OwoColorize, colors::*};
+/// let colored_text = format!("{} {}", "When".fg::<Red>(), "the");
+/// let chunks = ansi_cut::chunks_with_style(&colored_text, 3);
+/// assert_eq!(chunks[0].0, "Whe".fg::<Red>().to_string());
+/// assert_eq!(chunks[0].1.fg(), Some(Color::Basic(1)));
+/// assert!(chunks[2].1.is_empty());
+/// ```
+pub fn chunks_with_style(s: &str, chunk_size: usize) -> Vec<(String, Style)> {
+    let mut iter = chunks_iter(s, chunk_size);
+
+    let mut chunks = Vec::with_capacity(iter.len());
+    while let Some(chunk) = iter.next() {
+        let style = Style {
+            state: iter.state.clone(),
+        };
+
+        chunks.push((chunk, style));
+    }
+
+    chunks
+}
+
+/// The same as [`chunks()`] but the chunks are written into a given Vec.
+///
+/// The Vec is cleared first, but its strings are reused,
+/// so chunking again and again into the same Vec doesn't allocate much.
+///
+/// # Panics
+///
+/// Panics if chunk_size is 0.
+///
+/// # Examples
+///
+/// ```rust
+/// use owo_colors::{OwoColorize, colors::*};
+/// let colored_text = "When the night".fg::<Red>().to_string();
+/// let mut chunks = Vec::new();
+/// for size in [3, 5, 10] {
+///     ansi_cut::chunks_into(&colored_text, size, &mut chunks);
+///     assert_eq!(chunks, ansi_cut::chunks(&colored_text, size));
+/// }
+/// ```
+pub fn chunks_into(s: &str, chunk_size: usize, out: &mut Vec<String>) {
+    let mut iter = chunks_iter(s, chunk_size);
+
+    let count = iter.len();
+    out.truncate(count);
+    out.resize_with(count, String::new);
+
+    for buf in out.iter_mut() {
+        buf.clear();
+        iter.next_into(buf);
+    }
+}
+
+/// Splits a string into runs of chars for which `f` returns the same value.
+///
+/// Escape sequences never split a run, the ones between runs go to the following run.
+/// Each run starts with a style active at its beginning and closes it at the end.
+///
+/// # Examples
+///
+/// ```rust
+/// use owo_colors::{OwoColorize, colors::*};
+/// let colored_text = "abc123de".fg::<Red>().to_string();
+/// assert_eq!(
+///     ansi_cut::chunk_by(&colored_text, |c| c.is_ascii_digit()),
+///     vec![
+///         "abc".fg::<Red>().to_string(),
+///         "123".fg::<Red>().to_string(),
+///         "de".fg::<Red>().to_string(),
+///     ],
+/// );
+/// ```
+pub fn chunk_by<F, K>(s: &str, mut f: F) -> Vec<String>
+where
+    F: FnMut(char) -> K,
+    K: PartialEq,
+{
+    let mut iter = chunks_iter(s, 1);
+    iter.trailing_escapes = false;
+
+    let mut runs = Vec::new();
+    let mut run: Option<(K, usize)> = None;
+    for c in srip_ansi_sequences(s).chars() {
+        let key = f(c);
+        match &mut run {
+            Some((run_key, len)) if *run_key == key => *len += 1,
+            _ => {
+                if let Some((_, len)) = run.take() {
+                    runs.push(iter.next_chunk(len).text);
+                }
+
+                run = Some((key, 1));
+            }
+        }
+    }
+
+    if let Some((_, len)) = run {
+        runs.push(iter.next_chunk(len).text);
+    }
+
+    runs
+}
+
+/// The same as [`chunks()`] but returns an error instead of panicking.
+///
+/// # Errors
+///
+/// Returns [`AnsiCutError::ZeroChunkSize`] if chunk_size is 0.
+///
+/// # Examples
+///
+/// ```rust
+/// use ansi_cut::AnsiCutError;
+/// assert_eq!(ansi_cut::try_chunks("something", 5), Ok(vec!["somet".to_string(), "hing".to_string()]));
+/// assert_eq!(ansi_cut::try_chunks("something", 0), Err(AnsiCutError::ZeroChunkSize));
+/// ```
+pub fn try_chunks(s: &str, chunk_size: usize) -> Result<Vec<String>, AnsiCutError> {
+    if chunk_size == 0 {
+        return Err(AnsiCutError::ZeroChunkSize);
+    }
+
+    Ok(chunks(s, chunk_size))
+}
+
+/// Returns an iterator over chunk_size elements of string, starting at the beginning of the slice.
+/// It uses chars but not bytes!
+///
+/// It's a lazy version of [`chunks()`], a chunk is made only when it's requested.
+///
+/// # Panics
+///
+/// Panics if chunk_size is 0.
+///
+/// # Examples
+///
+/// ```rust
+/// use owo_colors::{OwoColorize, colors::*};
+/// let colored_text = "When the night has come".fg::<Red>().to_string();
+/// let mut chunks = ansi_cut::chunks_iter(&colored_text, 5);
+/// assert_eq!(chunks.len(), 5);
+/// assert_eq!(chunks.next(), Some("When ".fg::<Red>().to_string()));
+/// assert_eq!(chunks.len(), 4);
+/// ```
+pub fn chunks_iter(s: &str, chunk_size: usize) -> AnsiChunks<'_> {
+    assert!(chunk_size > 0);
+
+    let chars_left = parse_ansi(s)
+        .map(|token| match token {
+            Token::Text(text) => text.chars().count(),
+            Token::Escape(_) => 0,
+        })
+        .sum();
+
+    AnsiChunks {
+        text: s,
+        rest: s,
+        block: "",
+        state: AnsiState::default(),
+        chunk_size,
+        index: 0,
+        chars_left,
+        escapes: None,
+        trailing_escapes: true,
+        control: ControlPolicy::Preserve,
+    }
+}
+
+/// Returns an Vec over chunk_size elements of string together with their positions.
+///
+/// It's the same as [`chunks()`] but each chunk also has
+/// a range of visible chars it covers and a range of bytes in the original string.
+///
+/// # Panics
+///
+/// Panics if chunk_size is 0.
+///
+/// # Examples
+///
+/// ```rust
+/// use owo_colors::{OwoColorize, colors::*};
+/// let colored_text = "When the night".fg::<Red>().to_string();
+/// let chunks = ansi_cut::chunks_indexed(&colored_text, 5);
+/// assert_eq!(chunks[1].visible_range, 5..10);
+/// assert_eq!(chunks[1].byte_range, 10..15);
+/// assert_eq!(&colored_text[chunks[1].byte_range.clone()], "the n");
+/// assert_eq!(chunks[1].text, "the n".fg::<Red>().to_string());
+/// ```
+pub fn chunks_indexed(s: &str, chunk_size: usize) -> Vec<IndexedChunk> {
+    let mut iter = chunks_iter(s, chunk_size);
+    std::iter::from_fn(|| iter.next_indexed()).collect()
+}
+
+/// IndexedChunk is a chunk of a string together with its position in the string,
+/// which is created by [`chunks_indexed`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IndexedChunk {
+    /// A range of visible chars the chunk covers.
+    pub visible_range: Range<usize>,
+    /// A range of bytes in the original string (including ANSI sequences),
+    /// from the first char of the chunk up to the end of its last char.
+    pub byte_range: Range<usize>,
+    /// The chunk itself.
+    pub text: String,
+}
+
+/// AnsiChunks is an iterator over chunks of a string,
+/// which is created by [`chunks_iter`].
+#[derive(Debug, Clone)]
+pub struct AnsiChunks<'a> {
+    pub(crate) text: &'a str,
+    // a part of the string which is not parsed yet
+    rest: &'a str,
+    // a part of the current text block which is not in any chunk yet
+    block: &'a str,
+    state: AnsiState,
+    chunk_size: usize,
+    // a number of chars which are already in chunks
+    index: usize,
+    pub(crate) chars_left: usize,
+    // all escape sequences met so far if chunks start with them
+    // instead of a minimal prefix
+    pub(crate) escapes: Option<String>,
+    // whether escape sequences after the last text are included in the last chunk,
+    // escape sequences between chunks always go to the following one
+    trailing_escapes: bool,
+    pub(crate) control: ControlPolicy,
+}
+
+impl AnsiChunks<'_> {
+    fn next_indexed(&mut self) -> Option<IndexedChunk> {
+        if self.chars_left == 0 {
+            return None;
+        }
+
+        let chars = std::cmp::min(self.chunk_size, self.chars_left);
+
+        Some(self.next_chunk(chars))
+    }
+
+    // Makes a chunk of the given number of chars,
+    // which must be not 0 and not bigger than the number of chars left.
+    pub(crate) fn next_chunk(&mut self, chars: usize) -> IndexedChunk {
+        let visible_range = self.index..self.index + chars;
+
+        let mut chunk = String::new();
+        let byte_range = self.write_chunk(chars, &mut chunk);
+
+        IndexedChunk {
+            visible_range,
+            byte_range,
+            text: chunk,
+        }
+    }
+
+    /// Writes the next chunk to the end of a buffer,
+    /// so the buffer can be reused for many chunks.
+    ///
+    /// Returns false if there's no chunks left.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use owo_colors::{OwoColorize, colors::*};
+    /// let colored_text = "When the night".fg::<Red>().to_string();
+    /// let mut chunks = ansi_cut::chunks_iter(&colored_text, 5);
+    /// let mut buf = String::new();
+    /// while chunks.next_into(&mut buf) {
+    ///     println!("{}", buf);
+    ///     buf.clear();
+    /// }
+    /// ```
+    pub fn next_into(&mut self, buf: &mut String) -> bool {
+        if self.chars_left == 0 {
+            return false;
+        }
+
+        let chars = std::cmp::min(self.chunk_size, self.chars_left);
+        self.write_chunk(chars, buf);
+
+        true
+    }
+
+    // Writes a chunk of the given number of chars,
+    // which must be not 0 and not bigger than the number of chars left.
+    //
+    // Returns a byte range of the chunk in the string.
+    fn write_chunk(&mut self, mut chars: usize, chunk: &mut String) -> Range<usize> {
+        self.chars_left -= chars;
+        self.index += chars;
+
+        // escape sequences before the first char of a chunk belong to it,
+        // styles among them are opened by a prefix so only the rest is kept
+        let mut controls = String::new();
+        if self.block.is_empty() {
+            self.read_until_text(Some(&mut controls), false, false);
+        }
+
+        let start = self.offset();
+        let mut end;
+
+        // a chunk starts with a style active at its start
+        match &self.escapes {
+            // only a cut from the beginning of the string keeps all its escape sequences
+            Some(escapes) if self.index == chars => chunk.push_str(escapes),
+            Some(escapes) => chunk.push_str(&prune_escapes(escapes)),
+            None => {
+                open_ansi_sequences(&self.state, chunk);
+                chunk.push_str(&controls);
+            }
+        }
+
+        loop {
+            let (text, rest) = split_at_chars(self.block, &mut chars);
+            chunk.push_str(text);
+            self.block = rest;
+            end = self.offset();
+
+            let is_last = self.chars_left == 0;
+            if !self.block.is_empty() || (chars == 0 && !(is_last && self.trailing_escapes)) {
+                break;
+            }
+
+            // escape sequences which follow the last chunk are included in it
+            let has_text = self.read_until_text(Some(chunk), chars == 0, true);
+
+            if chars == 0 || !has_text {
+                break;
+            }
+        }
+
+        complete_ansi_sequences(&self.state, chunk);
+
+        start..end
+    }
+
+    // Returns a byte index of the not yet chunked text in the string.
+    fn offset(&self) -> usize {
+        self.text.len() - self.rest.len() - self.block.len()
+    }
+
+    // Parses the string up to the next text block,
+    // escape sequences are copied to out if it's given,
+    // ones which change a style are copied only if styles is set.
+    //
+    // Returns false if there's no text left.
+    fn read_until_text(
+        &mut self,
+        mut out: Option<&mut String>,
+        is_after_end: bool,
+        styles: bool,
+    ) -> bool {
+        while let Some((token, rest)) = split_first_token(self.rest) {
+            self.rest = rest;
+
+            match token {
+                Token::Text(text) => {
+                    self.block = text;
+                    return true;
+                }
+                Token::Escape(seq) => {
+                    let seq_str = match control_replacement(&seq, self.control, is_after_end) {
+                        Some(replacement) => replacement,
+                        None => seq.to_string(),
+                    };
+                    if let Some(out) = out.as_mut() {
+                        if styles || !is_style_escape(&seq) {
+                            out.push_str(&seq_str);
+                        }
+                    }
+
+                    if let Some(escapes) = self.escapes.as_mut() {
+                        escapes.push_str(&seq_str);
+                    }
+
+                    update_ansi_state_by_escape(&mut self.state, &seq);
+                }
+            }
+        }
+
+        false
+    }
+}
+
+impl Iterator for AnsiChunks<'_> {
+    type Item = String;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_indexed().map(|chunk| chunk.text)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let count = self.chars_left.div_ceil(self.chunk_size);
+        (count, Some(count))
+    }
+}
+
+// Splits a string after n chars and decreases n by a number of chars taken.
+fn split_at_chars<'a>(s: &'a str, n: &mut usize) -> (&'a str, &'a str) {
+    let mut end = 0;
+    for c in s.chars() {
+        if *n == 0 {
+            break;
+        }
+
+        end += c.len_utf8();
+        *n -= 1;
+    }
+
+    s.split_at(end)
+}
+
+impl ExactSizeIterator for AnsiChunks<'_> {}
+
+impl std::iter::FusedIterator for AnsiChunks<'_> {}
+
+/// Returns an Vec over chunks of string which are at most `cols` display columns wide.
+///
+/// A wide character takes 2 columns and a zero width character (like a combining mark) takes none,
+/// it's kept together with a character it follows.
+/// A wide character which doesn't fit in the rest of a chunk goes to the next one.
+///
+/// Colors are carried across chunks the same way as in [`chunks()`].
+///
+/// # Panics
+///
+/// Panics if cols is 0.
+///
+/// # Examples
+///
+/// ```rust
+/// use owo_colors::{OwoColorize, colors::*};
+/// let colored_text = "你好world".fg::<Red>().to_string();
+/// assert_eq!(
+///     ansi_cut::chunks_width(&colored_text, 3),
+///     vec![
+///         "你".fg::<Red>().to_string(),
+///         "好w".fg::<Red>().to_string(),
+///         "orl".fg::<Red>().to_string(),
+///         "d".fg::<Red>().to_string(),
+///     ],
+/// );
+/// ```
+#[cfg(feature = "unicode-width")]
+pub fn chunks_width(s: &str, cols: usize) -> Vec<String> {
+    chunks_width_with(s, cols, WideCharPolicy::Exclude)
+}
+
+/// The same as [`chunks_width`] but with a control of what happens
+/// with a chunk which is short because a wide character was moved to the next one.
+///
+/// With [`WideCharPolicy::Pad`] all chunks but the last one are exactly `cols` wide.
+///
+/// # Panics
+///
+/// Panics if cols is 0.
+///
+/// # Examples
+///
+/// ```rust
+/// use owo_colors::{OwoColorize, colors::*};
+/// use ansi_cut::WideCharPolicy;
+/// let colored_text = "你好".fg::<Red>().to_string();
+/// assert_eq!(
+///     ansi_cut::chunks_width_with(&colored_text, 3, WideCharPolicy::Pad),
+///     vec![
+///         format!("{} ", "你".fg::<Red>()),
+///         "好".fg::<Red>().to_string(),
+///     ],
+/// );
+/// ```
+#[cfg(feature = "unicode-width")]
+pub fn chunks_width_with(s: &str, cols: usize, policy: WideCharPolicy) -> Vec<String> {
+    assert!(cols > 0);
+
+    let stripped = srip_ansi_sequences(s);
+    let mut iter = chunks_iter(s, cols);
+
+    let mut chunks = Vec::new();
+    let mut push_chunk = |chars: usize, width: usize, is_last: bool| {
+        let mut chunk = iter.next_chunk(chars).text;
+        if policy == WideCharPolicy::Pad && !is_last {
+            push_spaces(&mut chunk, cols.saturating_sub(width));
+        }
+
+        chunks.push(chunk);
+    };
+
+    let mut chars = 0;
+    let mut width = 0;
+    for c in stripped.chars() {
+        let w = char_width(c);
+        // a character which is wider than a chunk is left alone in its chunk
+        if width + w > cols && width > 0 {
+            push_chunk(chars, width, false);
+            chars = 0;
+            width = 0;
+        }
+
+        chars += 1;
+        width += w;
+    }
+
+    if chars > 0 {
+        push_chunk(chars, width, true);
+    }
+
+    chunks
+}
+
+/// Returns an Vec over chunks of string which fit in the width of the current terminal.
+///
+/// It's the same as [`chunks_width`] with the width taken from [`terminal_width`],
+/// `default_width` is used if the width can't be detected (e.g. stdout is not a TTY).
+///
+/// # Panics
+///
+/// Panics if the width is 0.
+///
+/// # Examples
+///
+/// ```rust
+/// use owo_colors::{OwoColorize, colors::*};
+/// let colored_text = "When the night has come".fg::<Red>().to_string();
+/// for chunk in ansi_cut::chunks_for_terminal(&colored_text, 80) {
+///     println!("{}", chunk);
+/// }
+/// ```
+#[cfg(feature = "terminal_size")]
+pub fn chunks_for_terminal(s: &str, default_width: usize) -> Vec<String> {
+    let cols = terminal_width().unwrap_or(default_width);
+    chunks_width(s, cols)
+}
+
+/// Returns the width of the current terminal in display columns,
+/// or `None` if stdout is not a terminal.
+///
+/// It's not cached so it may be worth to keep it if it's used often.
+#[cfg(feature = "terminal_size")]
+pub fn terminal_width() -> Option<usize> {
+    terminal_size::terminal_size().map(|(terminal_size::Width(width), _)| width as usize)
+}
+
+/// Returns an Vec over chunk_size grapheme clusters of string, starting at the beginning of the slice.
+///
+/// It's the same as [`chunks()`] but it uses extended grapheme clusters instead of chars,
+/// so emoji ZWJ sequences, flags and characters with combining marks are never split.
+///
+/// # Panics
+///
+/// Panics if chunk_size is 0.
+///
+/// # Examples
+///
+/// ```rust
+/// use owo_colors::{OwoColorize, colors::*};
+/// let colored_text = "👩‍👩‍👧🇺🇦".fg::<Red>().to_string();
+/// assert_eq!(
+///     ansi_cut::chunks_graphemes(&colored_text, 1),
+///     vec!["👩‍👩‍👧".fg::<Red>().to_string(), "🇺🇦".fg::<Red>().to_string()],
+/// );
+/// ```
+#[cfg(feature = "unicode-segmentation")]
+pub fn chunks_graphemes(s: &str, chunk_size: usize) -> Vec<String> {
+    use unicode_segmentation::UnicodeSegmentation;
+
+    assert!(chunk_size > 0);
+
+    let stripped = srip_ansi_sequences(s);
+    let graphemes = stripped.graphemes(true).collect::<Vec<_>>();
+
+    let mut iter = chunks_iter(s, chunk_size);
+    graphemes
+        .chunks(chunk_size)
+        .map(|chunk| {
+            let chars = chunk.iter().map(|g| g.chars().count()).sum();
+            iter.next_chunk(chars).text
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::{rendered, trim_trailing_escapes};
+    use crate::{is_closed, unclosed_styles, AnsiCut, Color};
+
+    #[cfg(feature = "unicode-width")]
+    #[test]
+    fn chunks_width_test() {
+        assert_eq!(vec!["som", "eth", "ing"], chunks_width("something", 3));
+        assert_eq!(Vec::<String>::new(), chunks_width("", 3));
+
+        let s = "你好世界";
+        assert_eq!(vec!["你", "好", "世", "界"], chunks_width(s, 3));
+        assert_eq!(vec!["你好", "世界"], chunks_width(s, 4));
+        assert_eq!(vec!["你", "好", "世", "界"], chunks_width(s, 1));
+        assert_eq!(
+            vec!["你 ", "好 ", "世 ", "界"],
+            chunks_width_with(s, 3, WideCharPolicy::Pad)
+        );
+        assert_eq!(
+            vec!["a你", "好b"],
+            chunks_width_with("a你好b", 3, WideCharPolicy::Pad)
+        );
+
+        let s = "e\u{301}e\u{301}e\u{301}";
+        assert_eq!(vec!["e\u{301}e\u{301}", "e\u{301}"], chunks_width(s, 2));
+
+        let s = "\u{1b}[31m你好\u{1b}[1mworld\u{1b}[0m";
+        assert_eq!(
+            vec![
+                "\u{1b}[31m你\u{1b}[39m ",
+                "\u{1b}[31m好\u{1b}[1mw\u{1b}[22;39m",
+                "\u{1b}[1;31morl\u{1b}[22;39m",
+                "\u{1b}[1;31md\u{1b}[0m",
+            ],
+            chunks_width_with(s, 3, WideCharPolicy::Pad)
+        );
+    }
+
+    #[cfg(feature = "unicode-segmentation")]
+    #[test]
+    fn chunks_graphemes_test() {
+        assert_eq!(vec!["som", "eth", "ing"], chunks_graphemes("something", 3));
+        assert_eq!(Vec::<String>::new(), chunks_graphemes("", 3));
+
+        let s = "👩‍👩‍👧e\u{301}🇺🇦🇵🇱";
+        assert_eq!(vec!["👩‍👩‍👧", "e\u{301}", "🇺🇦", "🇵🇱"], chunks_graphemes(s, 1));
+        assert_eq!(vec!["👩‍👩‍👧e\u{301}", "🇺🇦🇵🇱"], chunks_graphemes(s, 2));
+        assert_eq!(vec!["👩‍👩‍👧e\u{301}🇺🇦", "🇵🇱"], chunks_graphemes(s, 3));
+
+        let s = "\u{1b}[31m🇺🇦\u{1b}[1me\u{301}x\u{1b}[0m";
+        assert_eq!(
+            vec![
+                "\u{1b}[31m🇺🇦\u{1b}[1me\u{301}\u{1b}[22;39m",
+                "\u{1b}[1;31mx\u{1b}[0m",
+            ],
+            chunks_graphemes(s, 2)
+        );
+    }
+
+    #[cfg(feature = "unicode-segmentation")]
+    #[test]
+    #[should_panic]
+    fn chunks_graphemes_panic_when_n_is_zero() {
+        chunks_graphemes("something", 0);
+    }
+
+    #[test]
+    fn chunks_iter_test() {
+        let strings = [
+            "",
+            "something",
+            "\u{1b}[31;40mTEXT\u{1b}[0m",
+            "\u{1b}[31;40mTEXT\u{1b}[0m \u{1b}[34m😀😃\u{1b}[39m zxc",
+            "\u{1b}[31mTEXT",
+            "\u{1b}[1m\u{1b}[31mab\u{1b}[4m\u{1b}[44mcd\u{1b}[0m\u{1b}[32mef\u{1b}[39m\u{1b}[22m",
+            "a\u{1b}[31mb\u{1b}[1mc\u{1b}[39md\u{1b}[0m\u{1b}[2Je\u{1b}[0m",
+            "\u{1b}[31mab\u{1b}[99;99;99;99mcd\u{1b}[0m",
+        ];
+
+        for s in strings {
+            for n in 1..10 {
+                let iter = chunks_iter(s, n);
+                let len = iter.len();
+                let chunks = iter.collect::<Vec<_>>();
+                let expected = chunks_str_cut(s, n);
+                assert_eq!(len, chunks.len());
+                assert_eq!(expected.len(), chunks.len());
+
+                for (chunk, expected) in chunks.iter().zip(&expected) {
+                    assert_eq!(rendered(expected), rendered(chunk));
+                    assert_eq!(
+                        unclosed_styles(expected).to_string(),
+                        unclosed_styles(chunk).to_string()
+                    );
+                    assert!(
+                        trim_trailing_escapes(chunk).len() <= trim_trailing_escapes(expected).len()
+                    );
+                }
+            }
+        }
+
+        let mut iter = chunks_iter("something", 4);
+        assert_eq!((3, Some(3)), iter.size_hint());
+        assert_eq!(Some("some".to_string()), iter.next());
+        assert_eq!((2, Some(2)), iter.size_hint());
+        assert_eq!(Some("thin".to_string()), iter.next());
+        assert_eq!(Some("g".to_string()), iter.next());
+        assert_eq!((0, Some(0)), iter.size_hint());
+        assert_eq!(None, iter.next());
+        assert_eq!(None, iter.next());
+    }
+
+    #[test]
+    fn chunks_escapes_count_test() {
+        let colors = ["31", "32", "33", "34", "35", "36"];
+        let s = (0..300)
+            .map(|i| format!("\u{1b}[{}mword\u{1b}[0m ", colors[i % colors.len()]))
+            .collect::<String>();
+
+        let chunks = chunks(&s, 7);
+        let expected = chunks_str_cut(&s, 7);
+        assert_eq!(expected.len(), chunks.len());
+
+        for (chunk, expected) in chunks.iter().zip(&expected) {
+            assert_eq!(rendered(expected), rendered(chunk));
+
+            // an opening sequence, at most 2 words with their escapes and a closing one
+            let escapes = chunk.matches('\u{1b}').count();
+            assert!(escapes <= 6, "{:?}", chunk);
+        }
+
+        assert_eq!(
+            vec![
+                "\u{1b}[31mword\u{1b}[0m \u{1b}[32mwo\u{1b}[39m",
+                "\u{1b}[32mrd\u{1b}[0m \u{1b}[33mword\u{1b}[39m",
+            ],
+            chunks[..2]
+        );
+        assert_eq!("\u{1b}[36md\u{1b}[0m ", chunks[chunks.len() - 1]);
+    }
+
+    #[test]
+    #[should_panic]
+    fn chunks_iter_panic_when_n_is_zero() {
+        chunks_iter("something", 0);
+    }
+
+    // chunks made by cut one by one
+    fn chunks_str_cut(s: &str, n: usize) -> Vec<String> {
+        let stripped = srip_ansi_sequences(s);
+        let mut bounds = stripped.char_indices().map(|(i, _)| i).collect::<Vec<_>>();
+        let count = bounds.len();
+        bounds.push(stripped.len());
+
+        (0..count)
+            .step_by(n)
+            .map(|i| s.cut(bounds[i]..bounds[std::cmp::min(i + n, count)]))
+            .collect()
+    }
+
+    #[test]
+    fn chunks_escape_at_boundary_test() {
+        assert_eq!(
+            chunks("AB\u{1b}[31mCD", 2),
+            ["AB", "\u{1b}[31mCD\u{1b}[39m"]
+        );
+        assert_eq!(
+            chunks("\u{1b}[31mAB\u{1b}[0mCD", 2),
+            ["\u{1b}[31mAB\u{1b}[39m", "CD"]
+        );
+        assert_eq!(
+            chunks("\u{1b}[31mAB\u{1b}[2KCD\u{1b}[0m", 2),
+            ["\u{1b}[31mAB\u{1b}[39m", "\u{1b}[31m\u{1b}[2KCD\u{1b}[0m"]
+        );
+
+        let text = "ABCDEF";
+        for i in 0..=text.len() {
+            let s = format!("{}\u{1b}[31m{}", &text[..i], &text[i..]);
+            for n in 1..=text.len() {
+                let parts = chunks(&s, n);
+                assert_eq!(rendered(&s), rendered(&parts.concat()));
+
+                for (j, part) in parts.iter().enumerate() {
+                    let start = j * n;
+                    let end = start + srip_ansi_sequences(part).len();
+                    // a chunk has the escape only if it styles its chars or ends the string
+                    let has_escape = part.contains("\u{1b}[31m");
+                    assert_eq!(i < end || (i == end && end == text.len()), has_escape);
+                }
+
+                let parts = crate::chunks::Chunks::new(&s)
+                    .size(n)
+                    .minimal_prefix(false)
+                    .collect::<Vec<_>>();
+                assert_eq!(rendered(&s), rendered(&parts.concat()));
+
+                for (j, part) in parts.iter().enumerate() {
+                    let end = j * n + srip_ansi_sequences(part).len();
+                    let has_escape = part.contains("\u{1b}[31m");
+                    assert_eq!(i < end || (i == end && end == text.len()), has_escape);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn chunks_not_colored_test() {
+        assert_eq!(
+            vec!["som".to_string(), "eth".to_string(), "ing".to_string()],
+            chunks("something", 3)
+        );
+        assert_eq!(
+            vec![
+                "so".to_string(),
+                "me".to_string(),
+                "th".to_string(),
+                "in".to_string(),
+                "g".to_string()
+            ],
+            chunks("something", 2)
+        );
+        assert_eq!(
+            vec!["a".to_string(), "b".to_string(), "c".to_string()],
+            chunks("abc", 1)
+        );
+        assert_eq!(vec!["something".to_string()], chunks("something", 99));
+    }
+
+    #[test]
+    fn chunks_indexed_test() {
+        let s = "\u{1b}[31;40mTEXT\u{1b}[0m \u{1b}[34m😀😃\u{1b}[39m zxc";
+        let chunks = chunks_indexed(s, 3);
+        assert_eq!(
+            vec![
+                (0..3, 8..11, "\u{1b}[31;40mTEX\u{1b}[49;39m"),
+                (
+                    3..6,
+                    11..26,
+                    "\u{1b}[31;40mT\u{1b}[0m \u{1b}[34m😀\u{1b}[39m"
+                ),
+                (6..9, 26..37, "\u{1b}[34m😃\u{1b}[39m z"),
+                (9..11, 37..39, "xc"),
+            ],
+            chunks
+                .iter()
+                .map(|c| (
+                    c.visible_range.clone(),
+                    c.byte_range.clone(),
+                    c.text.as_str()
+                ))
+                .collect::<Vec<_>>()
+        );
+        assert_eq!("TEX", &s[chunks[0].byte_range.clone()]);
+        assert_eq!("😃\u{1b}[39m z", &s[chunks[2].byte_range.clone()]);
+
+        let s = "😀😃😄😁😆";
+        let chunks = chunks_indexed(s, 2);
+        assert_eq!(
+            vec![(0..2, 0..8), (2..4, 8..16), (4..5, 16..20)],
+            chunks
+                .iter()
+                .map(|c| (c.visible_range.clone(), c.byte_range.clone()))
+                .collect::<Vec<_>>()
+        );
+
+        for n in 1..10 {
+            let s = "\u{1b}[1m\u{1b}[31mab\u{1b}[4m\u{1b}[44mcd\u{1b}[0m\u{1b}[32mef\u{1b}[39m\u{1b}[22m";
+            let chunks = chunks_indexed(s, n);
+            assert_eq!(
+                chunks_iter(s, n).collect::<Vec<_>>(),
+                chunks.iter().map(|c| c.text.clone()).collect::<Vec<_>>()
+            );
+
+            for chunk in chunks {
+                assert_eq!(
+                    srip_ansi_sequences(&chunk.text),
+                    srip_ansi_sequences(&s[chunk.byte_range])
+                );
+            }
+        }
+
+        assert!(chunks_indexed("", 3).is_empty());
+    }
+
+    #[test]
+    fn rchunks_test() {
+        assert_eq!(vec!["ing", "eth", "som"], rchunks("something", 3));
+        assert_eq!(vec!["hing", "omet", "s"], rchunks("something", 4));
+        assert_eq!(vec!["something"], rchunks("something", 9));
+        assert_eq!(vec!["something"], rchunks("something", 100));
+        assert_eq!(Vec::<String>::new(), rchunks("", 3));
+        assert_eq!(vec!["😁😆", "😃😄", "😀"], rchunks("😀😃😄😁😆", 2));
+
+        let s = "\u{1b}[31;40mTEXT\u{1b}[0m \u{1b}[34m😀😃\u{1b}[39m zxc";
+        assert_eq!(
+            vec![
+                "zxc",
+                "\u{1b}[34m😀😃\u{1b}[39m ",
+                "\u{1b}[31;40mXT\u{1b}[0m ",
+                "\u{1b}[31;40mTE\u{1b}[49;39m",
+            ],
+            rchunks(s, 3)
+        );
+
+        let s =
+            "\u{1b}[1m\u{1b}[31mab\u{1b}[4m\u{1b}[44mcd\u{1b}[0m\u{1b}[32mef\u{1b}[39m\u{1b}[22m";
+        for n in 1..10 {
+            let chunks = rchunks(s, n);
+            for (i, chunk) in chunks.iter().enumerate() {
+                let count = srip_ansi_sequences(chunk).chars().count();
+                if i + 1 == chunks.len() {
+                    assert!(count <= n);
+                } else {
+                    assert_eq!(n, count);
+                }
+            }
+
+            let rendered_chunks = chunks
+                .iter()
+                .rev()
+                .flat_map(|c| rendered(c))
+                .collect::<Vec<_>>();
+            assert_eq!(rendered(s), rendered_chunks);
+        }
+    }
+
+    #[test]
+    #[should_panic]
+    fn rchunks_panic_when_n_is_zero() {
+        rchunks("something", 0);
+    }
+
+    #[test]
+    fn windows_test() {
+        assert_eq!(
+            vec!["som", "ome", "met", "eth", "thi", "hin", "ing"],
+            windows("something", 3).collect::<Vec<_>>()
+        );
+        assert_eq!(
+            vec!["something"],
+            windows("something", 9).collect::<Vec<_>>()
+        );
+        assert_eq!(0, windows("something", 10).count());
+        assert_eq!(0, windows("", 1).count());
+        assert_eq!(
+            vec!["😀😃", "😃😄", "😄😁"],
+            windows("😀😃😄😁", 2).collect::<Vec<_>>()
+        );
+
+        let s = "\u{1b}[31;40mTEXT\u{1b}[0m \u{1b}[34m😀😃\u{1b}[39m zxc";
+        assert_eq!(
+            vec![
+                "\u{1b}[31;40mTEX\u{1b}[49;39m",
+                "\u{1b}[31;40mEXT\u{1b}[0m",
+                "\u{1b}[31;40mXT\u{1b}[0m \u{1b}[34m\u{1b}[39m",
+                "\u{1b}[31;40mT\u{1b}[0m \u{1b}[34m😀\u{1b}[39m",
+            ],
+            windows(s, 3).take(4).collect::<Vec<_>>()
+        );
+
+        let s =
+            "\u{1b}[1m\u{1b}[31mab\u{1b}[4m\u{1b}[44mcd\u{1b}[0m\u{1b}[32mef\u{1b}[39m\u{1b}[22m";
+        for n in 1..10 {
+            let windows = windows(s, n);
+            let len = windows.len();
+            let windows = windows.collect::<Vec<_>>();
+            assert_eq!(len, windows.len());
+            assert_eq!(7usize.saturating_sub(n), len);
+
+            for (i, window) in windows.iter().enumerate() {
+                assert_eq!(rendered(&s.cut_chars(i..i + n)), rendered(window));
+            }
+        }
+    }
+
+    #[test]
+    #[should_panic]
+    fn windows_panic_when_n_is_zero() {
+        windows("something", 0);
+    }
+
+    #[test]
+    fn chunks_exact_test() {
+        assert_eq!(
+            vec!["some", "thin", "g..."],
+            chunks_exact("something", 4, '.')
+        );
+        assert_eq!(vec!["som", "eth", "ing"], chunks_exact("something", 3, '.'));
+        assert_eq!(Vec::<String>::new(), chunks_exact("", 3, '.'));
+        assert_eq!(vec!["😀😃", "😄 "], chunks_exact("😀😃😄", 2, ' '));
+
+        let s = "\u{1b}[31;40mTEXT\u{1b}[0m \u{1b}[34;43m😀😃\u{1b}[39m zxc";
+        for n in 1..15 {
+            for style in [PadStyle::Plain, PadStyle::Background] {
+                for chunk in chunks_exact_with(s, n, '-', style) {
+                    assert_eq!(n, srip_ansi_sequences(&chunk).chars().count());
+                }
+            }
+        }
+
+        assert_eq!("\u{1b}[43m zxc\u{1b}[49m---", chunks_exact(s, 7, '-')[1]);
+        assert_eq!(
+            "\u{1b}[43m zxc\u{1b}[49m\u{1b}[43m---\u{1b}[49m",
+            chunks_exact_with(s, 7, '-', PadStyle::Background)[1]
+        );
+        assert_eq!(
+            "\u{1b}[31;40mTEXT\u{1b}[0m\u{1b}[40m---\u{1b}[49m",
+            chunks_exact_with("\u{1b}[31;40mTEXT\u{1b}[0m", 7, '-', PadStyle::Background)[0]
+        );
+        assert_eq!(
+            "\u{1b}[31;40mTEXT\u{1b}[49;39m\u{1b}[40m---\u{1b}[49m",
+            chunks_exact_with("\u{1b}[31;40mTEXT", 7, '-', PadStyle::Background)[0]
+        );
+    }
+
+    #[cfg(feature = "terminal_size")]
+    #[test]
+    fn chunks_for_terminal_test() {
+        let s = "\u{1b}[31m你好世界 hello world\u{1b}[0m";
+        let cols = terminal_width().unwrap_or(7);
+        assert_eq!(chunks_width(s, cols), chunks_for_terminal(s, 7));
+    }
+
+    #[test]
+    fn chunks_bytes_budget_test() {
+        assert_eq!(
+            Ok(vec![
+                "som".to_string(),
+                "eth".to_string(),
+                "ing".to_string()
+            ]),
+            chunks_bytes_budget("something", 3)
+        );
+        assert_eq!(
+            Ok(vec!["😀".to_string(), "😃".to_string()]),
+            chunks_bytes_budget("😀😃", 7)
+        );
+        assert_eq!(
+            Err(AnsiCutError::BudgetTooSmall {
+                index: 0,
+                max_bytes: 3
+            }),
+            chunks_bytes_budget("😀😃", 3)
+        );
+        assert_eq!(Ok(Vec::new()), chunks_bytes_budget("", 0));
+        assert_eq!(
+            Err(AnsiCutError::BudgetTooSmall {
+                index: 0,
+                max_bytes: 0
+            }),
+            chunks_bytes_budget("a", 0)
+        );
+
+        let s = (0..50)
+            .map(|i| {
+                format!(
+                    "\u{1b}[{};{};{}m{}",
+                    1 + i % 4,
+                    31 + i % 7,
+                    41 + i % 5,
+                    i % 10
+                )
+            })
+            .collect::<String>()
+            + "\u{1b}[0m";
+        for max_bytes in [64, 80, 100, 2048] {
+            let chunks = chunks_bytes_budget(&s, max_bytes).unwrap();
+            assert!(chunks.iter().all(|chunk| chunk.len() <= max_bytes));
+            assert_eq!(
+                rendered(&s),
+                chunks.iter().flat_map(|c| rendered(c)).collect::<Vec<_>>()
+            );
+        }
+
+        assert_eq!(1, chunks_bytes_budget(&s, 2048).unwrap().len());
+        assert_eq!(
+            Err(AnsiCutError::BudgetTooSmall {
+                index: 2,
+                max_bytes: 30
+            }),
+            chunks_bytes_budget(&s, 30)
+        );
+        assert_eq!(
+            Err(AnsiCutError::BudgetTooSmall {
+                index: 2,
+                max_bytes: 10
+            }),
+            chunks_bytes_budget("ab\u{1b}[31mcd", 10)
+        );
+    }
+
+    #[test]
+    fn chunks_words_test() {
+        let s = "It's an important message";
+        assert_eq!(vec!["It's an", "important", "message"], chunks_words(s, 9));
+        assert_eq!(
+            vec!["It's an", "importan", "t", "message"],
+            chunks_words(s, 8)
+        );
+        assert_eq!(
+            vec!["It's", "an", "impor", "tant", "messa", "ge"],
+            chunks_words(s, 5)
+        );
+        assert_eq!(vec!["It's an important", "message"], chunks_words(s, 17));
+        assert_eq!(vec![s], chunks_words(s, 100));
+        assert_eq!(vec!["ab", "cd"], chunks_words("ab   cd", 4));
+        assert_eq!(vec!["ab", "cd"], chunks_words("ab   cd   ", 4));
+        assert_eq!(vec!["  ab", "cd"], chunks_words("  ab cd", 4));
+        assert_eq!(Vec::<String>::new(), chunks_words("", 4));
+
+        let s = "\u{1b}[31mIt's \u{1b}[1man\u{1b}[22m\u{1b}[44m important\u{1b}[0m message";
+        assert_eq!(
+            vec![
+                "\u{1b}[31mIt's \u{1b}[1man\u{1b}[22;39m",
+                "\u{1b}[31;44mimportant\u{1b}[49;39m",
+                "message",
+            ],
+            chunks_words(s, 9)
+        );
+        assert_eq!(
+            vec![
+                "\u{1b}[31mIt's\u{1b}[39m",
+                "\u{1b}[1;31man\u{1b}[22;39m",
+                "\u{1b}[31;44mimpo\u{1b}[49;39m",
+                "\u{1b}[31;44mrtan\u{1b}[49;39m",
+                "\u{1b}[31;44mt\u{1b}[49;39m",
+                "mess",
+                "age",
+            ],
+            chunks_words(s, 4)
+        );
+    }
+
+    #[test]
+    fn chunks_closed_test() {
+        let s = "\u{1b}[31mTEXT";
+        assert_eq!(
+            vec!["\u{1b}[31mTE\u{1b}[39m", "\u{1b}[31mXT\u{1b}[39m"],
+            chunks(s, 2)
+        );
+
+        let s = "\u{1b}[31;40mTE \u{1b}[0m\u{1b}[1mXT \u{1b}[4;34m😀😃 zxc";
+        for n in 1..10 {
+            let mut all = Vec::new();
+            all.extend(chunks(s, n));
+            all.extend(chunks_exact(s, n, ' '));
+            all.extend(chunks_words(s, n));
+            all.extend(rchunks(s, n));
+            all.extend(windows(s, n));
+            all.extend(chunks_indexed(s, n).into_iter().map(|c| c.text));
+            all.extend(crate::chunks::Chunks::new(s).size(n).minimal_prefix(false));
+            #[cfg(feature = "unicode-width")]
+            all.extend(chunks_width(s, n));
+            #[cfg(feature = "unicode-segmentation")]
+            all.extend(chunks_graphemes(s, n));
+
+            for chunk in all {
+                assert!(is_closed(&unclosed_styles(&chunk).state), "{:?}", chunk);
+            }
+        }
+    }
+
+    #[test]
+    fn chunks_with_style_test() {
+        let s = "\u{1b}[31;40mTEXT\u{1b}[0m \u{1b}[1;4;34m😀😃\u{1b}[39m zxc";
+        let chunks = chunks_with_style(s, 3);
+        assert_eq!(
+            chunks.iter().map(|(c, _)| c.clone()).collect::<Vec<_>>(),
+            super::chunks(s, 3)
+        );
+        assert_eq!(
+            vec![
+                "\u{1b}[31;40m",
+                "\u{1b}[1;4;34m",
+                "\u{1b}[1;4m",
+                "\u{1b}[1;4m"
+            ],
+            chunks
+                .iter()
+                .map(|(_, style)| style.to_string())
+                .collect::<Vec<_>>()
+        );
+
+        let style = &chunks[0].1;
+        assert_eq!(Some(Color::Basic(1)), style.fg());
+        assert_eq!(Some(Color::Basic(0)), style.bg());
+        assert!(!style.is_bold() && !style.is_empty());
+
+        let style = &chunks[1].1;
+        assert_eq!(Some(Color::Basic(4)), style.fg());
+        assert_eq!(None, style.bg());
+        assert!(style.is_bold() && style.is_underline() && !style.is_italic());
+
+        assert!(!chunks[3].1.is_empty());
+        assert!(chunks_with_style("\u{1b}[31mTEXT\u{1b}[0m", 3)[1]
+            .1
+            .is_empty());
+        assert!(chunks_with_style("TEXT", 3)[1].1.is_empty());
+    }
+
+    #[test]
+    fn chunks_into_test() {
+        let s = "\u{1b}[31;40mTEXT\u{1b}[0m \u{1b}[34m😀😃\u{1b}[39m zxc";
+
+        let mut chunks = vec!["garbage".to_string(); 20];
+        for n in [1, 3, 10, 2, 100, 1] {
+            chunks_into(s, n, &mut chunks);
+            assert_eq!(super::chunks(s, n), chunks);
+        }
+
+        chunks_into("", 3, &mut chunks);
+        assert!(chunks.is_empty());
+
+        let mut chunks = Vec::new();
+        chunks_into(s, 1, &mut chunks);
+        let capacity = chunks[0].capacity();
+        let ptr = chunks[0].as_ptr();
+        chunks_into(s, 1, &mut chunks);
+        assert_eq!(capacity, chunks[0].capacity());
+        assert_eq!(ptr, chunks[0].as_ptr());
+
+        let mut iter = chunks_iter(s, 4);
+        let mut buf = String::from(">");
+        assert!(iter.next_into(&mut buf));
+        assert_eq!(">\u{1b}[31;40mTEXT\u{1b}[49;39m", buf);
+        assert_eq!(Some(" \u{1b}[34m😀😃\u{1b}[39m ".to_string()), iter.next());
+        buf.clear();
+        assert!(iter.next_into(&mut buf));
+        assert_eq!("zxc", buf);
+        assert!(!iter.next_into(&mut buf));
+    }
+
+    #[test]
+    fn chunk_by_test() {
+        assert_eq!(
+            vec!["abc", "123", "de", "4"],
+            chunk_by("abc123de4", |c| c.is_ascii_digit())
+        );
+        assert_eq!(
+            vec!["It's", " ", "an", "  ", "important"],
+            chunk_by("It's an  important", char::is_whitespace)
+        );
+        assert_eq!(vec!["abc"], chunk_by("abc", |_| ()));
+        assert_eq!(Vec::<String>::new(), chunk_by("", |_| ()));
+        assert_eq!(
+            vec!["aa", "BB", "c", "D"],
+            chunk_by("aaBBcD", |c| c.is_uppercase())
+        );
+
+        let s = "\u{1b}[31mab\u{1b}[1m12\u{1b}[0m\u{1b}[34mc3\u{1b}[39m";
+        let runs = chunk_by(s, |c| c.is_ascii_digit());
+        assert_eq!(
+            vec![
+                "\u{1b}[31mab\u{1b}[39m",
+                "\u{1b}[1;31m12\u{1b}[22;39m",
+                "\u{1b}[34mc\u{1b}[39m",
+                "\u{1b}[34m3\u{1b}[39m",
+            ],
+            runs
+        );
+        assert_eq!(
+            rendered(s),
+            runs.iter().flat_map(|r| rendered(r)).collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn try_chunks_test() {
+        assert_eq!(Err(AnsiCutError::ZeroChunkSize), try_chunks("something", 0));
+        assert_eq!(Err(AnsiCutError::ZeroChunkSize), try_chunks("", 0));
+        assert_eq!(Ok(Vec::new()), try_chunks("", 1));
+
+        let s = "\u{1b}[31;40mTEXT\u{1b}[0m \u{1b}[34m😀😃\u{1b}[39m zxc";
+        for n in 1..10 {
+            assert_eq!(Ok(chunks(s, n)), try_chunks(s, n));
+        }
+    }
+
+    #[test]
+    #[should_panic]
+    fn chunks_panic_when_n_is_zero() {
+        chunks("something", 0);
+    }
+
+    #[test]
+    fn chunks_colored() {
+        let text = "\u{1b}[31;40mTEXT\u{1b}[0m";
+        assert_eq!(
+            vec![
+                "\u{1b}[31;40mT\u{1b}[49;39m",
+                "\u{1b}[31;40mE\u{1b}[49;39m",
+                "\u{1b}[31;40mX\u{1b}[49;39m",
+                "\u{1b}[31;40mT\u{1b}[0m"
+            ],
+            chunks(text, 1)
+        );
+        assert_eq!(
+            vec!["\u{1b}[31;40mTE\u{1b}[49;39m", "\u{1b}[31;40mXT\u{1b}[0m"],
+            chunks(text, 2)
+        );
+        assert_eq!(
+            vec!["\u{1b}[31;40mTEX\u{1b}[49;39m", "\u{1b}[31;40mT\u{1b}[0m"],
+            chunks(text, 3)
+        );
+    }
+
+    #[test]
+    fn chunk_emojies_test() {
+        let emojes = "😀😃😄😁😆😅😂🤣🥲😊";
+        assert_eq!(
+            vec!["😀", "😃", "😄", "😁", "😆", "😅", "😂", "🤣", "🥲", "😊",],
+            chunks(emojes, 1)
+        );
+        assert_eq!(
+            vec!["😀😃", "😄😁", "😆😅", "😂🤣", "🥲😊",],
+            chunks(emojes, 2)
+        );
+        assert_eq!(vec!["😀😃😄", "😁😆😅", "😂🤣🥲", "😊",], chunks(emojes, 3));
+        assert_eq!(vec!["😀😃😄😁", "😆😅😂🤣", "🥲😊",], chunks(emojes, 4));
+        assert_eq!(vec!["😀😃😄😁😆", "😅😂🤣🥲😊",], chunks(emojes, 5));
+        assert_eq!(vec!["😀😃😄😁😆😅", "😂🤣🥲😊",], chunks(emojes, 6));
+        assert_eq!(vec!["😀😃😄😁😆😅😂", "🤣🥲😊",], chunks(emojes, 7));
+        assert_eq!(vec!["😀😃😄😁😆😅😂🤣", "🥲😊",], chunks(emojes, 8));
+        assert_eq!(vec!["😀😃😄😁😆😅😂🤣🥲", "😊",], chunks(emojes, 9));
+        assert_eq!(vec!["😀😃😄😁😆😅😂🤣🥲😊"], chunks(emojes, 10));
+        assert_eq!(vec!["😀😃😄😁😆😅😂🤣🥲😊"], chunks(emojes, 11));
+    }
+}
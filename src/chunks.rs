@@ -0,0 +1,388 @@
+//! A builder of chunks of a string.
+//!
+//! [`crate::chunks()`] covers the simple case,
+//! [`Chunks`] can also chunk by display columns or grapheme clusters, pad chunks
+//! and replay all escape sequences at the beginning of each chunk.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use ansi_cut::chunks::{Chunks, Unit};
+//! use owo_colors::{OwoColorize, colors::*};
+//!
+//! let colored_text = "When the night".fg::<Red>().to_string();
+//! let chunks = Chunks::new(&colored_text)
+//!     .size(5)
+//!     .unit(Unit::Chars)
+//!     .pad('.')
+//!     .minimal_prefix(true)
+//!     .collect::<Vec<_>>();
+//! assert_eq!(
+//!     chunks,
+//!     vec![
+//!         "When ".fg::<Red>().to_string(),
+//!         "the n".fg::<Red>().to_string(),
+//!         format!("{}.", "ight".fg::<Red>()),
+//!     ],
+//! );
+//! ```
+
+use crate::{chunks_iter, AnsiChunks, ControlPolicy};
+
+/// Unit defines what a size of a chunk is measured in.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Unit {
+    /// Chars.
+    #[default]
+    Chars,
+    /// Display columns.
+    ///
+    /// A wide character which doesn't fit in the rest of a chunk goes to the next one,
+    /// zero width characters are kept together with a character they follow.
+    ///
+    /// ```rust
+    /// use ansi_cut::chunks::{Chunks, Unit};
+    /// let chunks = Chunks::new("你好ab").size(3).unit(Unit::Width).collect::<Vec<_>>();
+    /// assert_eq!(chunks, vec!["你", "好a", "b"]);
+    /// ```
+    #[cfg(feature = "unicode-width")]
+    Width,
+    /// Extended grapheme clusters.
+    ///
+    /// ```rust
+    /// use ansi_cut::chunks::{Chunks, Unit};
+    /// let chunks = Chunks::new("🇺🇦e\u{301}x").size(2).unit(Unit::Graphemes).collect::<Vec<_>>();
+    /// assert_eq!(chunks, vec!["🇺🇦e\u{301}", "x"]);
+    /// ```
+    #[cfg(feature = "unicode-segmentation")]
+    Graphemes,
+}
+
+/// Chunks is a lazy iterator over chunks of a string,
+/// which is configured by its builder methods.
+///
+/// The options must be set before the iteration starts.
+#[derive(Debug, Clone)]
+pub struct Chunks<'a> {
+    chunks: AnsiChunks<'a>,
+    size: usize,
+    unit: Unit,
+    pad: Option<char>,
+    // the string not containing ANSI sequences,
+    // it's made on the first chunk if the unit is not chars
+    #[cfg(any(feature = "unicode-width", feature = "unicode-segmentation"))]
+    stripped: Option<String>,
+    // a byte index of the next chunk in stripped
+    #[cfg(any(feature = "unicode-width", feature = "unicode-segmentation"))]
+    cursor: usize,
+}
+
+impl<'a> Chunks<'a> {
+    /// Creates chunks of a string,
+    /// by default each chunk is 1 char, not padded and has a minimal prefix.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use ansi_cut::chunks::Chunks;
+    /// let chunks = Chunks::new("abc").collect::<Vec<_>>();
+    /// assert_eq!(chunks, vec!["a", "b", "c"]);
+    /// ```
+    pub fn new(s: &'a str) -> Self {
+        Self {
+            chunks: chunks_iter(s, 1),
+            size: 1,
+            unit: Unit::default(),
+            pad: None,
+            #[cfg(any(feature = "unicode-width", feature = "unicode-segmentation"))]
+            stripped: None,
+            #[cfg(any(feature = "unicode-width", feature = "unicode-segmentation"))]
+            cursor: 0,
+        }
+    }
+
+    /// Sets a size of a chunk in units.
+    ///
+    /// # Panics
+    ///
+    /// Panics if size is 0.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use ansi_cut::chunks::Chunks;
+    /// let chunks = Chunks::new("something").size(4).collect::<Vec<_>>();
+    /// assert_eq!(chunks, vec!["some", "thin", "g"]);
+    /// ```
+    pub fn size(mut self, size: usize) -> Self {
+        assert!(size > 0);
+        self.size = size;
+        self
+    }
+
+    /// Sets a unit a size of a chunk is measured in.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use ansi_cut::chunks::{Chunks, Unit};
+    /// let chunks = Chunks::new("something").size(4).unit(Unit::Chars).collect::<Vec<_>>();
+    /// assert_eq!(chunks, vec!["some", "thin", "g"]);
+    /// ```
+    pub fn unit(mut self, unit: Unit) -> Self {
+        self.unit = unit;
+        self
+    }
+
+    /// Sets a char which pads short chunks up to the size.
+    ///
+    /// The padding goes after the closing sequences of a chunk so it's not colored.
+    /// In case of `Unit::Width` the char is expected to be 1 column wide.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use ansi_cut::chunks::Chunks;
+    /// let chunks = Chunks::new("something").size(4).pad('-').collect::<Vec<_>>();
+    /// assert_eq!(chunks, vec!["some", "thin", "g---"]);
+    /// ```
+    pub fn pad(mut self, pad: char) -> Self {
+        self.pad = Some(pad);
+        self
+    }
+
+    /// Sets whether a chunk starts with only a style active at its beginning
+    /// or with escape sequences met before it.
+    ///
+    /// Sequences which make no difference to the chunk, like ones overridden later, are dropped.
+    ///
+    /// It's true by default.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use ansi_cut::chunks::Chunks;
+    /// let text = "\u{1b}[31mab\u{1b}[1mcd\u{1b}[0m";
+    ///
+    /// let chunks = Chunks::new(text).size(3).collect::<Vec<_>>();
+    /// assert_eq!(chunks[1], "\u{1b}[1;31md\u{1b}[0m");
+    ///
+    /// let chunks = Chunks::new(text).size(3).minimal_prefix(false).collect::<Vec<_>>();
+    /// assert_eq!(chunks[1], "\u{1b}[31m\u{1b}[1md\u{1b}[0m");
+    /// ```
+    pub fn minimal_prefix(mut self, minimal: bool) -> Self {
+        self.chunks.escapes = if minimal { None } else { Some(String::new()) };
+        self
+    }
+
+    /// Sets what's done with control sequences like cursor movements, see [`ControlPolicy`].
+    ///
+    /// It's [`ControlPolicy::Preserve`] by default.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use ansi_cut::{chunks::Chunks, ControlPolicy};
+    /// let text = "ab\u{1b}[2Ccd";
+    /// let chunks = Chunks::new(text).size(3).control(ControlPolicy::Normalize).collect::<Vec<_>>();
+    /// assert_eq!(chunks, vec!["ab  c", "d"]);
+    /// ```
+    pub fn control(mut self, policy: ControlPolicy) -> Self {
+        self.chunks.control = policy;
+        self
+    }
+
+    // Returns a number of chars and units in the next chunk.
+    fn next_chunk_len(&mut self) -> (usize, usize) {
+        match self.unit {
+            Unit::Chars => {
+                let chars = std::cmp::min(self.size, self.chunks.chars_left);
+                (chars, chars)
+            }
+            #[cfg(feature = "unicode-width")]
+            Unit::Width => {
+                let size = self.size;
+                let (mut bytes, mut chars, mut width) = (0, 0, 0);
+                for c in self.rest().chars() {
+                    let w = crate::char_width(c);
+                    // a character which is wider than a chunk is left alone in its chunk
+                    if width + w > size && width > 0 {
+                        break;
+                    }
+
+                    bytes += c.len_utf8();
+                    chars += 1;
+                    width += w;
+                }
+
+                self.cursor += bytes;
+                (chars, width)
+            }
+            #[cfg(feature = "unicode-segmentation")]
+            Unit::Graphemes => {
+                use unicode_segmentation::UnicodeSegmentation;
+
+                let size = self.size;
+                let (mut bytes, mut chars, mut graphemes) = (0, 0, 0);
+                for grapheme in self.rest().graphemes(true).take(size) {
+                    bytes += grapheme.len();
+                    chars += grapheme.chars().count();
+                    graphemes += 1;
+                }
+
+                self.cursor += bytes;
+                (chars, graphemes)
+            }
+        }
+    }
+
+    // Returns a not chunked part of the string not containing ANSI sequences.
+    #[cfg(any(feature = "unicode-width", feature = "unicode-segmentation"))]
+    fn rest(&mut self) -> &str {
+        let text = self.chunks.text;
+        let stripped = self
+            .stripped
+            .get_or_insert_with(|| crate::srip_ansi_sequences(text));
+
+        &stripped[self.cursor..]
+    }
+}
+
+impl Iterator for Chunks<'_> {
+    type Item = String;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.chunks.chars_left == 0 {
+            return None;
+        }
+
+        let (chars, units) = self.next_chunk_len();
+        let mut chunk = self.chunks.next_chunk(chars).text;
+
+        if let Some(pad) = self.pad {
+            for _ in units..self.size {
+                chunk.push(pad);
+            }
+        }
+
+        Some(chunk)
+    }
+}
+
+impl std::iter::FusedIterator for Chunks<'_> {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{tests::trim_trailing_escapes, AnsiCut};
+
+    const STRINGS: [&str; 4] = [
+        "",
+        "something",
+        "\u{1b}[31;40mTEXT\u{1b}[0m \u{1b}[34m😀😃\u{1b}[39m zxc",
+        "\u{1b}[1m\u{1b}[31m你b\u{1b}[4m\u{1b}[44mcd\u{1b}[0m\u{1b}[32m好f\u{1b}[39m\u{1b}[22m",
+    ];
+
+    #[test]
+    fn chunks_test() {
+        for s in STRINGS {
+            for n in 1..10 {
+                let chunks = Chunks::new(s).size(n).collect::<Vec<_>>();
+                assert_eq!(crate::chunks(s, n), chunks);
+
+                let chunks = Chunks::new(s).size(n).pad('.').collect::<Vec<_>>();
+                assert_eq!(crate::chunks_exact(s, n, '.'), chunks);
+            }
+        }
+    }
+
+    #[test]
+    fn chunks_have_visible_text_test() {
+        for s in STRINGS {
+            for n in 1..10 {
+                for minimal in [true, false] {
+                    let chunks = Chunks::new(s).size(n).minimal_prefix(minimal);
+                    for chunk in chunks {
+                        assert!(!crate::srip_ansi_sequences(&chunk).is_empty());
+                    }
+                }
+            }
+        }
+
+        assert!(crate::chunks("\u{1b}[31m\u{1b}[39m", 1).is_empty());
+    }
+
+    #[test]
+    fn chunks_not_minimal_prefix_test() {
+        for s in STRINGS {
+            let chars = crate::srip_ansi_sequences(s).chars().count();
+            for n in 1..10 {
+                let chunks = Chunks::new(s)
+                    .size(n)
+                    .minimal_prefix(false)
+                    .collect::<Vec<_>>();
+                let expected = (0..chars)
+                    .step_by(n)
+                    .map(|i| s.cut_chars(i..std::cmp::min(i + n, chars)))
+                    .collect::<Vec<_>>();
+                assert_eq!(expected.len(), chunks.len());
+
+                // escapes at the end of a cut go to the next chunk
+                for (chunk, expected) in chunks.iter().zip(&expected) {
+                    assert_eq!(
+                        trim_trailing_escapes(expected),
+                        trim_trailing_escapes(chunk)
+                    );
+                }
+            }
+        }
+    }
+
+    #[cfg(feature = "unicode-width")]
+    #[test]
+    fn chunks_width_test() {
+        for s in STRINGS {
+            for n in 1..10 {
+                let chunks = Chunks::new(s).size(n).unit(Unit::Width).collect::<Vec<_>>();
+                assert_eq!(crate::chunks_width(s, n), chunks);
+            }
+        }
+
+        let chunks = Chunks::new("你好世界")
+            .size(3)
+            .unit(Unit::Width)
+            .pad(' ')
+            .collect::<Vec<_>>();
+        assert_eq!(vec!["你 ", "好 ", "世 ", "界 "], chunks);
+    }
+
+    #[cfg(feature = "unicode-segmentation")]
+    #[test]
+    fn chunks_graphemes_test() {
+        for s in STRINGS.iter().chain(&["👩‍👩‍👧e\u{301}🇺🇦🇵🇱"]) {
+            for n in 1..10 {
+                let chunks = Chunks::new(s)
+                    .size(n)
+                    .unit(Unit::Graphemes)
+                    .collect::<Vec<_>>();
+                assert_eq!(crate::chunks_graphemes(s, n), chunks);
+            }
+        }
+    }
+
+    #[test]
+    fn chunks_lazy_test() {
+        let s = "\u{1b}[31;40mTEXT\u{1b}[0m \u{1b}[34m😀😃\u{1b}[39m zxc";
+        let chunks = Chunks::new(s).size(2).skip(2).take(2).collect::<Vec<_>>();
+        assert_eq!(
+            vec![" \u{1b}[34m😀\u{1b}[39m", "\u{1b}[34m😃\u{1b}[39m "],
+            chunks
+        );
+    }
+
+    #[test]
+    #[should_panic]
+    fn chunks_panic_when_size_is_zero() {
+        let _ = Chunks::new("something").size(0);
+    }
+}
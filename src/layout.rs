@@ -0,0 +1,1943 @@
+//! Laying a string out in a given width: wrapping, justifying, truncating, aligning and padding it.
+
+use crate::{
+    background_end, char_width, chunks_width, complete_ansi_sequences, cut_open, cut_str,
+    cut_width, cut_with, fill_line, is_closed, lines, lines_with_endings, open_ansi_sequences,
+    push_spaces, split_first_token, srip_ansi_sequences, styled_lines, update_ansi_state_by_escape,
+    width_to_end_byte, width_to_start_byte, AnsiIndexedStr, AnsiState, CutOptions, FillMode,
+    InsertStyle, LineEnding, PadStyle, ResetPolicy, Token, WideCharPolicy,
+};
+use std::ops::Range;
+
+/// FitOptions is a set of options which define how a string is fitted into a cell by [`fit`].
+///
+/// # Examples
+///
+/// ```rust
+/// use ansi_cut::{FitOptions, VerticalAlignment};
+/// let options = FitOptions::default().ellipsis("…").vertical(VerticalAlignment::Middle);
+/// assert_eq!(ansi_cut::fit("When the night", 6, 3, options), "      \nWhen …\n      ");
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FitOptions<'a> {
+    ellipsis: &'a str,
+    fill: char,
+    vertical: VerticalAlignment,
+    reset: ResetPolicy,
+}
+
+impl Default for FitOptions<'_> {
+    fn default() -> Self {
+        Self {
+            ellipsis: "",
+            fill: ' ',
+            vertical: VerticalAlignment::default(),
+            reset: ResetPolicy::default(),
+        }
+    }
+}
+
+impl<'a> FitOptions<'a> {
+    /// Sets a string which ends a truncated line, by default there's none.
+    pub fn ellipsis(mut self, ellipsis: &'a str) -> Self {
+        self.ellipsis = ellipsis;
+        self
+    }
+
+    /// Sets a character which pads short lines and fills missing ones, by default it's a space.
+    pub fn fill(mut self, fill: char) -> Self {
+        self.fill = fill;
+        self
+    }
+
+    /// Sets which lines are kept or where missing ones are added.
+    pub fn vertical(mut self, alignment: VerticalAlignment) -> Self {
+        self.vertical = alignment;
+        self
+    }
+
+    /// Sets how styles which continue past the end of a line are closed.
+    ///
+    /// With [`ResetPolicy::None`] they are left open, so the padding of the line gets them.
+    pub fn reset(mut self, policy: ResetPolicy) -> Self {
+        self.reset = policy;
+        self
+    }
+}
+
+/// VerticalAlignment defines where lines are placed within a height,
+/// see [`FitOptions`] and [`concat_horizontal_with`].
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum VerticalAlignment {
+    /// The first lines are kept and missing ones are added at the bottom.
+    #[default]
+    Top,
+    /// The middle lines are kept and missing ones are added on both sides,
+    /// if it can't be done equally the extra line goes to the bottom.
+    Middle,
+    /// The last lines are kept and missing ones are added at the top.
+    Bottom,
+}
+
+/// Alignment defines where a string is placed within a width, see [`align`].
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Alignment {
+    /// The string is placed at the beginning and the padding goes after it.
+    #[default]
+    Left,
+    /// The string is placed in the middle,
+    /// if the padding can't be split equally its extra column goes on the right.
+    Center,
+    /// The string is placed at the end and the padding goes before it.
+    Right,
+}
+
+/// SuffixStyle defines how a suffix of a truncated string is styled.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum SuffixStyle {
+    /// The styles are closed before the suffix.
+    #[default]
+    Plain,
+    /// The suffix gets a style which is active at the place the string is cut.
+    Inherit,
+}
+
+/// Splits a multi-line string into pages of `height` lines which are at most `width` display columns wide.
+///
+/// Each line of the string is wrapped like in [`chunks_width`],
+/// then the wrapped lines are grouped into pages, lines of a page are separated by `\n`.
+///
+/// Every page can be rendered on its own,
+/// styles which continue from a previous page are reopened at its top.
+///
+/// # Panics
+///
+/// Panics if width or height is 0.
+///
+/// # Examples
+///
+/// ```rust
+/// use owo_colors::{OwoColorize, colors::*};
+/// let colored_text = format!("{}\n{}", "When the".fg::<Red>(), "night".fg::<Blue>());
+/// assert_eq!(
+///     ansi_cut::pages(&colored_text, 5, 2),
+///     vec![
+///         format!("{}\n{}", "When ".fg::<Red>(), "the".fg::<Red>()),
+///         "night".fg::<Blue>().to_string(),
+///     ],
+/// );
+/// ```
+pub fn pages(s: &str, width: usize, height: usize) -> Vec<String> {
+    assert!(width > 0);
+    assert!(height > 0);
+
+    let mut lines = Vec::new();
+    for (line, _) in styled_lines(s) {
+        let chunks = chunks_width(&line, width);
+        if chunks.is_empty() {
+            lines.push(String::new());
+        } else {
+            lines.extend(chunks);
+        }
+    }
+
+    lines.chunks(height).map(|page| page.join("\n")).collect()
+}
+
+/// Wraps a string into lines which are at most `width` display columns wide.
+/// Preserving its colors.
+///
+/// Lines are broken at whitespace, which is dropped at the breaks,
+/// a word which is wider than `width` is broken into pieces.
+/// A character which is wider than `width` itself can't be broken,
+/// so it's left alone on its line, which is wider than `width` then.
+/// E.g. `wrap("你好", 1)` returns `["你", "好"]`, each 2 columns wide.
+///
+/// Lines of the string are wrapped separately, see [`lines`].
+/// Each line starts with a style which is active at its beginning and is closed at its end,
+/// so it can be printed on its own.
+///
+/// # Panics
+///
+/// Panics if width is 0.
+///
+/// # Examples
+///
+/// ```rust
+/// use owo_colors::{OwoColorize, colors::*};
+/// let colored_text = "When the night has come".fg::<Red>().to_string();
+/// assert_eq!(
+///     ansi_cut::wrap(&colored_text, 10),
+///     vec![
+///         "When the".fg::<Red>().to_string(),
+///         "night has".fg::<Red>().to_string(),
+///         "come".fg::<Red>().to_string(),
+///     ],
+/// );
+/// ```
+pub fn wrap(s: &str, width: usize) -> Vec<String> {
+    assert!(width > 0);
+
+    let mut wrapped = Vec::new();
+    for line in lines(s) {
+        wrap_line(&line, width, &mut wrapped);
+    }
+
+    wrapped
+}
+
+/// The same as [`wrap`] but a background color which is active at the end of a wrapped line
+/// can be extended to the end of it, so a highlighted text looks like a solid block.
+///
+/// Each line which has a background color active at its last character
+/// is filled like in [`fill_line`] with a given mode, other lines are left as they are.
+///
+/// # Panics
+///
+/// Panics if width is 0.
+///
+/// # Examples
+///
+/// ```rust
+/// use owo_colors::{OwoColorize, colors::*};
+/// use ansi_cut::FillMode;
+/// let colored_text = "When the night has come".bg::<Blue>().to_string();
+/// assert_eq!(
+///     ansi_cut::wrap_with(&colored_text, 10, Some(FillMode::PadSpaces(10))),
+///     vec![
+///         format!("{}{}", "When the".bg::<Blue>(), "  ".bg::<Blue>()),
+///         format!("{}{}", "night has".bg::<Blue>(), " ".bg::<Blue>()),
+///         format!("{}{}", "come".bg::<Blue>(), "      ".bg::<Blue>()),
+///     ],
+/// );
+/// ```
+pub fn wrap_with(s: &str, width: usize, fill: Option<FillMode>) -> Vec<String> {
+    let mut wrapped = wrap(s, width);
+    if let Some(mode) = fill {
+        for line in &mut wrapped {
+            if background_end(line).is_some() {
+                *line = fill_line(line, mode);
+            }
+        }
+    }
+
+    wrapped
+}
+
+/// Wraps a string like in [`wrap`] and justifies the lines to exactly `width` display columns.
+/// Preserving its colors.
+///
+/// Extra spaces are distributed between the words of a line, the leftmost gaps get more of them.
+/// The last line of a paragraph and a line with a single word are left as they are.
+///
+/// The inserted spaces get a style of a gap they are inserted into only if it has a background color,
+/// see [`justify_with`] to never style them.
+///
+/// # Panics
+///
+/// Panics if width is 0.
+///
+/// # Examples
+///
+/// ```rust
+/// use owo_colors::{OwoColorize, colors::*};
+/// let colored_text = "When the night has come".fg::<Red>().to_string();
+/// assert_eq!(
+///     ansi_cut::justify(&colored_text, 10),
+///     vec![
+///         "\u{1b}[31mWhen \u{1b}[39m  \u{1b}[31mthe\u{1b}[39m",
+///         "\u{1b}[31mnight \u{1b}[39m \u{1b}[31mhas\u{1b}[39m",
+///         "\u{1b}[31mcome\u{1b}[39m",
+///     ],
+/// );
+/// ```
+pub fn justify(s: &str, width: usize) -> Vec<String> {
+    justify_with(s, width, PadStyle::Background)
+}
+
+/// The same as [`justify`] but with a control of how the inserted spaces are styled.
+///
+/// With [`PadStyle::Plain`] they are never styled,
+/// with [`PadStyle::Background`] they get a style of a gap if it has a background color.
+///
+/// # Examples
+///
+/// ```rust
+/// use owo_colors::{OwoColorize, colors::*};
+/// use ansi_cut::PadStyle;
+/// let colored_text = "When the night".bg::<Red>().to_string();
+/// assert_eq!(
+///     ansi_cut::justify_with(&colored_text, 9, PadStyle::Background)[0],
+///     "When  the".bg::<Red>().to_string(),
+/// );
+/// assert_eq!(
+///     ansi_cut::justify_with(&colored_text, 9, PadStyle::Plain)[0],
+///     "\u{1b}[41mWhen \u{1b}[49m \u{1b}[41mthe\u{1b}[49m",
+/// );
+/// ```
+pub fn justify_with(s: &str, width: usize, style: PadStyle) -> Vec<String> {
+    assert!(width > 0);
+
+    let mut justified = Vec::new();
+    for line in lines(s) {
+        let mut wrapped = Vec::new();
+        wrap_line(&line, width, &mut wrapped);
+
+        let last = wrapped.pop();
+        for line in wrapped {
+            justified.push(justify_line(&line, width, style));
+        }
+        justified.extend(last);
+    }
+
+    justified
+}
+
+// Inserts spaces into gaps between words of a line to make it `width` columns wide.
+fn justify_line(line: &str, width: usize, style: PadStyle) -> String {
+    let stripped = srip_ansi_sequences(line);
+    let line_width = stripped.chars().map(char_width).sum::<usize>();
+
+    // a visible byte index right after the first whitespace character of each gap
+    let mut gaps = Vec::new();
+    let mut prev_is_space = false;
+    for (i, c) in stripped.char_indices() {
+        let is_space = c.is_whitespace();
+        if is_space && !prev_is_space && i > 0 {
+            gaps.push(i + c.len_utf8());
+        }
+        prev_is_space = is_space;
+    }
+
+    if gaps.is_empty() || line_width >= width {
+        return line.to_string();
+    }
+
+    let extra = width - line_width;
+    let count = gaps.len();
+    let mut spaces = (0..count).map(|i| extra / count + usize::from(i < extra % count));
+    let mut gaps = gaps.into_iter().peekable();
+
+    let mut buf = String::with_capacity(line.len() + extra);
+    let mut state = AnsiState::default();
+    let mut offset = 0;
+    let mut rest = line;
+    while let Some((token, next)) = split_first_token(rest) {
+        match token {
+            Token::Text(mut text) => {
+                let end = offset + text.len();
+                while let Some(gap) = gaps.next_if(|&gap| gap <= end) {
+                    let (left, right) = text.split_at(gap - offset);
+                    buf.push_str(left);
+                    push_gap_spaces(&mut buf, &state, spaces.next().unwrap_or(0), style);
+                    text = right;
+                    offset = gap;
+                }
+
+                buf.push_str(text);
+                offset = end;
+            }
+            Token::Escape(seq) => {
+                update_ansi_state_by_escape(&mut state, &seq);
+
+                buf.push_str(&rest[..rest.len() - next.len()]);
+            }
+        }
+
+        rest = next;
+    }
+
+    buf
+}
+
+// Pushes spaces into a gap which has a given style.
+fn push_gap_spaces(buf: &mut String, state: &AnsiState, n: usize, style: PadStyle) {
+    let keep_style = style == PadStyle::Background && state.bg_color.is_some();
+    if keep_style || is_closed(state) {
+        push_spaces(buf, n);
+        return;
+    }
+
+    complete_ansi_sequences(state, buf);
+    push_spaces(buf, n);
+    open_ansi_sequences(state, buf);
+}
+
+// Wraps a single line, the line must not contain line endings.
+fn wrap_line(line: &str, width: usize, wrapped: &mut Vec<String>) {
+    let stripped = srip_ansi_sequences(line);
+    let str_width = |range: Range<usize>| stripped[range].chars().map(char_width).sum::<usize>();
+
+    let mut words = Vec::new();
+    let mut word_start = None;
+    for (i, c) in stripped.char_indices() {
+        if c.is_whitespace() {
+            if let Some(start) = word_start.take() {
+                words.push(start..i);
+            }
+        } else if word_start.is_none() {
+            word_start = Some(i);
+        }
+    }
+
+    if let Some(start) = word_start {
+        words.push(start..stripped.len());
+    }
+
+    // a line which is being filled: its start, end and width
+    let mut current: Option<(usize, usize, usize)> = None;
+    let mut ranges = Vec::new();
+    for word in words {
+        let word_width = str_width(word.clone());
+        if let Some((start, end, line_width)) = current {
+            let line_width = line_width + str_width(end..word.start) + word_width;
+            if line_width <= width {
+                current = Some((start, word.end, line_width));
+                continue;
+            }
+
+            ranges.push(start..end);
+        }
+
+        // the word starts a new line and is broken if it doesn't fit in it
+        let (mut start, mut line_width) = (word.start, 0);
+        for (i, c) in stripped[word.clone()].char_indices() {
+            let w = char_width(c);
+            if line_width + w > width && line_width > 0 {
+                ranges.push(start..word.start + i);
+                start = word.start + i;
+                line_width = 0;
+            }
+
+            line_width += w;
+        }
+
+        current = Some((start, word.end, line_width));
+    }
+
+    match current {
+        Some((start, end, _)) => ranges.push(start..end),
+        None => {
+            wrapped.push(String::new());
+            return;
+        }
+    }
+
+    let text = AnsiIndexedStr::new(line);
+    let options = CutOptions::default().minimal(true).strict(true);
+    for range in ranges {
+        wrapped.push(text.cut_bytes(range.start, Some(range.end), options));
+    }
+}
+
+/// Truncates a string to `width` display columns and appends a suffix if anything was cut.
+/// Preserving its colors.
+///
+/// The string is returned unchanged if it fits in `width`.
+/// Otherwise it's cut so that the content together with the suffix takes exactly `width` columns,
+/// if a wide character doesn't fit at the end of the content, its place is filled by a space.
+/// If the suffix itself is wider than `width` the suffix is cut.
+///
+/// The styles are closed before the suffix, see [`truncate_with`] to style the suffix.
+///
+/// # Examples
+///
+/// ```rust
+/// use owo_colors::{OwoColorize, colors::*};
+/// let colored_text = "When the night has come".fg::<Red>().to_string();
+/// assert_eq!(
+///     ansi_cut::truncate(&colored_text, 10, "…"),
+///     format!("{}…", "When the ".fg::<Red>()),
+/// );
+/// assert_eq!(ansi_cut::truncate(&colored_text, 30, "…"), colored_text);
+/// ```
+pub fn truncate(s: &str, width: usize, suffix: &str) -> String {
+    truncate_with(s, width, suffix, SuffixStyle::Plain)
+}
+
+/// The same as [`truncate`] but with a control of how the suffix is styled.
+///
+/// # Examples
+///
+/// ```rust
+/// use owo_colors::{OwoColorize, colors::*};
+/// use ansi_cut::SuffixStyle;
+/// let colored_text = "When the night has come".fg::<Red>().to_string();
+/// assert_eq!(
+///     ansi_cut::truncate_with(&colored_text, 10, "…", SuffixStyle::Inherit),
+///     "When the …".fg::<Red>().to_string(),
+/// );
+/// ```
+pub fn truncate_with(s: &str, width: usize, suffix: &str, style: SuffixStyle) -> String {
+    let stripped = srip_ansi_sequences(s);
+    if stripped.chars().map(char_width).sum::<usize>() <= width {
+        return s.to_string();
+    }
+
+    truncate_stripped(s, &stripped, width, suffix, style)
+}
+
+// Truncates a string which is wider than `width`, `stripped` is the string without escape sequences.
+fn truncate_stripped(
+    s: &str,
+    stripped: &str,
+    width: usize,
+    suffix: &str,
+    style: SuffixStyle,
+) -> String {
+    let suffix_width = srip_ansi_sequences(suffix)
+        .chars()
+        .map(char_width)
+        .sum::<usize>();
+    if suffix_width >= width {
+        return cut_width(suffix, ..width, WideCharPolicy::Pad);
+    }
+
+    let (end, pad) = width_to_end_byte(stripped, width - suffix_width);
+    match style {
+        SuffixStyle::Plain => {
+            let mut buf = cut_str(s, 0, Some(end));
+            push_spaces(&mut buf, pad);
+            buf.push_str(suffix);
+            buf
+        }
+        SuffixStyle::Inherit => {
+            let (mut buf, style) = cut_open(s, ..end);
+            push_spaces(&mut buf, pad);
+            buf.push_str(suffix);
+            complete_ansi_sequences(&style.state, &mut buf);
+            buf
+        }
+    }
+}
+
+/// Truncates a string to `width` display columns by replacing its middle with a marker.
+/// Preserving its colors.
+///
+/// The string is returned unchanged if it fits in `width`.
+/// Otherwise the beginning and the end of the string are kept so that together with the marker
+/// they take exactly `width` columns, the beginning gets one more column if they can't be equal.
+/// If a wide character doesn't fit in one of the parts, its place is filled by a space.
+///
+/// The beginning is closed before the marker, the marker is not styled
+/// and the end starts with a style which is active at its beginning.
+///
+/// If the marker is wider than `width` an empty string is returned,
+/// if it's exactly `width` wide only the marker is returned.
+///
+/// # Examples
+///
+/// ```rust
+/// use owo_colors::{OwoColorize, colors::*};
+/// let colored_text = format!("{}/{}", "home".fg::<Red>(), "night.txt".fg::<Blue>());
+/// assert_eq!(
+///     ansi_cut::truncate_middle(&colored_text, 9, "…"),
+///     format!("{}…{}", "home".fg::<Red>(), ".txt".fg::<Blue>()),
+/// );
+/// ```
+pub fn truncate_middle(s: &str, width: usize, marker: &str) -> String {
+    let stripped = srip_ansi_sequences(s);
+    let str_width = stripped.chars().map(char_width).sum::<usize>();
+    if str_width <= width {
+        return s.to_string();
+    }
+
+    let marker_width = srip_ansi_sequences(marker)
+        .chars()
+        .map(char_width)
+        .sum::<usize>();
+    match marker_width.cmp(&width) {
+        std::cmp::Ordering::Greater => return String::new(),
+        std::cmp::Ordering::Equal => return marker.to_string(),
+        std::cmp::Ordering::Less => {}
+    }
+
+    let left = width - marker_width;
+    let head_width = left.div_ceil(2);
+    let tail_width = left / 2;
+
+    let (head_end, pad_right) = width_to_end_byte(&stripped, head_width);
+    let (tail_start, pad_left) = width_to_start_byte(&stripped, str_width - tail_width);
+
+    let text = AnsiIndexedStr::new(s);
+    let mut buf = text.cut_bytes(0, Some(head_end), CutOptions::default());
+    push_spaces(&mut buf, pad_right);
+    buf.push_str(marker);
+    push_spaces(&mut buf, pad_left);
+    let tail = text.cut_bytes(tail_start, None, CutOptions::default().minimal(true));
+    buf.push_str(&tail);
+
+    buf
+}
+
+/// Truncates a string to `width` display columns by replacing its beginning with a marker.
+/// Preserving its colors.
+///
+/// The string is returned unchanged if it fits in `width`.
+/// Otherwise the end of the string is kept so that together with the marker
+/// it takes exactly `width` columns.
+/// If a wide character doesn't fit in the kept part, its place is filled by a space.
+///
+/// The marker is not styled and the kept part starts with a style which is active at its beginning,
+/// the escape sequences of the dropped part are not replayed.
+///
+/// If the marker is wider than `width` an empty string is returned,
+/// if it's exactly `width` wide only the marker is returned.
+///
+/// # Examples
+///
+/// ```rust
+/// use owo_colors::{OwoColorize, colors::*};
+/// let colored_text = format!("{} {}", "When the".fg::<Red>(), "night".fg::<Blue>());
+/// assert_eq!(
+///     ansi_cut::truncate_front(&colored_text, 10, "…"),
+///     format!("…{} {}", "the".fg::<Red>(), "night".fg::<Blue>()),
+/// );
+/// ```
+pub fn truncate_front(s: &str, width: usize, marker: &str) -> String {
+    let stripped = srip_ansi_sequences(s);
+    let str_width = stripped.chars().map(char_width).sum::<usize>();
+    if str_width <= width {
+        return s.to_string();
+    }
+
+    let marker_width = srip_ansi_sequences(marker)
+        .chars()
+        .map(char_width)
+        .sum::<usize>();
+    match marker_width.cmp(&width) {
+        std::cmp::Ordering::Greater => return String::new(),
+        std::cmp::Ordering::Equal => return marker.to_string(),
+        std::cmp::Ordering::Less => {}
+    }
+
+    let (start, pad_left) = width_to_start_byte(&stripped, str_width - (width - marker_width));
+
+    let mut buf = marker.to_string();
+    push_spaces(&mut buf, pad_left);
+    buf.push_str(&cut_with(s, start.., CutOptions::default().minimal(true)));
+
+    buf
+}
+
+/// Makes a string exactly `width` display columns wide.
+/// Preserving its colors.
+///
+/// A string which is wider than `width` is truncated like in [`truncate`] with no suffix,
+/// a narrower one is padded by spaces according to the alignment.
+/// The padding is not styled.
+///
+/// See [`align_with`] to truncate with an ellipsis.
+///
+/// # Examples
+///
+/// ```rust
+/// use owo_colors::{OwoColorize, colors::*};
+/// use ansi_cut::Alignment;
+/// let colored_text = "night".fg::<Red>().to_string();
+/// assert_eq!(
+///     ansi_cut::align(&colored_text, 8, Alignment::Center),
+///     format!(" {}  ", "night".fg::<Red>()),
+/// );
+/// assert_eq!(
+///     ansi_cut::align(&colored_text, 3, Alignment::Center),
+///     "nig".fg::<Red>().to_string(),
+/// );
+/// ```
+pub fn align(s: &str, width: usize, alignment: Alignment) -> String {
+    align_with(s, width, alignment, "")
+}
+
+/// The same as [`align`] but a truncated string ends with a given ellipsis.
+///
+/// # Examples
+///
+/// ```rust
+/// use owo_colors::{OwoColorize, colors::*};
+/// use ansi_cut::Alignment;
+/// let colored_text = "When the night".fg::<Red>().to_string();
+/// assert_eq!(
+///     ansi_cut::align_with(&colored_text, 6, Alignment::Right, "…"),
+///     format!("{}…", "When ".fg::<Red>()),
+/// );
+/// ```
+pub fn align_with(s: &str, width: usize, alignment: Alignment, ellipsis: &str) -> String {
+    let stripped = srip_ansi_sequences(s);
+    let str_width = stripped.chars().map(char_width).sum::<usize>();
+    if str_width > width {
+        return truncate_stripped(s, &stripped, width, ellipsis, SuffixStyle::Plain);
+    }
+
+    let (left, right) = match alignment {
+        Alignment::Left => (0, width - str_width),
+        Alignment::Center => ((width - str_width) / 2, (width - str_width).div_ceil(2)),
+        Alignment::Right => (width - str_width, 0),
+    };
+
+    let mut buf = String::with_capacity(s.len() + width - str_width);
+    push_spaces(&mut buf, left);
+    buf.push_str(s);
+    push_spaces(&mut buf, right);
+
+    buf
+}
+
+/// Makes a multi-line string fit exactly into a cell of `width` x `height`.
+/// Preserving its colors.
+///
+/// Each line is truncated or padded to exactly `width` display columns like in [`align_with`],
+/// and the lines are clipped or missing ones are added to get exactly `height` lines,
+/// the lines are joined by `\n`.
+///
+/// A style which is left open by one of the lines is reopened at the beginning of the next ones.
+/// See [`FitOptions`] for the ellipsis, fill character, vertical alignment
+/// and how the lines are closed.
+///
+/// # Examples
+///
+/// ```rust
+/// use owo_colors::{OwoColorize, colors::*};
+/// use ansi_cut::FitOptions;
+/// let colored_text = "When the\nnight\nhas come".fg::<Red>().to_string();
+/// assert_eq!(
+///     ansi_cut::fit(&colored_text, 6, 2, FitOptions::default()),
+///     format!("{}\n{} ", "When t".fg::<Red>(), "night".fg::<Red>()),
+/// );
+/// ```
+pub fn fit(s: &str, width: usize, height: usize, options: FitOptions<'_>) -> String {
+    let lines = styled_lines(s).map(|(line, _)| line).collect::<Vec<_>>();
+    let count = lines.len();
+
+    let (skip, top, bottom) = if count >= height {
+        let skip = match options.vertical {
+            VerticalAlignment::Top => 0,
+            VerticalAlignment::Middle => (count - height) / 2,
+            VerticalAlignment::Bottom => count - height,
+        };
+
+        (skip, 0, 0)
+    } else {
+        let missing = height - count;
+        let top = match options.vertical {
+            VerticalAlignment::Top => 0,
+            VerticalAlignment::Middle => missing / 2,
+            VerticalAlignment::Bottom => missing,
+        };
+
+        (0, top, missing - top)
+    };
+
+    let mut empty_line = String::new();
+    push_padding(
+        &mut empty_line,
+        "",
+        None,
+        width,
+        options.fill,
+        PadStyle::Plain,
+    );
+
+    let lines = lines.iter().skip(skip).take(height);
+    let lines = std::iter::repeat_n(empty_line.clone(), top)
+        .chain(lines.map(|line| fit_line(line, width, &options)))
+        .chain(std::iter::repeat_n(empty_line, bottom));
+
+    lines.collect::<Vec<_>>().join("\n")
+}
+
+// Truncates or pads a single line to exactly `width` columns.
+fn fit_line(line: &str, width: usize, options: &FitOptions<'_>) -> String {
+    let stripped = srip_ansi_sequences(line);
+    let str_width = stripped.chars().map(char_width).sum::<usize>();
+    let cut_options = CutOptions::default().reset(options.reset);
+
+    if str_width <= width {
+        let mut buf = cut_with(line, .., cut_options);
+        push_padding(
+            &mut buf,
+            line,
+            None,
+            width - str_width,
+            options.fill,
+            PadStyle::Plain,
+        );
+        return buf;
+    }
+
+    let ellipsis_width = srip_ansi_sequences(options.ellipsis)
+        .chars()
+        .map(char_width)
+        .sum::<usize>();
+    if ellipsis_width >= width {
+        return cut_width(options.ellipsis, ..width, WideCharPolicy::Pad);
+    }
+
+    let (end, pad) = width_to_end_byte(&stripped, width - ellipsis_width);
+    let mut buf = cut_with(line, ..end, cut_options);
+    push_spaces(&mut buf, pad);
+    buf.push_str(options.ellipsis);
+
+    buf
+}
+
+/// Lays out items into columns which fit in `total_width` display columns, like `ls` does.
+/// Preserving their colors.
+///
+/// The items go down the columns, columns are separated by `gap` spaces
+/// and each of them is as wide as its widest item, shorter items are padded by spaces.
+/// The rows are joined by `\n`.
+///
+/// The number of columns is the largest one for which the columns fit in `total_width`,
+/// with `rows = ceil(items / columns)` every column but the last one has exactly `rows` items.
+/// If even a single column doesn't fit, the items are truncated to `total_width`.
+///
+/// Each cell is closed, so a style of an item never continues to its neighbours.
+/// The items are expected to be single line strings.
+///
+/// # Examples
+///
+/// ```rust
+/// use owo_colors::{OwoColorize, colors::*};
+/// let items = vec![
+///     "src".fg::<Blue>().to_string(),
+///     "Cargo.toml".to_string(),
+///     "README.md".to_string(),
+///     "target".fg::<Blue>().to_string(),
+/// ];
+/// assert_eq!(
+///     ansi_cut::columns(&items, 25, 2),
+///     format!("{}         README.md\nCargo.toml  {}", "src".fg::<Blue>(), "target".fg::<Blue>()),
+/// );
+/// ```
+pub fn columns<S>(items: &[S], total_width: usize, gap: usize) -> String
+where
+    S: AsRef<str>,
+{
+    let widths = items
+        .iter()
+        .map(|item| {
+            srip_ansi_sequences(item.as_ref())
+                .chars()
+                .map(char_width)
+                .sum::<usize>()
+        })
+        .collect::<Vec<_>>();
+
+    // widths of columns for a given number of rows
+    let column_widths = |rows: usize| {
+        widths
+            .chunks(rows)
+            .map(|column| column.iter().copied().max().unwrap_or(0))
+            .collect::<Vec<_>>()
+    };
+
+    let mut layout = None;
+    for count in (1..=items.len()).rev() {
+        let rows = items.len().div_ceil(count);
+        let column_widths = column_widths(rows);
+        let gaps = gap * column_widths.len().saturating_sub(1);
+        if column_widths.iter().sum::<usize>() + gaps <= total_width {
+            layout = Some((rows, column_widths));
+            break;
+        }
+    }
+
+    let (rows, column_widths) = layout.unwrap_or_else(|| {
+        let column_widths = column_widths(items.len().max(1));
+        (
+            items.len(),
+            column_widths
+                .iter()
+                .map(|w| (*w).min(total_width))
+                .collect(),
+        )
+    });
+
+    let mut buf = String::new();
+    for row in 0..rows {
+        if row > 0 {
+            buf.push('\n');
+        }
+
+        for (column, &column_width) in column_widths.iter().enumerate() {
+            let i = column * rows + row;
+            let Some(item) = items.get(i) else {
+                break;
+            };
+
+            if column > 0 {
+                push_spaces(&mut buf, gap);
+            }
+
+            let item = item.as_ref();
+            if widths[i] > column_width {
+                buf.push_str(&cut_width(item, ..column_width, WideCharPolicy::Pad));
+            } else {
+                buf.push_str(&cut_str(item, 0, None));
+            }
+
+            // the last cell of a row is not padded
+            let is_last = items.get(i + rows).is_none();
+            if !is_last {
+                push_spaces(&mut buf, column_width - widths[i]);
+            }
+        }
+    }
+
+    buf
+}
+
+/// Places two multi-line strings side by side.
+/// Preserving their colors.
+///
+/// Lines of `left` are padded by spaces to the width of the widest of them,
+/// so the lines of `right` start at the same column after `gap` spaces.
+/// Each line is closed, so styles of `left` never continue into `right`.
+/// The lines are joined by `\n`.
+///
+/// If the strings have different number of lines, the shorter one gets empty lines at the bottom,
+/// see [`concat_horizontal_with`] to change it.
+///
+/// # Examples
+///
+/// ```rust
+/// use owo_colors::{OwoColorize, colors::*};
+/// let left = "When\nthe night".fg::<Red>().to_string();
+/// let right = "has come".fg::<Blue>().to_string();
+/// assert_eq!(
+///     ansi_cut::concat_horizontal(&left, &right, 1),
+///     format!(
+///         "{}      {}\n{}",
+///         "When".fg::<Red>(),
+///         "has come".fg::<Blue>(),
+///         "the night".fg::<Red>(),
+///     ),
+/// );
+/// ```
+pub fn concat_horizontal(left: &str, right: &str, gap: usize) -> String {
+    concat_horizontal_with(left, right, gap, VerticalAlignment::Top)
+}
+
+/// The same as [`concat_horizontal`] but with a control of where the lines of a shorter string are placed.
+///
+/// # Examples
+///
+/// ```rust
+/// use ansi_cut::VerticalAlignment;
+/// assert_eq!(
+///     ansi_cut::concat_horizontal_with("a\nb\nc", "d", 1, VerticalAlignment::Bottom),
+///     "a\nb\nc d",
+/// );
+/// ```
+pub fn concat_horizontal_with(
+    left: &str,
+    right: &str,
+    gap: usize,
+    alignment: VerticalAlignment,
+) -> String {
+    let left = lines(left).collect::<Vec<_>>();
+    let right = lines(right).collect::<Vec<_>>();
+    let height = left.len().max(right.len());
+
+    let str_width = |s: &str| {
+        srip_ansi_sequences(s)
+            .chars()
+            .map(char_width)
+            .sum::<usize>()
+    };
+    let left_width = left.iter().map(|line| str_width(line)).max().unwrap_or(0);
+
+    let top = |count: usize| match alignment {
+        VerticalAlignment::Top => 0,
+        VerticalAlignment::Middle => (height - count) / 2,
+        VerticalAlignment::Bottom => height - count,
+    };
+    let (left_top, right_top) = (top(left.len()), top(right.len()));
+
+    let mut buf = String::new();
+    for i in 0..height {
+        if i > 0 {
+            buf.push('\n');
+        }
+
+        let left_line = i.checked_sub(left_top).and_then(|i| left.get(i));
+        let left_line = left_line.map_or("", String::as_str);
+        let right_line = i.checked_sub(right_top).and_then(|i| right.get(i));
+        let right_line = right_line.map_or("", String::as_str);
+
+        buf.push_str(left_line);
+        if !right_line.is_empty() {
+            push_spaces(&mut buf, left_width - str_width(left_line) + gap);
+            buf.push_str(right_line);
+        }
+    }
+
+    buf
+}
+
+/// Replaces tabs by spaces up to the next tab stop.
+/// Preserving its colors.
+///
+/// Tab stops are placed every `tab_width` display columns, the column is counted ignoring
+/// escape sequences and starts over at every line.
+///
+/// The spaces inherit the style active at a tab, see [`expand_tabs_with`] to keep them plain.
+///
+/// # Panics
+///
+/// Panics if `tab_width` is 0.
+///
+/// # Examples
+///
+/// ```rust
+/// use owo_colors::{OwoColorize, colors::*};
+/// let colored_text = format!("{}\tnight", "When".fg::<Red>());
+/// assert_eq!(
+///     ansi_cut::expand_tabs(&colored_text, 8),
+///     format!("{}    night", "When".fg::<Red>()),
+/// );
+/// ```
+pub fn expand_tabs(s: &str, tab_width: usize) -> String {
+    expand_tabs_with(s, tab_width, InsertStyle::Inherit)
+}
+
+/// The same as [`expand_tabs`] but with a control of which style the spaces get.
+///
+/// # Panics
+///
+/// Panics if `tab_width` is 0.
+///
+/// # Examples
+///
+/// ```rust
+/// use owo_colors::{OwoColorize, colors::*};
+/// use ansi_cut::InsertStyle;
+/// let colored_text = "a\tb".bg::<Red>().to_string();
+/// assert_eq!(
+///     ansi_cut::expand_tabs_with(&colored_text, 4, InsertStyle::Isolate),
+///     format!("{}   {}", "a".bg::<Red>(), "b".bg::<Red>()),
+/// );
+/// ```
+pub fn expand_tabs_with(s: &str, tab_width: usize, style: InsertStyle) -> String {
+    assert!(tab_width > 0);
+
+    let mut buf = String::with_capacity(s.len());
+    let mut state = AnsiState::default();
+    let mut column = 0;
+    let mut rest = s;
+    while let Some((token, next)) = split_first_token(rest) {
+        match token {
+            Token::Text(text) => {
+                for c in text.chars() {
+                    match c {
+                        '\t' => {
+                            let n = tab_width - column % tab_width;
+                            if style == InsertStyle::Isolate && !is_closed(&state) {
+                                complete_ansi_sequences(&state, &mut buf);
+                                push_spaces(&mut buf, n);
+                                open_ansi_sequences(&state, &mut buf);
+                            } else {
+                                push_spaces(&mut buf, n);
+                            }
+
+                            column += n;
+                        }
+                        '\n' => {
+                            buf.push(c);
+                            column = 0;
+                        }
+                        c => {
+                            buf.push(c);
+                            column += char_width(c);
+                        }
+                    }
+                }
+            }
+            Token::Escape(seq) => {
+                update_ansi_state_by_escape(&mut state, &seq);
+
+                buf.push_str(&rest[..rest.len() - next.len()]);
+            }
+        }
+
+        rest = next;
+    }
+
+    buf
+}
+
+/// Draws `top` over `base` starting at display column `col`.
+/// Preserving their colors.
+///
+/// The columns of `base` covered by `top` are replaced by it,
+/// the part of `base` before `col` is kept as it is and the part after `top`
+/// starts with a style of `base` which is active at its beginning.
+/// A wide character of `base` which is partially covered by `top` is replaced by spaces.
+/// If `base` is shorter than `col` it's padded by spaces.
+///
+/// If the strings have several lines each line of `top` is drawn over the matching line of `base`
+/// starting at the same column `col`, lines are split like in [`lines_with_endings`]
+/// and keep their endings.
+/// Lines of `top` which go beyond the end of `base` are drawn over empty lines.
+///
+/// # Examples
+///
+/// ```rust
+/// use owo_colors::{OwoColorize, colors::*};
+/// let base = "When the night has come".fg::<Red>().to_string();
+/// let top = "DAY".fg::<Blue>().to_string();
+/// assert_eq!(
+///     ansi_cut::overlay(&base, &top, 9),
+///     format!("{}{}{}", "When the ".fg::<Red>(), "DAY".fg::<Blue>(), "ht has come".fg::<Red>()),
+/// );
+///
+/// assert_eq!(ansi_cut::overlay("abc\nde", "X\nY\nZ", 1), "aXc\ndY\n Z");
+/// ```
+pub fn overlay(base: &str, top: &str, col: usize) -> String {
+    if !srip_ansi_sequences(base).contains('\n') && !srip_ansi_sequences(top).contains('\n') {
+        return overlay_line(base, top, col);
+    }
+
+    let mut base_lines = lines_with_endings(base);
+    let mut top_lines = lines(top);
+    let mut buf = String::with_capacity(base.len() + top.len());
+    let mut last_ending = LineEnding::None;
+    loop {
+        let (line, ending) = match (base_lines.next(), top_lines.next()) {
+            (Some((line, ending)), Some(top)) => (overlay_line(&line, &top, col), ending),
+            (Some((line, ending)), None) => (line, ending),
+            (None, Some(top)) => {
+                // a line which is added to the end of `base` needs to be separated from it
+                if !buf.is_empty() && last_ending == LineEnding::None {
+                    buf.push('\n');
+                }
+
+                (overlay_line("", &top, col), LineEnding::None)
+            }
+            (None, None) => break,
+        };
+
+        buf.push_str(&line);
+        buf.push_str(ending.as_str());
+        last_ending = ending;
+    }
+
+    buf
+}
+
+// Draws a single line `top` over a single line `base`.
+fn overlay_line(base: &str, top: &str, col: usize) -> String {
+    let stripped = srip_ansi_sequences(base);
+    let base_width = stripped.chars().map(char_width).sum::<usize>();
+    let top_width = srip_ansi_sequences(top)
+        .chars()
+        .map(char_width)
+        .sum::<usize>();
+
+    let (head_end, pad_right) = width_to_end_byte(&stripped, col);
+    let mut buf = cut_str(base, 0, Some(head_end));
+    push_spaces(&mut buf, pad_right + col.saturating_sub(base_width));
+
+    buf.push_str(&cut_str(top, 0, None));
+
+    // a wide char which is covered partially leaves a space even if it's the last one
+    let (tail_start, pad_left) = width_to_start_byte(&stripped, col + top_width);
+    push_spaces(&mut buf, pad_left);
+    if tail_start < stripped.len() {
+        let options = CutOptions::default().minimal(true);
+        buf.push_str(&cut_with(base, tail_start.., options));
+    }
+
+    buf
+}
+
+/// Pads a string with `fill` on the right up to `width` display columns.
+/// Preserving its colors.
+///
+/// The string is never truncated, it's returned unchanged if it's already `width` columns wide or wider.
+/// If `fill` is a wide character and it doesn't fit in the columns left, spaces are used for them.
+///
+/// The padding is not styled, see [`pad_right_with`] to color it.
+///
+/// # Examples
+///
+/// ```rust
+/// use owo_colors::{OwoColorize, colors::*};
+/// let colored_text = "When".fg::<Red>().to_string();
+/// assert_eq!(
+///     ansi_cut::pad_right(&colored_text, 6, '.'),
+///     format!("{}..", "When".fg::<Red>()),
+/// );
+/// ```
+pub fn pad_right(s: &str, width: usize, fill: char) -> String {
+    pad_right_with(s, width, fill, PadStyle::Plain)
+}
+
+/// The same as [`pad_right`] but with a control of how the padding is styled.
+///
+/// With [`PadStyle::Background`] the padding gets a background color of the last char of the string.
+///
+/// # Examples
+///
+/// ```rust
+/// use owo_colors::{OwoColorize, colors::*};
+/// use ansi_cut::PadStyle;
+/// let colored_text = "When".bg::<Red>().to_string();
+/// assert_eq!(
+///     ansi_cut::pad_right_with(&colored_text, 6, ' ', PadStyle::Background),
+///     format!("{}{}", "When".bg::<Red>(), "  ".bg::<Red>()),
+/// );
+/// ```
+pub fn pad_right_with(s: &str, width: usize, fill: char, style: PadStyle) -> String {
+    let stripped = srip_ansi_sequences(s);
+    let str_width = stripped.chars().map(char_width).sum::<usize>();
+    if str_width >= width {
+        return s.to_string();
+    }
+
+    let last_char = stripped.char_indices().last().map(|(i, _)| i);
+    let mut buf = s.to_string();
+    push_padding(&mut buf, s, last_char, width - str_width, fill, style);
+
+    buf
+}
+
+/// Pads a string with `fill` on the left up to `width` display columns.
+/// Preserving its colors.
+///
+/// The string is never truncated, it's returned unchanged if it's already `width` columns wide or wider.
+/// If `fill` is a wide character and it doesn't fit in the columns left, spaces are used for them.
+///
+/// The padding is not styled, see [`pad_left_with`] to color it.
+///
+/// # Examples
+///
+/// ```rust
+/// use owo_colors::{OwoColorize, colors::*};
+/// let colored_text = "When".fg::<Red>().to_string();
+/// assert_eq!(
+///     ansi_cut::pad_left(&colored_text, 6, '.'),
+///     format!("..{}", "When".fg::<Red>()),
+/// );
+/// ```
+pub fn pad_left(s: &str, width: usize, fill: char) -> String {
+    pad_left_with(s, width, fill, PadStyle::Plain)
+}
+
+/// The same as [`pad_left`] but with a control of how the padding is styled.
+///
+/// With [`PadStyle::Background`] the padding gets a background color of the first char of the string.
+///
+/// # Examples
+///
+/// ```rust
+/// use owo_colors::{OwoColorize, colors::*};
+/// use ansi_cut::PadStyle;
+/// let colored_text = "When".bg::<Red>().to_string();
+/// assert_eq!(
+///     ansi_cut::pad_left_with(&colored_text, 6, ' ', PadStyle::Background),
+///     format!("{}{}", "  ".bg::<Red>(), "When".bg::<Red>()),
+/// );
+/// ```
+pub fn pad_left_with(s: &str, width: usize, fill: char, style: PadStyle) -> String {
+    let stripped = srip_ansi_sequences(s);
+    let str_width = stripped.chars().map(char_width).sum::<usize>();
+    if str_width >= width {
+        return s.to_string();
+    }
+
+    let first_char = (!stripped.is_empty()).then_some(0);
+    let mut buf = String::with_capacity(s.len() + width - str_width);
+    push_padding(&mut buf, s, first_char, width - str_width, fill, style);
+    buf.push_str(s);
+
+    buf
+}
+
+// Pushes a padding of `cols` columns,
+// with PadStyle::Background it gets a background color of a given visible byte of a string.
+fn push_padding(
+    buf: &mut String,
+    s: &str,
+    styled_by: Option<usize>,
+    cols: usize,
+    fill: char,
+    style: PadStyle,
+) {
+    let mut padding_state = AnsiState::default();
+    if style == PadStyle::Background {
+        if let Some(style) = styled_by.and_then(|i| AnsiIndexedStr::new(s).style_at(i)) {
+            padding_state.bg_color = style.state.bg_color;
+        }
+    }
+
+    let fill_width = char_width(fill).max(1);
+
+    open_ansi_sequences(&padding_state, buf);
+    for _ in 0..cols / fill_width {
+        buf.push(fill);
+    }
+    push_spaces(buf, cols % fill_width);
+    complete_ansi_sequences(&padding_state, buf);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::unclosed_styles;
+
+    #[test]
+    fn wrap_test() {
+        assert_eq!(Vec::<String>::new(), wrap("", 3));
+        assert_eq!(vec!["", ""], wrap("   \n\n", 3));
+        assert_eq!(vec!["some", "thing"], wrap("some thing", 6));
+        assert_eq!(vec!["some thing"], wrap("some thing", 10));
+        assert_eq!(vec!["a b", "c", "d"], wrap("  a b  c\r\n d   ", 3));
+        assert_eq!(vec!["som", "eth", "ing", "a b"], wrap("something a b", 3));
+        assert_eq!(vec!["a", "lon", "g b"], wrap("a long b", 3));
+
+        // a colored word spans a break
+        let s = "some \u{1b}[31mred and\u{1b}[1m bold\u{1b}[0m text";
+        assert_eq!(
+            vec![
+                "some \u{1b}[31mred\u{1b}[39m",
+                "\u{1b}[31mand\u{1b}[1m bold\u{1b}[0m",
+                "text",
+            ],
+            wrap(s, 8)
+        );
+        assert_eq!(
+            vec![
+                "some",
+                "\u{1b}[31mred\u{1b}[39m",
+                "\u{1b}[31mand\u{1b}[1m\u{1b}[22;39m",
+                "\u{1b}[1;31mbold\u{1b}[0m",
+                "text",
+            ],
+            wrap(s, 4)
+        );
+        assert_eq!(
+            vec![
+                "\u{1b}[31mso\u{1b}[39m",
+                "\u{1b}[31mme\u{1b}[39m",
+                "\u{1b}[31mx\u{1b}[39m"
+            ],
+            wrap("\u{1b}[31msome\nx\u{1b}[39m", 2)
+        );
+
+        assert_eq!(vec!["你好", "世界"], wrap("你好世界", 5));
+        assert_eq!(vec!["你好", "世界"], wrap("你好 世界", 5));
+        // a char wider than width is left alone on a line wider than width
+        assert_eq!(vec!["你", "好"], wrap("你好", 1));
+        assert_eq!(vec!["a", "你", "b"], wrap("a你b", 1));
+        assert_eq!(
+            vec!["\u{1b}[34m😀😃\u{1b}[39m", "\u{1b}[34m😄\u{1b}[39m a"],
+            wrap("\u{1b}[34m😀😃😄\u{1b}[39m a", 4)
+        );
+
+        let s = "\u{1b}[31;40mWhen the\u{1b}[1m night 你好 has\u{1b}[0m come 😀😃";
+        for n in 1..20 {
+            for line in wrap(s, n) {
+                let width = srip_ansi_sequences(&line)
+                    .chars()
+                    .map(char_width)
+                    .sum::<usize>();
+                assert!(width <= n.max(2), "{:?}", line);
+                assert!(unclosed_styles(&line).is_empty(), "{:?}", line);
+            }
+        }
+    }
+
+    #[test]
+    #[should_panic]
+    fn wrap_panic_when_width_is_zero() {
+        wrap("something", 0);
+    }
+
+    #[test]
+    fn wrap_with_test() {
+        assert_eq!(wrap("some thing", 6), wrap_with("some thing", 6, None));
+        assert_eq!(
+            vec!["some", "thing"],
+            wrap_with("some thing", 6, Some(FillMode::EraseToEol))
+        );
+        assert_eq!(
+            vec!["some", "thing"],
+            wrap_with("some thing", 6, Some(FillMode::PadSpaces(6)))
+        );
+
+        let s = "plain \u{1b}[44mblue andmore\u{1b}[49m plain";
+        assert_eq!(
+            vec![
+                "plain \u{1b}[44mblue\u{1b}[K\u{1b}[49m",
+                "\u{1b}[44mandmore\u{1b}[K\u{1b}[49m",
+                "plain",
+            ],
+            wrap_with(s, 10, Some(FillMode::EraseToEol))
+        );
+        assert_eq!(
+            vec![
+                "plain \u{1b}[44mblue\u{1b}[49m",
+                "\u{1b}[44mandmore\u{1b}[49m\u{1b}[44m   \u{1b}[49m",
+                "plain",
+            ],
+            wrap_with(s, 10, Some(FillMode::PadSpaces(10)))
+        );
+    }
+
+    #[test]
+    fn justify_test() {
+        assert_eq!(Vec::<String>::new(), justify("", 3));
+        assert_eq!(vec!["a  b", "c"], justify("a b c", 4));
+        assert_eq!(vec!["a b c"], justify("a b c", 5));
+        assert_eq!(vec!["a  b  c", "de"], justify("a b c de", 7));
+        assert_eq!(vec!["a   b  c", "def"], justify("a b c def", 8));
+        assert_eq!(vec!["long", "word"], justify("long word", 4));
+        assert_eq!(vec!["a  b", "c", "d e"], justify("a b c\nd e", 4));
+        assert_eq!(vec!["你  好", "世"], justify("你 好 世", 6));
+
+        let s = "\u{1b}[31mred\u{1b}[39m \u{1b}[34mblue\u{1b}[39m end";
+        assert_eq!(
+            vec!["\u{1b}[31mred\u{1b}[39m   \u{1b}[34mblue\u{1b}[39m", "end"],
+            justify(s, 10)
+        );
+
+        let s = "\u{1b}[31mred blue\u{1b}[39m end";
+        assert_eq!(
+            vec!["\u{1b}[31mred \u{1b}[39m  \u{1b}[31mblue\u{1b}[39m", "end"],
+            justify(s, 10)
+        );
+
+        let s = "\u{1b}[41mred blue\u{1b}[49m end";
+        assert_eq!(
+            vec!["\u{1b}[41mred   blue\u{1b}[49m", "end"],
+            justify(s, 10)
+        );
+        assert_eq!(
+            vec!["\u{1b}[41mred \u{1b}[49m  \u{1b}[41mblue\u{1b}[49m", "end"],
+            justify_with(s, 10, PadStyle::Plain)
+        );
+
+        let s = "\u{1b}[31;40mWhen the\u{1b}[1m night 你好 has\u{1b}[0m come 😀 a b";
+        for width in 2..30 {
+            let lines = justify(s, width);
+            for line in &lines[..lines.len() - 1] {
+                let w = srip_ansi_sequences(line)
+                    .chars()
+                    .map(char_width)
+                    .sum::<usize>();
+                let words = srip_ansi_sequences(line).split_whitespace().count();
+                assert!(w == width || words == 1, "{:?}", line);
+                assert!(unclosed_styles(line).is_empty(), "{:?}", line);
+            }
+
+            let words = |lines: Vec<String>| {
+                lines
+                    .iter()
+                    .map(|line| {
+                        srip_ansi_sequences(line)
+                            .split_whitespace()
+                            .collect::<String>()
+                    })
+                    .collect::<Vec<_>>()
+            };
+            assert_eq!(words(wrap(s, width)), words(lines));
+        }
+    }
+
+    #[test]
+    fn truncate_test() {
+        assert_eq!("", truncate("", 3, "…"));
+        assert_eq!("some", truncate("some", 4, "…"));
+        assert_eq!("so…", truncate("some", 3, "…"));
+        assert_eq!("s...", truncate("something", 4, "..."));
+        assert_eq!("..", truncate("something", 2, "..."));
+        assert_eq!("", truncate("something", 0, "…"));
+        assert_eq!("你 …", truncate("你好世界", 4, "…"));
+        assert_eq!("你好…", truncate("你好世界", 5, "…"));
+        assert_eq!(" ", truncate("你好世界", 1, "好"));
+
+        let s = "\u{1b}[31mred\u{1b}[1m bold\u{1b}[0m text";
+        assert_eq!(s, truncate(s, 13, "…"));
+        assert_eq!(
+            "\u{1b}[31mred\u{1b}[1m b\u{1b}[22;39m…",
+            truncate(s, 6, "…")
+        );
+        assert_eq!("\u{1b}[31mre\u{1b}[39m…", truncate(s, 3, "…"));
+        assert_eq!(
+            "\u{1b}[31mred\u{1b}[1m b…\u{1b}[22;39m",
+            truncate_with(s, 6, "…", SuffixStyle::Inherit)
+        );
+        assert_eq!(
+            "\u{1b}[31mred\u{1b}[1m bold\u{1b}[0m t…",
+            truncate_with(s, 11, "…", SuffixStyle::Inherit)
+        );
+        assert_eq!(
+            "\u{1b}[31mre\u{1b}[39m\u{1b}[34m…\u{1b}[39m",
+            truncate(s, 3, "\u{1b}[34m…\u{1b}[39m")
+        );
+
+        let s = "\u{1b}[31;40mWhen 你好\u{1b}[1m night 😀\u{1b}[0m has come";
+        let str_width = |s: &str| {
+            srip_ansi_sequences(s)
+                .chars()
+                .map(char_width)
+                .sum::<usize>()
+        };
+        for width in 0..30 {
+            for style in [SuffixStyle::Plain, SuffixStyle::Inherit] {
+                let truncated = truncate_with(s, width, "…", style);
+                assert_eq!(width.min(str_width(s)), str_width(&truncated));
+                assert!(unclosed_styles(&truncated).is_empty(), "{:?}", truncated);
+            }
+        }
+    }
+
+    #[test]
+    fn truncate_middle_test() {
+        assert_eq!("", truncate_middle("", 3, "…"));
+        assert_eq!("some", truncate_middle("some", 4, "…"));
+        assert_eq!("so…g", truncate_middle("something", 4, "…"));
+        assert_eq!("so...ng", truncate_middle("something", 7, "..."));
+        assert_eq!("", truncate_middle("something", 2, "..."));
+        assert_eq!("...", truncate_middle("something", 3, "..."));
+        assert_eq!("你…界", truncate_middle("你好世界", 5, "…"));
+        assert_eq!("你 … 界", truncate_middle("你好世界", 7, "…"));
+        assert_eq!(" …", truncate_middle("你好世界", 2, "…"));
+
+        let s = "\u{1b}[31mred\u{1b}[1m bold\u{1b}[0m text";
+        assert_eq!(s, truncate_middle(s, 13, "…"));
+        assert_eq!("\u{1b}[31mre\u{1b}[39m…xt", truncate_middle(s, 5, "…"));
+        assert_eq!(
+            "\u{1b}[31mred\u{1b}[1m\u{1b}[22;39m…ext",
+            truncate_middle(s, 7, "…")
+        );
+        assert_eq!(
+            "\u{1b}[31mred\u{1b}[1m\u{1b}[22;39m…\u{1b}[1;31mext\u{1b}[0m",
+            truncate_middle("\u{1b}[31mred\u{1b}[1m bold text\u{1b}[0m", 7, "…")
+        );
+        assert_eq!(
+            "\u{1b}[31mre\u{1b}[39m\u{1b}[34m…\u{1b}[39mxt",
+            truncate_middle(s, 5, "\u{1b}[34m…\u{1b}[39m")
+        );
+
+        let s = "\u{1b}[31;40mWhen 你好\u{1b}[1m night 😀\u{1b}[0m has come";
+        let str_width = |s: &str| {
+            srip_ansi_sequences(s)
+                .chars()
+                .map(char_width)
+                .sum::<usize>()
+        };
+        for width in 1..30 {
+            let truncated = truncate_middle(s, width, "…");
+            assert_eq!(width.min(str_width(s)), str_width(&truncated));
+            assert!(unclosed_styles(&truncated).is_empty(), "{:?}", truncated);
+        }
+    }
+
+    #[test]
+    fn truncate_front_test() {
+        assert_eq!("", truncate_front("", 3, "…"));
+        assert_eq!("some", truncate_front("some", 4, "…"));
+        assert_eq!("…ing", truncate_front("something", 4, "…"));
+        assert_eq!("...hing", truncate_front("something", 7, "..."));
+        assert_eq!("", truncate_front("something", 2, "..."));
+        assert_eq!("...", truncate_front("something", 3, "..."));
+        assert_eq!("…世界", truncate_front("你好世界", 5, "…"));
+        assert_eq!("… 界", truncate_front("你好世界", 4, "…"));
+
+        let s = "\u{1b}[31mred\u{1b}[1m bold\u{1b}[0m text";
+        assert_eq!(s, truncate_front(s, 13, "…"));
+        assert_eq!("…text", truncate_front(s, 5, "…"));
+        assert_eq!("…\u{1b}[1;31mold\u{1b}[0m text", truncate_front(s, 9, "…"));
+        assert_eq!(
+            "\u{1b}[34m…\u{1b}[39m\u{1b}[1;31mold\u{1b}[0m text",
+            truncate_front(s, 9, "\u{1b}[34m…\u{1b}[39m")
+        );
+
+        let s = "\u{1b}[31;40mWhen 你好\u{1b}[1m night 😀\u{1b}[0m has come";
+        let str_width = |s: &str| {
+            srip_ansi_sequences(s)
+                .chars()
+                .map(char_width)
+                .sum::<usize>()
+        };
+        for width in 1..30 {
+            let truncated = truncate_front(s, width, "…");
+            assert_eq!(width.min(str_width(s)), str_width(&truncated));
+            assert!(unclosed_styles(&truncated).is_empty(), "{:?}", truncated);
+        }
+    }
+
+    #[test]
+    fn align_test() {
+        assert_eq!("   ", align("", 3, Alignment::Left));
+        assert_eq!("", align("some", 0, Alignment::Left));
+        assert_eq!("some  ", align("some", 6, Alignment::Left));
+        assert_eq!("  some", align("some", 6, Alignment::Right));
+        assert_eq!(" some ", align("some", 6, Alignment::Center));
+        assert_eq!(" some  ", align("some", 7, Alignment::Center));
+        assert_eq!("some", align("some", 4, Alignment::Center));
+        assert_eq!("so", align("some", 2, Alignment::Right));
+        assert_eq!("s…", align_with("some", 2, Alignment::Right, "…"));
+        assert_eq!("some", align_with("some", 4, Alignment::Right, "…"));
+
+        assert_eq!(" 你好  ", align("你好", 7, Alignment::Center));
+        assert_eq!("你 ", align("你好", 3, Alignment::Center));
+        assert_eq!("你 …", align_with("你好世界", 4, Alignment::Left, "…"));
+
+        let s = "\u{1b}[31mred\u{1b}[1m bold\u{1b}[0m";
+        assert_eq!(format!(" {}  ", s), align(s, 11, Alignment::Center));
+        assert_eq!(
+            "\u{1b}[31mred\u{1b}[1m b\u{1b}[22;39m…",
+            align_with(s, 6, Alignment::Center, "…")
+        );
+
+        let s = "\u{1b}[31;40mWhen 你好\u{1b}[1m night 😀\u{1b}[0m has come";
+        for width in 0..40 {
+            for alignment in [Alignment::Left, Alignment::Center, Alignment::Right] {
+                for ellipsis in ["", "…"] {
+                    let aligned = align_with(s, width, alignment, ellipsis);
+                    let aligned_width = srip_ansi_sequences(&aligned)
+                        .chars()
+                        .map(char_width)
+                        .sum::<usize>();
+                    assert_eq!(width, aligned_width, "{:?}", aligned);
+                    assert!(unclosed_styles(&aligned).is_empty(), "{:?}", aligned);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn pad_test() {
+        assert_eq!("   ", pad_right("", 3, ' '));
+        assert_eq!("some", pad_right("some", 3, ' '));
+        assert_eq!("some", pad_right("some", 4, ' '));
+        assert_eq!("some--", pad_right("some", 6, '-'));
+        assert_eq!("--some", pad_left("some", 6, '-'));
+        assert_eq!("some", pad_left("some", 2, '-'));
+        assert_eq!("你好..", pad_right("你好", 6, '.'));
+        assert_eq!("a你你 ", pad_right("a", 6, '你'));
+        assert_eq!("你你 a", pad_left("a", 6, '你'));
+
+        let s = "\u{1b}[31;40mred\u{1b}[1m bold\u{1b}[0m";
+        assert_eq!(format!("{}  ", s), pad_right(s, 10, ' '));
+        assert_eq!(format!("  {}", s), pad_left(s, 10, ' '));
+        assert_eq!(
+            format!("{}\u{1b}[40m  \u{1b}[49m", s),
+            pad_right_with(s, 10, ' ', PadStyle::Background)
+        );
+        assert_eq!(
+            format!("\u{1b}[40m  \u{1b}[49m{}", s),
+            pad_left_with(s, 10, ' ', PadStyle::Background)
+        );
+        assert_eq!(
+            "\u{1b}[40m\u{1b}[0m   ",
+            pad_right_with("\u{1b}[40m\u{1b}[0m", 3, ' ', PadStyle::Background)
+        );
+
+        let s = "\u{1b}[31mred \u{1b}[44mblue\u{1b}[0m plain";
+        assert_eq!(
+            format!("{}  ", s),
+            pad_right_with(s, 16, ' ', PadStyle::Background)
+        );
+        assert_eq!(
+            format!("  {}", s),
+            pad_left_with(s, 16, ' ', PadStyle::Background)
+        );
+    }
+
+    #[test]
+    fn fit_test() {
+        let options = FitOptions::default;
+        assert_eq!("", fit("some", 3, 0, options()));
+        assert_eq!("\n", fit("some", 0, 2, options()));
+        assert_eq!("   \n   ", fit("", 3, 2, options()));
+        assert_eq!("som\n   ", fit("some", 3, 2, options()));
+        assert_eq!(
+            "so…\n---",
+            fit("some", 3, 2, options().ellipsis("…").fill('-'))
+        );
+        assert_eq!("a  \nb  ", fit("a\nb\nc\n", 3, 2, options()));
+        assert_eq!(
+            "b  \nc  ",
+            fit(
+                "a\nb\nc\n",
+                3,
+                2,
+                options().vertical(VerticalAlignment::Bottom)
+            )
+        );
+        assert_eq!(
+            "b  ",
+            fit(
+                "a\nb\nc\n",
+                3,
+                1,
+                options().vertical(VerticalAlignment::Middle)
+            )
+        );
+        assert_eq!(
+            "   \na  \n   \n   ",
+            fit("a", 3, 4, options().vertical(VerticalAlignment::Middle))
+        );
+        assert_eq!(
+            "   \n   \na  ",
+            fit("a", 3, 3, options().vertical(VerticalAlignment::Bottom))
+        );
+        assert_eq!("你好\n世界", fit("你好\r\n世界", 4, 2, options()));
+        assert_eq!("你 \n世 ", fit("你好世\n世界", 3, 2, options()));
+
+        let s = "\u{1b}[31mred\nred \u{1b}[1mbold\u{1b}[0m\nplain";
+        assert_eq!(
+            concat!(
+                "\u{1b}[31mred\u{1b}[39m   \n",
+                "\u{1b}[31mred \u{1b}[1mb\u{1b}[22;39m…\n",
+                "plain ",
+            ),
+            fit(s, 6, 3, options().ellipsis("…"))
+        );
+        assert_eq!(
+            concat!(
+                "\u{1b}[31mred   \n",
+                "\u{1b}[31mred \u{1b}[1mb…\n",
+                "plain ",
+            ),
+            fit(s, 6, 3, options().ellipsis("…").reset(ResetPolicy::None))
+        );
+
+        let s = "\u{1b}[31;40mWhen 你好\u{1b}[1m night\n😀\u{1b}[0m has\n\ncome";
+        for width in 0..20 {
+            for height in 0..6 {
+                let fitted = fit(s, width, height, options().ellipsis("…"));
+                let lines = fitted.split('\n').collect::<Vec<_>>();
+                assert_eq!(height.max(1), lines.len(), "{:?}", fitted);
+                for line in lines.iter().filter(|_| height > 0) {
+                    let w = srip_ansi_sequences(line)
+                        .chars()
+                        .map(char_width)
+                        .sum::<usize>();
+                    assert_eq!(width, w, "{:?}", fitted);
+                    assert!(unclosed_styles(line).is_empty(), "{:?}", line);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn columns_test() {
+        let empty: &[&str] = &[];
+        assert_eq!("", columns(empty, 10, 2));
+        assert_eq!("a  b  c", columns(&["a", "b", "c"], 10, 2));
+        assert_eq!("a  c\nb", columns(&["a", "b", "c"], 4, 2));
+        assert_eq!("a\nb\nc", columns(&["a", "b", "c"], 3, 2));
+        assert_eq!("abc\nd", columns(&["abcdef", "d"], 3, 2));
+        assert_eq!(
+            "aaaa  c\nb     dd",
+            columns(&["aaaa", "b", "c", "dd"], 9, 2)
+        );
+        assert_eq!("你好  b\na     c", columns(&["你好", "a", "b", "c"], 8, 2));
+        assert_eq!("你 \nb", columns(&["你好", "b"], 3, 2));
+
+        // styles are closed in every cell
+        let items = ["\u{1b}[31mred", "\u{1b}[1mbold\u{1b}[0m", "\u{1b}[44mx"];
+        assert_eq!(
+            "\u{1b}[31mred\u{1b}[39m   \u{1b}[44mx\u{1b}[49m\n\u{1b}[1mbold\u{1b}[0m",
+            columns(&items, 8, 2)
+        );
+
+        let items = [
+            "abc",
+            "你好世界",
+            "\u{1b}[31m😀\u{1b}[0m",
+            "a",
+            "bb",
+            "ccc",
+            "dddd",
+        ];
+        for total_width in 1..30 {
+            let laid = columns(&items, total_width, 1);
+            for line in laid.lines() {
+                let w = srip_ansi_sequences(line)
+                    .chars()
+                    .map(char_width)
+                    .sum::<usize>();
+                assert!(w <= total_width, "{:?}", laid);
+                assert!(unclosed_styles(line).is_empty(), "{:?}", line);
+            }
+        }
+    }
+
+    #[test]
+    fn concat_horizontal_test() {
+        assert_eq!("", concat_horizontal("", "", 1));
+        assert_eq!("a", concat_horizontal("a", "", 1));
+        assert_eq!(" b", concat_horizontal("", "b", 1));
+        assert_eq!("a  c\nbb d", concat_horizontal("a\nbb\n", "c\r\nd", 1));
+        assert_eq!("a  c\nbb\n   d", concat_horizontal("a\nbb", "c\n\nd", 1));
+        assert_eq!("你好 c\na", concat_horizontal("你好\na", "c", 1));
+
+        let left = "a\nb\nc\nd";
+        assert_eq!(
+            "a\nb x\nc\nd",
+            concat_horizontal_with(left, "x", 1, VerticalAlignment::Middle)
+        );
+        assert_eq!(
+            "a\nb x\nc y\nd",
+            concat_horizontal_with(left, "x\ny", 1, VerticalAlignment::Middle)
+        );
+        assert_eq!(
+            "  x\n  y\na z",
+            concat_horizontal_with("a", "x\ny\nz", 1, VerticalAlignment::Bottom)
+        );
+
+        let left = "\u{1b}[31mred\nred \u{1b}[44mblue";
+        let right = "\u{1b}[1mbold\nbold\u{1b}[0m";
+        assert_eq!(
+            concat!(
+                "\u{1b}[31mred\u{1b}[39m       \u{1b}[1mbold\u{1b}[22m\n",
+                "\u{1b}[31mred \u{1b}[44mblue\u{1b}[49;39m  \u{1b}[1mbold\u{1b}[0m",
+            ),
+            concat_horizontal(left, right, 2)
+        );
+    }
+
+    #[test]
+    fn expand_tabs_test() {
+        assert_eq!("", expand_tabs("", 4));
+        assert_eq!("    a", expand_tabs("\ta", 4));
+        assert_eq!("a   b", expand_tabs("a\tb", 4));
+        assert_eq!("abcd    e", expand_tabs("abcd\te", 4));
+        assert_eq!("ab      c", expand_tabs("ab\t\tc", 4));
+        assert_eq!("a   b\n    c", expand_tabs("a\tb\n\tc", 4));
+        assert_eq!("a   b\r\n    c", expand_tabs("a\tb\r\n\tc", 4));
+        assert_eq!("你  a", expand_tabs("你\ta", 4));
+        assert_eq!("a b", expand_tabs("a\tb", 1));
+
+        let s = "\u{1b}[4mab\u{1b}[24m\t\u{1b}[31mc\td\u{1b}[0m";
+        assert_eq!(
+            "\u{1b}[4mab\u{1b}[24m  \u{1b}[31mc   d\u{1b}[0m",
+            expand_tabs(s, 4)
+        );
+        assert_eq!(
+            "\u{1b}[4mab\u{1b}[24m  \u{1b}[31mc\u{1b}[39m   \u{1b}[31md\u{1b}[0m",
+            expand_tabs_with(s, 4, InsertStyle::Isolate)
+        );
+    }
+
+    #[test]
+    #[should_panic]
+    fn expand_tabs_panic_when_tab_width_is_zero() {
+        expand_tabs("a\tb", 0);
+    }
+
+    #[test]
+    #[should_panic]
+    fn expand_tabs_with_panic_when_tab_width_is_zero() {
+        expand_tabs_with("a\tb", 0, InsertStyle::Isolate);
+    }
+
+    #[test]
+    fn overlay_test() {
+        assert_eq!("ab", overlay("", "ab", 0));
+        assert_eq!("  ab", overlay("", "ab", 2));
+        assert_eq!("xy  ab", overlay("xy", "ab", 4));
+        assert_eq!("abcd", overlay("xycd", "ab", 0));
+        assert_eq!("xaby", overlay("xyzy", "ab", 1));
+        assert_eq!("xyzab", overlay("xyz", "ab", 3));
+        assert_eq!("xab", overlay("xyz", "ab", 1));
+        assert_eq!("xy", overlay("xy", "", 1));
+        assert_eq!("你 ab 界", overlay("你好世界", "ab", 3));
+        assert_eq!("你ab世界", overlay("你好世界", "ab", 2));
+        assert_eq!(" a好", overlay("你好", "a", 1));
+        assert_eq!("aX ", overlay("a中", "X", 1));
+        assert_eq!("X ", overlay("中", "X", 0));
+        assert_eq!(" X", overlay("中", "X", 1));
+        assert_eq!("中X ", overlay("中中", "X", 2));
+        assert_eq!("中 X", overlay("中中", "X", 3));
+        assert_eq!("中XYZ", overlay("中中", "XYZ", 2));
+        assert_eq!(
+            "\u{1b}[31ma\u{1b}[39mX ",
+            overlay("\u{1b}[31ma中\u{1b}[39m", "X", 1)
+        );
+
+        let base = "\u{1b}[31mred \u{1b}[44mblue\u{1b}[0m plain";
+        assert_eq!(
+            "\u{1b}[31mre\u{1b}[39m\u{1b}[1mXX\u{1b}[22m\u{1b}[31;44mblue\u{1b}[0m plain",
+            overlay(base, "\u{1b}[1mXX", 2)
+        );
+        assert_eq!(
+            "\u{1b}[31mred \u{1b}[44mb\u{1b}[49;39mXX\u{1b}[31;44me\u{1b}[0m plain",
+            overlay(base, "XX", 5)
+        );
+
+        // lines are drawn one over another
+        assert_eq!("aXc\ndY", overlay("abc\nde", "X\nY", 1));
+        assert_eq!("aXc\r\nde\n", overlay("abc\r\nde\n", "X", 1));
+        assert_eq!("aXc\n Y\n Z", overlay("abc\n", "X\nY\nZ", 1));
+        assert_eq!("aXc\n Y", overlay("abc", "X\nY", 1));
+        assert_eq!(" \n Y", overlay("", "\nY", 1));
+        assert_eq!(
+            "\u{1b}[31ma\u{1b}[39mX\n\u{1b}[31mc\u{1b}[39mY",
+            overlay("\u{1b}[31mab\ncd\u{1b}[39m", "X\nY", 1)
+        );
+
+        let base = "\u{1b}[31;40mWhen 你好\u{1b}[1m night 😀\u{1b}[0m has come";
+        let str_width = |s: &str| {
+            srip_ansi_sequences(s)
+                .chars()
+                .map(char_width)
+                .sum::<usize>()
+        };
+        for col in 0..30 {
+            for top in ["", "x", "\u{1b}[34mxyz", "世界"] {
+                let drawn = overlay(base, top, col);
+                let expected = str_width(base).max(col + str_width(top));
+                assert_eq!(expected, str_width(&drawn), "{:?}", drawn);
+                assert!(unclosed_styles(&drawn).is_empty(), "{:?}", drawn);
+            }
+        }
+    }
+
+    #[test]
+    fn pages_test() {
+        let s = "something\n\nmore\r\nand more";
+        assert_eq!(
+            vec!["some\nthin", "g\n", "more\nand ", "more"],
+            pages(s, 4, 2)
+        );
+        assert_eq!(vec!["something\n\nmore\nand more"], pages(s, 10, 10));
+        assert_eq!(Vec::<String>::new(), pages("", 10, 10));
+        assert_eq!(vec!["你\n好a"], pages("你好a", 3, 2));
+        assert_eq!(vec!["你\n好", "a"], pages("你好a", 2, 2));
+
+        // a single line which spans many pages
+        let s = "\u{1b}[31mWhen the night\u{1b}[0m";
+        let pages = pages(s, 2, 2);
+        assert_eq!(
+            vec![
+                "\u{1b}[31mWh\u{1b}[39m\n\u{1b}[31men\u{1b}[39m",
+                "\u{1b}[31m t\u{1b}[39m\n\u{1b}[31mhe\u{1b}[39m",
+                "\u{1b}[31m n\u{1b}[39m\n\u{1b}[31mig\u{1b}[39m",
+                "\u{1b}[31mht\u{1b}[0m",
+            ],
+            pages
+        );
+
+        // a page boundary in the middle of a style
+        let s = "a\u{1b}[1mb\nc\u{1b}[44md\ne\u{1b}[0mf";
+        let pages = super::pages(s, 10, 1);
+        assert_eq!(
+            vec![
+                "a\u{1b}[1mb\u{1b}[22m",
+                "\u{1b}[1mc\u{1b}[44md\u{1b}[49;22m",
+                "\u{1b}[1;44me\u{1b}[0mf",
+            ],
+            pages
+        );
+        for page in pages {
+            assert!(is_closed(&unclosed_styles(&page).state));
+        }
+    }
+}
@@ -17,14 +17,76 @@
 //!
 //! println!("{}", cutted_text);
 //! ```
+//!
+//! ## Untrusted input
+//!
+//! The functions of the crate keep escape sequences of a string,
+//! not only colors but also ones which move a cursor or set a window title.
+//! A string which comes from a user should be passed through [`sanitize`] first.
 
 use ansi_parser::AnsiSequence;
-use ansi_parser::{AnsiParser, Output};
-use std::ops::{Bound, RangeBounds};
+use std::borrow::Cow;
+use std::convert::TryFrom;
+use std::fmt;
+use std::io;
+use std::ops::{Bound, Range, RangeBounds};
+
+mod chunking;
+pub mod chunks;
+#[cfg(feature = "unicode-width")]
+mod layout;
+mod sanitize;
+
+#[cfg(feature = "unicode-segmentation")]
+pub use chunking::chunks_graphemes;
+pub use chunking::{
+    chunk_by, chunks, chunks_bytes_budget, chunks_exact, chunks_exact_with, chunks_indexed,
+    chunks_into, chunks_iter, chunks_with_style, chunks_words, rchunks, try_chunks, windows,
+    AnsiChunks, AnsiWindows, IndexedChunk,
+};
+#[cfg(feature = "terminal_size")]
+pub use chunking::{chunks_for_terminal, terminal_width};
+#[cfg(feature = "unicode-width")]
+pub use chunking::{chunks_width, chunks_width_with};
+#[cfg(feature = "unicode-width")]
+pub use layout::{
+    align, align_with, columns, concat_horizontal, concat_horizontal_with, expand_tabs,
+    expand_tabs_with, fit, justify, justify_with, overlay, pad_left, pad_left_with, pad_right,
+    pad_right_with, pages, truncate, truncate_front, truncate_middle, truncate_with, wrap,
+    wrap_with, Alignment, FitOptions, SuffixStyle, VerticalAlignment,
+};
+pub use sanitize::{contains_ansi, is_plain, sanitize, strip, strip_into};
 
 /// AnsiCut a trait to cut a string while keeping information
 /// about its color defined as ANSI control sequences.
+///
+/// An escape sequence which is cut off at the end of a string (like `"text \x1b[3"`)
+/// is dropped, so it doesn't eat the beginning of whatever is printed after a cut.
+/// It's the same for a lone `\x1b` at the end.
+///
+/// A `\x1b` which doesn't start a known sequence is dropped too, while chars after it are a text.
+/// A CSI sequence with more than 1024 bytes of parameters is not considered to be a sequence.
+///
+/// Single char C1 introducers of CSI (`\u{9b}`) and OSC (`\u{9d}`) sequences are recognized too,
+/// by default such sequences are written in their 7-bit form, see [`C1Policy`].
+///
+/// A cut which doesn't start at the beginning of a string keeps escape sequences met before it,
+/// except ones which make no difference to it:
+/// everything before the last `\x1b[0m` and styles which are overridden later.
 pub trait AnsiCut {
+    /// Returns the string which is cut.
+    ///
+    /// It's what all other methods work on,
+    /// so it's the only one which needs to be implemented.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use ansi_cut::AnsiCut;
+    /// assert_eq!(String::from("\u{1b}[31mWhen\u{1b}[39m").as_ansi_str(), "\u{1b}[31mWhen\u{1b}[39m");
+    /// ```
+    fn as_ansi_str(&self) -> &str;
+
     /// Cut string from the beginning of the range to the end.
     /// Preserving its colors.
     ///
@@ -33,6 +95,9 @@ pub trait AnsiCut {
     ///
     /// Exceeding an upper bound does not panic.
     ///
+    /// A range with no visible text in it may still give escape sequences around it,
+    /// use [`AnsiCut::cut_strict`] to get an empty string instead.
+    ///
     /// # Panics
     ///
     /// Panics if a start or end indexes are not on a UTF-8 code point boundary.
@@ -46,798 +111,8516 @@ pub trait AnsiCut {
     /// let cut_text = colored_text.cut(5..);
     /// println!("{}", cut_text);
     /// ```
-    fn cut<R>(&self, range: R) -> String
-    where
-        R: RangeBounds<usize>;
-}
-
-impl AnsiCut for &str {
     fn cut<R>(&self, range: R) -> String
     where
         R: RangeBounds<usize>,
     {
-        crate::cut(&self, range)
+        crate::cut(self.as_ansi_str(), range)
     }
-}
 
-impl AnsiCut for String {
-    fn cut<R>(&self, range: R) -> String
-    where
-        R: RangeBounds<usize>,
-    {
-        crate::cut(&self, range)
+    /// Returns the first `n` characters of the string not containing ANSI control sequences.
+    /// Preserving its colors.
+    ///
+    /// If `n` exceeds the number of characters the whole string is returned.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use owo_colors::{OwoColorize, colors::*};
+    /// use ansi_cut::AnsiCut;
+    /// let colored_text = "😀😃😄".fg::<Red>().to_string();
+    /// assert_eq!(colored_text.take_visible(2), "😀😃".fg::<Red>().to_string());
+    /// ```
+    fn take_visible(&self, n: usize) -> String {
+        crate::cut_chars(self.as_ansi_str(), ..n)
     }
-}
 
-/// Returns an Vec over chunk_size elements of string, starting at the beginning of the slice.
-/// It uses chars but not bytes!
-///
-/// The chunks are vectors and do not overlap.
-/// If chunk_size does not divide the length of the slice, then the last chunk will not have length chunk_size.
-///
-/// # Panics
-///
-/// Panics if chunk_size is 0.
-///
-/// # Examples
-///
-/// ```rust
-/// use owo_colors::{OwoColorize, colors::*};
-/// let colored_text = format!("{} {} {}", "A".fg::<Black>(), "Colored".fg::<Red>(), "Text".fg::<Blue>()).bg::<Yellow>().to_string();
-/// let chunks = ansi_cut::chunks(&colored_text, 3);
-/// for chunk in &chunks {
-///     println!("{}", chunk);
-/// }
-/// ```
-pub fn chunks(s: &str, chunk_size: usize) -> Vec<String> {
-    assert!(chunk_size > 0);
+    /// Returns the string without its first `n` characters not counting ANSI control sequences.
+    /// Preserving its colors.
+    ///
+    /// Escape sequences before the kept characters are not copied,
+    /// instead the style active at that point is set at the beginning.
+    ///
+    /// If `n` exceeds the number of characters an empty string is returned.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use owo_colors::{OwoColorize, colors::*};
+    /// use ansi_cut::AnsiCut;
+    /// let colored_text = "😀😃😄".fg::<Red>().to_string();
+    /// assert_eq!(colored_text.skip_visible(2), "😄".fg::<Red>().to_string());
+    /// ```
+    fn skip_visible(&self, n: usize) -> String {
+        crate::skip_visible(self.as_ansi_str(), n)
+    }
 
-    let stripped = srip_ansi_sequences(s);
-    let count_chars = stripped.chars().count();
-    let mut chunks = Vec::new();
-    let mut start_pos = 0;
+    /// Divides a string into two at an index.
+    /// Preserving its colors.
+    ///
+    /// The index is defined in terms of `byte`s of the string not containing ANSI
+    /// control sequences.
+    ///
+    /// Both parts can be rendered independently;
+    /// the first one has all its styles closed and the second one begins
+    /// with the style active at the index.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the index is not on a UTF-8 code point boundary.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use owo_colors::{OwoColorize, colors::*};
+    /// use ansi_cut::AnsiCut;
+    /// let colored_text = "Hello World".fg::<Red>().to_string();
+    /// let (left, right) = colored_text.split_at_visible(5);
+    /// assert_eq!(left, "Hello".fg::<Red>().to_string());
+    /// assert_eq!(right, " World".fg::<Red>().to_string());
+    /// ```
+    fn split_at_visible(&self, mid: usize) -> (String, String) {
+        crate::split_at_visible(self.as_ansi_str(), mid)
+    }
 
-    while start_pos < count_chars {
-        let start = stripped
-            .chars()
-            .map(|c| c.len_utf8())
-            .take(start_pos)
-            .sum::<usize>();
-        let end_pos = std::cmp::min(start_pos + chunk_size, count_chars);
-        let end = stripped
-            .chars()
-            .map(|c| c.len_utf8())
-            .take(end_pos)
-            .sum::<usize>();
-        let part = s.cut(start..end);
-        start_pos = end_pos;
-
-        if part.is_empty() {
-            break;
-        }
+    /// Divides a string into two at a display column.
+    /// Preserving its colors.
+    ///
+    /// The first part takes at most `cols` columns of the string not containing ANSI
+    /// control sequences. A wide character which is split by the column goes to the second part,
+    /// see [`AnsiCut::split_at_width_with`] to pad the first part in such case.
+    ///
+    /// Both parts can be rendered independently, like with [`AnsiCut::split_at_visible`].
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use owo_colors::{OwoColorize, colors::*};
+    /// use ansi_cut::AnsiCut;
+    /// let colored_text = "你好世界".fg::<Red>().to_string();
+    /// let (left, right) = colored_text.split_at_width(3);
+    /// assert_eq!(left, "你".fg::<Red>().to_string());
+    /// assert_eq!(right, "好世界".fg::<Red>().to_string());
+    /// ```
+    #[cfg(feature = "unicode-width")]
+    fn split_at_width(&self, cols: usize) -> (String, String) {
+        crate::split_at_width(self.as_ansi_str(), cols, WideCharPolicy::default())
+    }
 
-        chunks.push(part);
+    /// The same as [`AnsiCut::split_at_width`] but with a control of what happens
+    /// with a wide character which is split by the column.
+    ///
+    /// With [`WideCharPolicy::Pad`] the first part is padded with a space
+    /// so it always takes `cols` columns if the string is wide enough.
+    #[cfg(feature = "unicode-width")]
+    fn split_at_width_with(&self, cols: usize, policy: WideCharPolicy) -> (String, String) {
+        crate::split_at_width(self.as_ansi_str(), cols, policy)
     }
 
-    chunks
-}
+    /// Removes a range from the string and stitches the rest together.
+    /// Preserving its colors.
+    ///
+    /// Range is defined in terms of `byte`s of the string not containing ANSI
+    /// control sequences.
+    ///
+    /// Styles which are changed inside the removed range are switched at the seam,
+    /// so the text after the range looks the same as in the original string.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a start or end indexes are not on a UTF-8 code point boundary.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use owo_colors::{OwoColorize, colors::*};
+    /// use ansi_cut::AnsiCut;
+    /// let colored_text = format!("{}{}", "Hello".fg::<Red>(), " World".fg::<Blue>());
+    /// let text = colored_text.cut_out(2..6);
+    /// assert_eq!(text, "\u{1b}[31mHe\u{1b}[34mWorld\u{1b}[39m");
+    /// ```
+    fn cut_out<R>(&self, range: R) -> String
+    where
+        R: RangeBounds<usize>,
+    {
+        crate::cut_out(self.as_ansi_str(), range)
+    }
 
-// Bounds are byte index
-// It's not safe to go over grapheme boundres.
-fn cut<S, R>(string: S, bounds: R) -> String
-where
-    S: AsRef<str>,
-    R: RangeBounds<usize>,
-{
-    let string = string.as_ref();
-    let (start, end) = bounds_to_usize(bounds.start_bound(), bounds.end_bound());
+    /// Replaces a range of the string with a given string.
+    /// Preserving its colors.
+    ///
+    /// Range is defined in terms of `byte`s of the string not containing ANSI
+    /// control sequences.
+    ///
+    /// The replacement may contain ANSI control sequences itself.
+    /// It inherits the style active at the start of the range,
+    /// and after it the style is switched back so the rest of the string looks as before.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a start or end indexes are not on a UTF-8 code point boundary.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use owo_colors::{OwoColorize, colors::*};
+    /// use ansi_cut::AnsiCut;
+    /// let colored_text = "Hello World".fg::<Red>().to_string();
+    /// let text = colored_text.replace_range_visible(6.., &"There".fg::<Blue>().to_string());
+    /// assert_eq!(text, "\u{1b}[31mHello \u{1b}[34mThere\u{1b}[39m");
+    /// ```
+    fn replace_range_visible<R>(&self, range: R, replacement: &str) -> String
+    where
+        R: RangeBounds<usize>,
+    {
+        crate::replace_range_visible(self.as_ansi_str(), range, replacement)
+    }
 
-    cut_str(string, start, end)
-}
+    /// Inserts a string at a character index.
+    /// Preserving its colors.
+    ///
+    /// The index is defined in terms of `char`s of the string not containing ANSI
+    /// control sequences. If it exceeds the number of characters the text is appended.
+    ///
+    /// The inserted text inherits the style active at the index,
+    /// see [`AnsiCut::insert_str_visible_with`] to isolate it.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use owo_colors::{OwoColorize, colors::*};
+    /// use ansi_cut::AnsiCut;
+    /// let colored_text = "Hello World".fg::<Red>().to_string();
+    /// let text = colored_text.insert_str_visible(5, ",");
+    /// assert_eq!(text, "Hello, World".fg::<Red>().to_string());
+    /// ```
+    fn insert_str_visible(&self, index: usize, text: &str) -> String {
+        crate::insert_str_visible(self.as_ansi_str(), index, text, InsertStyle::Inherit)
+    }
 
-fn cut_str(string: &str, lower_bound: usize, upper_bound: Option<usize>) -> String {
-    let mut asci_state = AnsiState::default();
-    let tokens = string.ansi_parse();
-    let mut buf = String::new();
-    let mut index = 0;
+    /// The same as [`AnsiCut::insert_str_visible`] but with a control
+    /// of the style the inserted text gets.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use owo_colors::{OwoColorize, colors::*};
+    /// use ansi_cut::{AnsiCut, InsertStyle};
+    /// let colored_text = "Hello World".fg::<Red>().to_string();
+    /// let text = colored_text.insert_str_visible_with(5, "│", InsertStyle::Isolate);
+    /// assert_eq!(text, format!("{}│{}", "Hello".fg::<Red>(), " World".fg::<Red>()));
+    /// ```
+    fn insert_str_visible_with(&self, index: usize, text: &str, style: InsertStyle) -> String {
+        crate::insert_str_visible(self.as_ansi_str(), index, text, style)
+    }
 
-    '_tokens_loop: for token in tokens {
-        match token {
-            Output::TextBlock(text) => {
-                let block_end_index = index + text.len();
-                if lower_bound > block_end_index {
-                    index += text.len();
-                    continue;
-                };
+    /// Returns the last `n` characters of the string not containing ANSI control sequences.
+    /// Preserving its colors.
+    ///
+    /// Escape sequences before the characters are not copied,
+    /// instead the style active at that point is set at the beginning.
+    ///
+    /// If `n` exceeds the number of characters the whole string is returned.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use owo_colors::{OwoColorize, colors::*};
+    /// use ansi_cut::AnsiCut;
+    /// let colored_text = format!("{} {}", "Hello".fg::<Red>(), "World".fg::<Blue>());
+    /// assert_eq!(colored_text.tail_visible(3), "rld".fg::<Blue>().to_string());
+    /// ```
+    fn tail_visible(&self, n: usize) -> String {
+        crate::tail_visible(self.as_ansi_str(), n)
+    }
 
-                let mut start = 0;
-                if lower_bound > index {
-                    start = lower_bound - index;
-                }
+    /// The same as [`AnsiCut::cut`] but returns an error instead of panicking.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a start or end indexes are not on a UTF-8 code point boundary,
+    /// or if a start index is greater than an end index.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use ansi_cut::{AnsiCut, AnsiCutError};
+    /// assert_eq!("😀😃".try_cut(4..), Ok(String::from("😃")));
+    /// assert_eq!(
+    ///     "😀😃".try_cut(1..),
+    ///     Err(AnsiCutError::NotACharBoundary { index: 1, ch: '😀', prev_boundary: 0, next_boundary: 4 }),
+    /// );
+    /// ```
+    fn try_cut<R>(&self, range: R) -> Result<String, AnsiCutError>
+    where
+        R: RangeBounds<usize>,
+    {
+        crate::try_cut(self.as_ansi_str(), range)
+    }
 
-                let mut end = text.len();
-                let mut done = false;
-                if let Some(upper_bound) = upper_bound {
-                    if upper_bound > index && upper_bound < block_end_index {
-                        end = upper_bound - index;
-                        done = true;
-                    }
-                }
+    /// The same as [`AnsiCut::cut_with`] but returns an error instead of panicking.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a start or end indexes are not on a UTF-8 code point boundary,
+    /// if a start index is greater than an end index,
+    /// or if an index splits a character from its combining marks with [`GraphemePolicy::Error`].
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use ansi_cut::{AnsiCut, AnsiCutError, CutOptions, GraphemePolicy};
+    /// let options = CutOptions::default().grapheme(GraphemePolicy::Error);
+    /// assert_eq!("e\u{301}x".try_cut_with(3.., options), Ok(String::from("x")));
+    /// assert_eq!(
+    ///     "e\u{301}x".try_cut_with(1.., options),
+    ///     Err(AnsiCutError::NotAGraphemeBoundary { index: 1 }),
+    /// );
+    /// ```
+    fn try_cut_with<R>(&self, range: R, options: CutOptions) -> Result<String, AnsiCutError>
+    where
+        R: RangeBounds<usize>,
+    {
+        crate::try_cut_with(self.as_ansi_str(), range, options)
+    }
 
-                index += text.len();
+    /// The same as [`AnsiCut::cut`] but instead of panicking it snaps indexes
+    /// which are not on a UTF-8 code point boundary.
+    ///
+    /// A start index is rounded up to the next boundary and an end index is rounded down
+    /// to the previous one, so a partially covered character is never included.
+    /// Indexes are snapped in terms of the string not containing ANSI control sequences.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use ansi_cut::AnsiCut;
+    /// assert_eq!("😀😃😄".cut_lossy(1..9), "😃");
+    /// assert_eq!("😀".cut_lossy(1..3), "");
+    /// ```
+    fn cut_lossy<R>(&self, range: R) -> String
+    where
+        R: RangeBounds<usize>,
+    {
+        crate::cut_lossy(self.as_ansi_str(), range)
+    }
 
-                match text.get(start..end) {
-                    Some(text) => {
-                        buf.push_str(text);
-                        if done {
-                            break '_tokens_loop;
-                        }
-                    }
-                    None => {
-                        panic!("One of indexes are not on a UTF-8 code point boundary");
-                    }
-                }
-            }
-            Output::Escape(seq) => {
-                let seq_str = seq.to_string();
-                buf.push_str(&seq_str);
-                if let AnsiSequence::SetGraphicsMode(v) = seq {
-                    update_ansi_state(&mut asci_state, v.as_ref());
-                }
-            }
-        }
+    /// The same as [`AnsiCut::cut`] but it borrows the string when it's possible.
+    ///
+    /// A cut is borrowed when it's a contiguous part of the original string
+    /// and there's no style left open which needs to be closed,
+    /// for example when the string has no ANSI control sequences.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a start or end indexes are not on a UTF-8 code point boundary.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use std::borrow::Cow;
+    /// use owo_colors::{OwoColorize, colors::*};
+    /// use ansi_cut::AnsiCut;
+    ///
+    /// assert!(matches!("When the night has come".cut_cow(5..), Cow::Borrowed("the night has come")));
+    ///
+    /// let colored_text = "When the night has come".fg::<Red>().to_string();
+    /// assert!(matches!(colored_text.cut_cow(..), Cow::Borrowed(_)));
+    /// assert!(matches!(colored_text.cut_cow(5..), Cow::Owned(_)));
+    /// ```
+    fn cut_cow<R>(&self, range: R) -> Cow<'_, str>
+    where
+        R: RangeBounds<usize>,
+    {
+        crate::cut_cow(self.as_ansi_str(), range)
     }
 
-    complete_ansi_sequences(&asci_state, &mut buf);
+    /// Returns a lazy cut of the string which is made only when it's displayed.
+    ///
+    /// It prints the same as [`AnsiCut::cut`] returns but without allocating a `String`.
+    ///
+    /// # Panics
+    ///
+    /// Displaying the cut panics if a start or end indexes are not on a UTF-8 code point boundary.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use owo_colors::{OwoColorize, colors::*};
+    /// use ansi_cut::AnsiCut;
+    /// let colored_text = "When the night has come".fg::<Red>().to_string();
+    /// let cut = colored_text.cut_lazy(5..);
+    /// println!("{}", cut);
+    /// assert_eq!(cut.to_string(), colored_text.cut(5..));
+    /// ```
+    fn cut_lazy<R>(&self, range: R) -> Cut<'_, R>
+    where
+        R: RangeBounds<usize>,
+    {
+        Cut {
+            text: self.as_ansi_str(),
+            range,
+        }
+    }
 
-    buf
+    /// The same as [`AnsiCut::cut`] but escape sequences before the range are not copied,
+    /// instead a style active at the beginning of the range is opened.
+    ///
+    /// Sequences which look like SGR but the crate doesn't know what they do,
+    /// like `\x1b[>4;2m`, are replayed as they were written unless a full reset follows them.
+    ///
+    /// A cut which has nothing in the range is an empty string.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a start or end indexes are not on a UTF-8 code point boundary.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use ansi_cut::AnsiCut;
+    /// let text = "\u{1b}[1mWhen \u{1b}[31mthe \u{1b}[44mnight\u{1b}[0m";
+    /// assert_eq!(text.cut(9..11), "\u{1b}[1m\u{1b}[31m\u{1b}[44mni\u{1b}[49;39;22m");
+    /// assert_eq!(text.cut_minimal(9..11), "\u{1b}[1;31;44mni\u{1b}[49;39;22m");
+    /// ```
+    fn cut_minimal<R>(&self, range: R) -> String
+    where
+        R: RangeBounds<usize>,
+    {
+        crate::cut_with(
+            self.as_ansi_str(),
+            range,
+            CutOptions::default().minimal(true),
+        )
+    }
+
+    /// The same as [`AnsiCut::cut`] but with options which control how the cut is made.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a start or end indexes are not on a UTF-8 code point boundary.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use ansi_cut::{AnsiCut, CutOptions, ResetPolicy};
+    /// let text = "\u{1b}[1;31mWhen the night\u{1b}[0m";
+    /// let options = CutOptions::default().reset(ResetPolicy::ReuseOriginal);
+    /// assert_eq!(text.cut_with(..4, options), "\u{1b}[1;31mWhen\u{1b}[0m");
+    /// ```
+    fn cut_with<R>(&self, range: R, options: CutOptions) -> String
+    where
+        R: RangeBounds<usize>,
+    {
+        crate::cut_with(self.as_ansi_str(), range, options)
+    }
+
+    /// The same as [`AnsiCut::cut`] but a cut which has no visible text in it
+    /// is an empty string instead of bare escape sequences.
+    ///
+    /// It's the case for empty ranges and for ranges which contain only escape sequences.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a start or end indexes are not on a UTF-8 code point boundary.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use ansi_cut::AnsiCut;
+    /// let text = "\u{1b}[40mTEXT\u{1b}[49m";
+    /// assert_eq!(text.cut(3..3), "\u{1b}[40m\u{1b}[49m");
+    /// assert_eq!(text.cut_strict(3..3), "");
+    /// assert_eq!(text.cut_strict(4..), "");
+    /// assert_eq!(text.cut_strict(3..), "\u{1b}[40mT\u{1b}[49m");
+    /// ```
+    fn cut_strict<R>(&self, range: R) -> String
+    where
+        R: RangeBounds<usize>,
+    {
+        crate::cut_with(
+            self.as_ansi_str(),
+            range,
+            CutOptions::default().strict(true),
+        )
+    }
+
+    /// The same as [`AnsiCut::cut`] but styles are left open at the end of the cut,
+    /// instead a style active at the end of the cut is returned.
+    ///
+    /// It's useful when cuts are joined together,
+    /// the style can be used to reopen or close them when it's needed.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a start or end indexes are not on a UTF-8 code point boundary.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use owo_colors::{OwoColorize, colors::*};
+    /// use ansi_cut::AnsiCut;
+    /// let colored_text = "When the night has come".fg::<Red>().to_string();
+    /// let (cut_text, style) = colored_text.cut_open(..4);
+    /// assert_eq!(cut_text, "\u{1b}[31mWhen");
+    /// assert_eq!(style.to_string(), "\u{1b}[31m");
+    /// ```
+    fn cut_open<R>(&self, range: R) -> (String, Style)
+    where
+        R: RangeBounds<usize>,
+    {
+        crate::cut_open(self.as_ansi_str(), range)
+    }
+
+    /// Cut string from the beginning of the range to the end.
+    /// Preserving its colors.
+    ///
+    /// Unlike [`AnsiCut::cut`] the range is defined in terms of `char`s of the string
+    /// not containing ANSI control sequences.
+    ///
+    /// Exceeding an upper bound does not panic.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use owo_colors::{OwoColorize, colors::*};
+    /// use ansi_cut::AnsiCut;
+    /// let colored_text = "😀😃😄".fg::<Red>().to_string();
+    /// let cut_text = colored_text.cut_chars(1..2);
+    /// assert_eq!(cut_text, "😃".fg::<Red>().to_string());
+    /// ```
+    fn cut_chars<R>(&self, range: R) -> String
+    where
+        R: RangeBounds<usize>,
+    {
+        crate::cut_chars(self.as_ansi_str(), range)
+    }
+
+    /// Cut string from the beginning of the range to the end.
+    /// Preserving its colors.
+    ///
+    /// The range is defined in terms of display columns of the string not containing ANSI
+    /// control sequences. Wide characters (e.g. CJK) take 2 columns and zero width characters
+    /// (e.g. combining marks) take none.
+    ///
+    /// A wide character which is split by a bound is excluded from the result,
+    /// see [`AnsiCut::cut_width_with`] to change it.
+    ///
+    /// Exceeding an upper bound does not panic.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use owo_colors::{OwoColorize, colors::*};
+    /// use ansi_cut::AnsiCut;
+    /// let colored_text = "你好世界".fg::<Red>().to_string();
+    /// assert_eq!(colored_text.cut_width(2..6), "好世".fg::<Red>().to_string());
+    /// assert_eq!(colored_text.cut_width(1..5), "好".fg::<Red>().to_string());
+    /// ```
+    #[cfg(feature = "unicode-width")]
+    fn cut_width<R>(&self, range: R) -> String
+    where
+        R: RangeBounds<usize>,
+    {
+        crate::cut_width(self.as_ansi_str(), range, WideCharPolicy::default())
+    }
+
+    /// The same as [`AnsiCut::cut_width`] but with a control of what happens
+    /// with wide characters which are split by a bound.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use owo_colors::{OwoColorize, colors::*};
+    /// use ansi_cut::{AnsiCut, WideCharPolicy};
+    /// let colored_text = "你好世界".fg::<Red>().to_string();
+    /// assert_eq!(
+    ///     colored_text.cut_width_with(1..5, WideCharPolicy::Pad),
+    ///     format!(" {} ", "好".fg::<Red>()),
+    /// );
+    /// ```
+    #[cfg(feature = "unicode-width")]
+    fn cut_width_with<R>(&self, range: R, policy: WideCharPolicy) -> String
+    where
+        R: RangeBounds<usize>,
+    {
+        crate::cut_width(self.as_ansi_str(), range, policy)
+    }
+
+    /// Cut string from the beginning of the range to the end.
+    /// Preserving its colors.
+    ///
+    /// The range is defined in terms of extended grapheme clusters of the string
+    /// not containing ANSI control sequences.
+    /// So emojies joined by ZWJ, flags and characters with combining marks are never split.
+    ///
+    /// Exceeding an upper bound does not panic.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use owo_colors::{OwoColorize, colors::*};
+    /// use ansi_cut::AnsiCut;
+    /// let colored_text = "👨‍👩‍👧‍👦🇺🇦e\u{301}".fg::<Red>().to_string();
+    /// assert_eq!(colored_text.cut_graphemes(1..), "🇺🇦e\u{301}".fg::<Red>().to_string());
+    /// ```
+    #[cfg(feature = "unicode-segmentation")]
+    fn cut_graphemes<R>(&self, range: R) -> String
+    where
+        R: RangeBounds<usize>,
+    {
+        crate::cut_graphemes(self.as_ansi_str(), range)
+    }
+
+    /// Returns an Vec over chunk_size elements of string, starting at the beginning of the slice.
+    /// It uses chars but not bytes!
+    ///
+    /// The same as [`chunks()`](crate::chunks()).
+    ///
+    /// # Panics
+    ///
+    /// Panics if chunk_size is 0.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use owo_colors::{OwoColorize, colors::*};
+    /// use ansi_cut::AnsiCut;
+    /// let colored_text = "When the night".fg::<Red>().to_string();
+    /// assert_eq!(colored_text.chunks(5), ansi_cut::chunks(&colored_text, 5));
+    /// ```
+    fn chunks(&self, chunk_size: usize) -> Vec<String> {
+        crate::chunks(self.as_ansi_str(), chunk_size)
+    }
 }
 
-#[derive(Debug, Clone, Default)]
-struct AnsiState {
-    fg_color: Option<AnsiColor>,
-    bg_color: Option<AnsiColor>,
-    undr_color: Option<AnsiColor>,
-    bold: bool,
-    faint: bool,
-    italic: bool,
-    underline: bool,
-    double_underline: bool,
-    slow_blink: bool,
-    rapid_blink: bool,
-    inverse: bool,
-    hide: bool,
-    crossedout: bool,
-    reset: bool,
-    framed: bool,
-    encircled: bool,
-    font: Option<u8>,
-    fraktur: bool,
-    proportional_spacing: bool,
-    overlined: bool,
-    igrm_underline: bool,
-    igrm_double_underline: bool,
-    igrm_overline: bool,
-    igrm_double_overline: bool,
-    igrm_stress_marking: bool,
-    superscript: bool,
-    subscript: bool,
-    unknown: bool,
+impl AnsiCut for &str {
+    fn as_ansi_str(&self) -> &str {
+        self
+    }
+}
+
+impl AnsiCut for String {
+    fn as_ansi_str(&self) -> &str {
+        self
+    }
 }
 
+/// An error which can be returned when cutting a string.
 #[derive(Debug, Clone, PartialEq, Eq)]
-enum AnsiColor {
-    Bit4 { index: u8 },
-    Bit8 { index: u8 },
-    Bit24 { r: u8, g: u8, b: u8 },
+pub enum AnsiCutError {
+    /// An index is not on a UTF-8 code point boundary.
+    ///
+    /// Indexes are defined in terms of the string not containing ANSI control sequences.
+    NotACharBoundary {
+        /// The offending index.
+        index: usize,
+        /// The char the index falls inside.
+        ch: char,
+        /// The nearest valid boundary before the index.
+        prev_boundary: usize,
+        /// The nearest valid boundary after the index.
+        next_boundary: usize,
+    },
+    /// A start of a range is greater than its end.
+    StartAfterEnd {
+        /// A start of the range.
+        start: usize,
+        /// An end of the range.
+        end: usize,
+    },
+    /// An index splits a character from its combining marks or a ZWJ sequence,
+    /// see [`GraphemePolicy`].
+    ///
+    /// Indexes are defined in terms of the string not containing ANSI control sequences.
+    NotAGraphemeBoundary {
+        /// The offending index.
+        index: usize,
+    },
+    /// A chunk size is 0.
+    ZeroChunkSize,
+    /// A char together with escape sequences it requires doesn't fit in a byte budget.
+    BudgetTooSmall {
+        /// A char index of the char in the string not containing ANSI control sequences.
+        index: usize,
+        /// The byte budget.
+        max_bytes: usize,
+    },
 }
 
-fn update_ansi_state(state: &mut AnsiState, mode: &[u8]) {
-    let mut ptr = mode;
-    loop {
-        if ptr.is_empty() {
-            break;
+impl std::fmt::Display for AnsiCutError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AnsiCutError::NotACharBoundary {
+                index,
+                ch,
+                prev_boundary,
+                next_boundary,
+            } => write!(
+                f,
+                "index {} is not on a UTF-8 code point boundary, it's inside {:?} (valid boundaries: {}, {})",
+                index, ch, prev_boundary, next_boundary
+            ),
+            AnsiCutError::StartAfterEnd { start, end } => {
+                write!(f, "range start {} is greater than its end {}", start, end)
+            }
+            AnsiCutError::NotAGraphemeBoundary { index } => write!(
+                f,
+                "index {} splits a character from its combining marks",
+                index
+            ),
+            AnsiCutError::ZeroChunkSize => write!(f, "chunk size is 0"),
+            AnsiCutError::BudgetTooSmall { index, max_bytes } => write!(
+                f,
+                "char {} with its escape sequences doesn't fit in {} bytes",
+                index, max_bytes
+            ),
         }
+    }
+}
 
-        let tag = ptr[0];
+impl std::error::Error for AnsiCutError {}
 
-        match tag {
-            0 => {
-                *state = AnsiState::default();
-                state.reset = true;
-            }
-            1 => state.bold = true,
-            2 => state.faint = true,
-            3 => state.italic = true,
-            4 => state.underline = true,
-            5 => state.slow_blink = true,
-            6 => state.rapid_blink = true,
-            7 => state.inverse = true,
-            8 => state.hide = true,
-            9 => state.crossedout = true,
-            10 => state.font = None,
-            n @ 11..=19 => state.font = Some(n),
-            20 => state.fraktur = true,
-            21 => state.double_underline = true,
-            22 => {
-                state.faint = false;
-                state.bold = false;
-            }
-            23 => {
-                state.italic = false;
-            }
-            24 => {
-                state.underline = false;
-                state.double_underline = false;
-            }
-            25 => {
-                state.slow_blink = false;
-                state.rapid_blink = false;
-            }
-            26 => {
-                state.proportional_spacing = true;
-            }
-            28 => {
-                state.inverse = false;
-            }
-            29 => {
-                state.crossedout = false;
-            }
-            n @ 30..=37 | n @ 90..=97 => {
-                state.fg_color = Some(AnsiColor::Bit4 { index: n });
-            }
-            38 => {
-                if let Some((color, n)) = parse_ansi_color(ptr) {
-                    state.fg_color = Some(color);
-                    ptr = &ptr[n..];
-                }
-            }
-            39 => {
-                state.fg_color = None;
-            }
-            n @ 40..=47 | n @ 100..=107 => {
-                state.bg_color = Some(AnsiColor::Bit4 { index: n });
-            }
-            48 => {
-                if let Some((color, n)) = parse_ansi_color(ptr) {
-                    state.bg_color = Some(color);
-                    ptr = &ptr[n..];
-                }
-            }
-            49 => {
-                state.bg_color = None;
-            }
-            50 => {
-                state.proportional_spacing = false;
-            }
-            51 => {
-                state.framed = true;
-            }
-            52 => {
-                state.encircled = true;
-            }
-            53 => {
-                state.overlined = true;
-            }
-            54 => {
-                state.encircled = false;
-                state.framed = false;
-            }
-            55 => {
-                state.overlined = false;
-            }
-            58 => {
-                if let Some((color, n)) = parse_ansi_color(ptr) {
-                    state.undr_color = Some(color);
-                    ptr = &ptr[n..];
+/// InsertStyle defines which style an inserted text gets.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum InsertStyle {
+    /// The text inherits the style active at the place it's inserted.
+    #[default]
+    Inherit,
+    /// The style active at the place is closed before the text and reopened after it.
+    Isolate,
+}
+
+/// CutOptions is a set of options which define how a string is cut.
+///
+/// # Examples
+///
+/// ```rust
+/// use ansi_cut::{AnsiCut, CutOptions, ResetPolicy};
+/// let options = CutOptions::default().reset(ResetPolicy::FullReset);
+/// assert_eq!("\u{1b}[1;31mWhen the night".cut_with(..4, options), "\u{1b}[1;31mWhen\u{1b}[0m");
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CutOptions {
+    minimal: bool,
+    strict: bool,
+    drop_osc: bool,
+    reset: ResetPolicy,
+    unknown: UnknownPolicy,
+    c1: C1Policy,
+    control: ControlPolicy,
+    grapheme: GraphemePolicy,
+    count_controls: bool,
+    // a bit per C0 control char which counts toward indexes even if count_controls is false
+    counted_controls: u32,
+}
+
+impl Default for CutOptions {
+    fn default() -> Self {
+        Self {
+            minimal: false,
+            strict: false,
+            drop_osc: false,
+            reset: ResetPolicy::default(),
+            unknown: UnknownPolicy::default(),
+            c1: C1Policy::default(),
+            control: ControlPolicy::default(),
+            grapheme: GraphemePolicy::default(),
+            count_controls: true,
+            counted_controls: 1 << b'\t',
+        }
+    }
+}
+
+impl CutOptions {
+    /// Sets whether escape sequences before a range are dropped and
+    /// only a style active at its beginning is opened, see [`AnsiCut::cut_minimal`].
+    pub fn minimal(mut self, minimal: bool) -> Self {
+        self.minimal = minimal;
+        self
+    }
+
+    /// Sets whether a cut with no visible text in it is an empty string,
+    /// see [`AnsiCut::cut_strict`].
+    pub fn strict(mut self, strict: bool) -> Self {
+        self.strict = strict;
+        self
+    }
+
+    /// Sets how styles left open at the end of a cut are closed.
+    pub fn reset(mut self, policy: ResetPolicy) -> Self {
+        self.reset = policy;
+        self
+    }
+
+    /// Sets what's done with SGR codes which are not known, see [`UnknownPolicy`].
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use ansi_cut::{AnsiCut, CutOptions, UnknownPolicy};
+    /// let text = "\u{1b}[31mWhen\u{1b}[56m the night";
+    /// let options = CutOptions::default().unknown(UnknownPolicy::IgnoreUnknown);
+    /// assert_eq!(text.cut_with(..6, options), "\u{1b}[31mWhen\u{1b}[56m t\u{1b}[39m");
+    /// assert_eq!(text.cut(..6), "\u{1b}[31mWhen\u{1b}[56m t\u{1b}[39m\u{1b}[0m");
+    /// ```
+    pub fn unknown(mut self, policy: UnknownPolicy) -> Self {
+        self.unknown = policy;
+        self
+    }
+
+    /// Sets whether OSC sequences (like `\x1b]0;title\x07` which sets a window title) are dropped.
+    ///
+    /// Hyperlinks are kept either way.
+    /// By default OSC sequences are passed through.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use ansi_cut::{AnsiCut, CutOptions};
+    /// let text = "When\u{1b}]0;title\u{7} the night";
+    /// assert_eq!(text.cut_with(2..6, CutOptions::default()), "en\u{1b}]0;title\u{7} t");
+    /// assert_eq!(text.cut_with(2..6, CutOptions::default().drop_osc(true)), "en t");
+    /// ```
+    pub fn drop_osc(mut self, drop: bool) -> Self {
+        self.drop_osc = drop;
+        self
+    }
+
+    /// Sets how C1 sequences (like `\u{9b}31m`) are written, see [`C1Policy`].
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use ansi_cut::{AnsiCut, C1Policy, CutOptions};
+    /// let text = "\u{9b}31mWhen the night";
+    /// let options = CutOptions::default().c1(C1Policy::Keep);
+    /// assert_eq!(text.cut_with(..4, options), "\u{9b}31mWhen\u{1b}[39m");
+    /// assert_eq!(text.cut(..4), "\u{1b}[31mWhen\u{1b}[39m");
+    /// ```
+    pub fn c1(mut self, policy: C1Policy) -> Self {
+        self.c1 = policy;
+        self
+    }
+
+    /// Sets what's done with control sequences like cursor movements, see [`ControlPolicy`].
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use ansi_cut::{AnsiCut, ControlPolicy, CutOptions};
+    /// let text = "Name:\u{1b}[3CJohn\u{1b}[K";
+    /// let options = |policy| CutOptions::default().control(policy);
+    /// assert_eq!(text.cut_with(..7, options(ControlPolicy::Preserve)), "Name:\u{1b}[3CJo");
+    /// assert_eq!(text.cut_with(..7, options(ControlPolicy::Strip)), "Name:Jo");
+    /// assert_eq!(text.cut_with(..7, options(ControlPolicy::Normalize)), "Name:   Jo");
+    /// ```
+    pub fn control(mut self, policy: ControlPolicy) -> Self {
+        self.control = policy;
+        self
+    }
+
+    /// Sets what happens when an index splits a character from its combining marks,
+    /// see [`GraphemePolicy`].
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use ansi_cut::{AnsiCut, CutOptions, GraphemePolicy};
+    /// let text = "Cafe\u{301} au lait";
+    /// let options = |policy| CutOptions::default().grapheme(policy);
+    /// assert_eq!(text.cut_with(..4, options(GraphemePolicy::Allow)), "Cafe");
+    /// assert_eq!(text.cut_with(..4, options(GraphemePolicy::Snap)), "Cafe\u{301}");
+    /// assert!(text.try_cut_with(..4, options(GraphemePolicy::Error)).is_err());
+    /// ```
+    pub fn grapheme(mut self, policy: GraphemePolicy) -> Self {
+        self.grapheme = policy;
+        self
+    }
+
+    /// Sets whether C0 control chars (like `\r` or `\x07`) count toward indexes.
+    ///
+    /// By default they do, like any other char.
+    /// If they don't, they take no place in the string indexes are defined in,
+    /// but they are still copied to a cut at their position like escape sequences are.
+    /// Chars set by [`CutOptions::counted_controls`] are counted either way, it's only `\t` by default.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use ansi_cut::{AnsiCut, CutOptions};
+    /// let text = "When\r the night";
+    /// assert_eq!(text.cut_with(..6, CutOptions::default()), "When\r ");
+    /// assert_eq!(text.cut_with(..6, CutOptions::default().count_controls(false)), "When\r t");
+    /// ```
+    pub fn count_controls(mut self, count: bool) -> Self {
+        self.count_controls = count;
+        self
+    }
+
+    /// Sets C0 control chars which count toward indexes even if [`CutOptions::count_controls`] is false.
+    ///
+    /// Chars which are not C0 controls are ignored as they are always counted.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use ansi_cut::{AnsiCut, CutOptions};
+    /// let text = "a\tb\nc";
+    /// let options = CutOptions::default().count_controls(false);
+    /// assert_eq!(text.cut_with(1..3, options), "\tb\n");
+    /// assert_eq!(text.cut_with(1..3, options.counted_controls(&['\n'])), "b\n");
+    /// ```
+    pub fn counted_controls(mut self, controls: &[char]) -> Self {
+        self.counted_controls = controls
+            .iter()
+            .filter(|&&c| c < ' ')
+            .fold(0, |mask, &c| mask | 1 << c as u32);
+        self
+    }
+
+    // Checks whether a char counts toward indexes.
+    fn is_counted(&self, c: char) -> bool {
+        self.count_controls || c >= ' ' || self.counted_controls & (1 << c as u32) != 0
+    }
+}
+
+/// ResetPolicy defines how styles which are left open at the end of a cut are closed.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum ResetPolicy {
+    /// Each style is closed by its own parameter in reverse order they were opened,
+    /// all of them in a single sequence, e.g. `\x1b[39;22m` for `\x1b[1;31m`.
+    #[default]
+    PerAttribute,
+    /// All styles are closed by a single `\x1b[0m`.
+    FullReset,
+    /// Styles are left open.
+    None,
+    /// The escape sequence which follows the cut in the original string is reused
+    /// if it closes all the styles, e.g. `\x1b[0m`.
+    ///
+    /// If it closes only some of them and opens nothing, e.g. `\x1b[39m` for `\x1b[31;40m`,
+    /// it's reused too and the rest are closed after it as [`ResetPolicy::PerAttribute`] does.
+    /// Otherwise the styles are closed as [`ResetPolicy::PerAttribute`] does.
+    ReuseOriginal,
+    /// Styles are closed as [`ResetPolicy::PerAttribute`] does,
+    /// but a sequence is kept to 1, 2, 3 or 5 parameters as only such are parsed by `ansi-parser`,
+    /// e.g. `\x1b[39;49;22m\x1b[24m` instead of `\x1b[39;49;22;24m`.
+    ParserCompatible,
+}
+
+/// UnknownPolicy defines what's done with SGR codes which are not known to the crate,
+/// like `\x1b[56m`.
+///
+/// Parameters above 255 are unknown codes too, a color with such a parameter is kept as a whole,
+/// like `38;5;300` of `\x1b[38;5;300;1m`.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum UnknownPolicy {
+    /// Unknown codes are closed by a full reset `\x1b[0m` at the end of a cut.
+    ///
+    /// It also resets the styles which are kept by a cut.
+    #[default]
+    FullResetOnUnknown,
+    /// Unknown codes are neither reopened nor closed.
+    IgnoreUnknown,
+    /// Unknown codes are reopened at the beginning of a cut as they were written,
+    /// but they are not closed as there's no known way to do it.
+    ReplayRaw,
+}
+
+/// C1Policy defines how escape sequences with a single char C1 introducer are written,
+/// like `\u{9b}31m` which is the same as `\x1b[31m`.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum C1Policy {
+    /// The sequences are written in their 7-bit form.
+    #[default]
+    Normalize,
+    /// The sequences are written as they are.
+    Keep,
+}
+
+/// ControlPolicy defines what's done with escape sequences which are neither SGR nor OSC ones,
+/// like cursor movements (`\x1b[5C`) or erasing (`\x1b[2K`).
+///
+/// It doesn't change indexes of a cut, the sequences are zero width either way.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum ControlPolicy {
+    /// The sequences are copied as they are.
+    #[default]
+    Preserve,
+    /// The sequences are dropped.
+    Strip,
+    /// A cursor forward (`\x1b[nC`) is replaced by `n` spaces
+    /// unless it goes after the end of a cut, in which case it's dropped.
+    /// It's replaced by at most 1024 spaces, so a huge `n` can't blow up a cut.
+    /// An erase in line (`\x1b[K`, `\x1b[2K`) is dropped.
+    ///
+    /// Other sequences are copied as they are.
+    Normalize,
+}
+
+/// GraphemePolicy defines what happens when a cut index splits a character
+/// from combining marks which follow it (like `e\u{301}`) or a ZWJ sequence (like `👩\u{200d}💻`).
+///
+/// Such an index is a valid UTF-8 boundary, but the first part of a cut loses the marks
+/// and the second one starts with orphaned marks which attach to whatever is printed before them.
+///
+/// Only combining marks, variation selectors, emoji modifiers, tags and ZWJ are recognized,
+/// it's not a full grapheme segmentation.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum GraphemePolicy {
+    /// Indexes are used as they are.
+    #[default]
+    Allow,
+    /// Indexes are moved forward to the next grapheme boundary,
+    /// so marks are kept together with their base character
+    /// and adjacent cuts still cover the whole string.
+    Snap,
+    /// [`AnsiCut::try_cut_with`] returns [`AnsiCutError::NotAGraphemeBoundary`],
+    /// while other methods panic.
+    Error,
+}
+
+/// BlankLinePolicy defines what happens with blank lines when a text is indented, see [`indent_with`].
+///
+/// A line is blank if it has no visible characters but whitespace.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum BlankLinePolicy {
+    /// Blank lines are left as they are.
+    #[default]
+    Keep,
+    /// Blank lines get the prefix like any other line.
+    Indent,
+}
+
+/// PadStyle defines how a padding of a chunk is styled.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum PadStyle {
+    /// The padding is not styled.
+    #[default]
+    Plain,
+    /// The padding gets a background color of the last char of the chunk.
+    Background,
+}
+
+/// FillMode defines how a background color is extended to the end of a line, see [`fill_line`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FillMode {
+    /// `\x1b[K` (erase to the end of line) is emitted while the background is active,
+    /// so a terminal fills the rest of the line with it.
+    EraseToEol,
+    /// The line is padded by spaces with the background color up to a given display width.
+    #[cfg(feature = "unicode-width")]
+    PadSpaces(usize),
+}
+
+/// WideCharPolicy defines what happens with a wide character
+/// which is split by a bound of a range in terms of display columns.
+#[cfg(feature = "unicode-width")]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum WideCharPolicy {
+    /// The character is excluded from the result.
+    #[default]
+    Exclude,
+    /// The character is excluded from the result and the
+    /// columns it would take in the range are filled with spaces.
+    ///
+    /// The spaces are not colored.
+    Pad,
+}
+
+/// ShortLinePolicy defines what happens with a line
+/// which is shorter than a range in terms of display columns.
+#[cfg(feature = "unicode-width")]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum ShortLinePolicy {
+    /// The line is left short.
+    #[default]
+    Keep,
+    /// The line is padded by spaces up to the end of the range.
+    ///
+    /// The spaces are not colored.
+    Pad,
+}
+
+/// LineEnding is a terminator of a line returned by [`lines_with_endings`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LineEnding {
+    /// The line ends with `\n`.
+    Lf,
+    /// The line ends with `\r\n`.
+    CrLf,
+    /// The line is the last one and has no ending.
+    None,
+}
+
+impl LineEnding {
+    /// Returns the ending as a string.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use ansi_cut::LineEnding;
+    /// assert_eq!(LineEnding::CrLf.as_str(), "\r\n");
+    /// assert_eq!(LineEnding::None.as_str(), "");
+    /// ```
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            LineEnding::Lf => "\n",
+            LineEnding::CrLf => "\r\n",
+            LineEnding::None => "",
+        }
+    }
+}
+
+/// Cut a formatted value from the beginning of the range to the end.
+/// Preserving its colors.
+///
+/// It gives the same as calling [`AnsiCut::cut`] on `value.to_string()`,
+/// but the value is cut while it's being formatted so it's never collected into a `String`,
+/// and formatting is stopped once the end of the range is reached.
+///
+/// # Panics
+///
+/// Panics if a start or end indexes are not on a UTF-8 code point boundary.
+///
+/// # Examples
+///
+/// ```rust
+/// use owo_colors::{OwoColorize, colors::*};
+/// let cut_text = ansi_cut::cut_display("When the night has come".fg::<Red>(), 5..);
+/// assert_eq!(cut_text, "the night has come".fg::<Red>().to_string());
+/// ```
+pub fn cut_display<D, R>(value: D, range: R) -> String
+where
+    D: std::fmt::Display,
+    R: RangeBounds<usize>,
+{
+    let (start, end) = bounds_to_usize(range.start_bound(), range.end_bound());
+    let cut = CutWriter::new(start, end, CutOptions::default(), String::new());
+    let mut f = CutFormatter::new(cut, CutOptions::default());
+
+    // an error is returned either by the cut or to stop formatting after the range
+    let _ = fmt::write(&mut f, format_args!("{}", value));
+
+    match f.finish() {
+        Ok(buf) => buf,
+        Err(CutError::Index(err)) => panic!("{}", err),
+        Err(CutError::Write(_)) => unreachable!("writing into a string never fails"),
+    }
+}
+
+/// Cut a string from the beginning of the range to the end and write it into `w`.
+/// Preserving its colors.
+///
+/// It's the same as [`AnsiCut::cut`] but the cut is written directly into the writer
+/// instead of being collected into a `String`.
+///
+/// # Errors
+///
+/// Returns an error if the writer fails.
+///
+/// # Panics
+///
+/// Panics if a start or end indexes are not on a UTF-8 code point boundary.
+///
+/// # Examples
+///
+/// ```rust
+/// use owo_colors::{OwoColorize, colors::*};
+/// let colored_text = "When the night has come".fg::<Red>().to_string();
+/// let mut buf = String::from("> ");
+/// ansi_cut::cut_to(&colored_text, 5.., &mut buf).unwrap();
+/// assert_eq!(buf, format!("> {}", "the night has come".fg::<Red>()));
+/// ```
+pub fn cut_to<W, R>(s: &str, range: R, w: &mut W) -> fmt::Result
+where
+    W: fmt::Write + ?Sized,
+    R: RangeBounds<usize>,
+{
+    let (start, end) = bounds_to_usize(range.start_bound(), range.end_bound());
+    match try_cut_to(s, start, end, CutOptions::default(), w) {
+        Ok(_) => Ok(()),
+        Err(CutError::Index(err)) => panic!("{}", err),
+        Err(CutError::Write(err)) => Err(err),
+    }
+}
+
+/// The same as [`cut_to`] but writes into [`std::io::Write`].
+///
+/// # Errors
+///
+/// Returns an error if the writer fails.
+///
+/// # Panics
+///
+/// Panics if a start or end indexes are not on a UTF-8 code point boundary.
+///
+/// # Examples
+///
+/// ```rust
+/// use owo_colors::{OwoColorize, colors::*};
+/// let colored_text = "When the night has come".fg::<Red>().to_string();
+/// let mut stdout = std::io::stdout();
+/// ansi_cut::cut_to_io(&colored_text, 5.., &mut stdout).unwrap();
+/// ```
+pub fn cut_to_io<W, R>(s: &str, range: R, w: &mut W) -> io::Result<()>
+where
+    W: io::Write + ?Sized,
+    R: RangeBounds<usize>,
+{
+    let mut writer = IoWriter {
+        inner: w,
+        error: None,
+    };
+
+    match cut_to(s, range, &mut writer) {
+        Ok(()) => Ok(()),
+        Err(_) => Err(writer
+            .error
+            .unwrap_or_else(|| io::Error::other("formatter error"))),
+    }
+}
+
+// IoWriter is an adapter of io::Write to fmt::Write,
+// which keeps an io error because fmt::Error can't hold it.
+struct IoWriter<'a, W: ?Sized> {
+    inner: &'a mut W,
+    error: Option<io::Error>,
+}
+
+impl<W> fmt::Write for IoWriter<'_, W>
+where
+    W: io::Write + ?Sized,
+{
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.inner.write_all(s.as_bytes()).map_err(|err| {
+            self.error = Some(err);
+            fmt::Error
+        })
+    }
+}
+
+/// Cut is a lazy cut of a string which is made each time it's displayed.
+///
+/// It's created by [`AnsiCut::cut_lazy`].
+#[derive(Debug, Clone, Copy)]
+pub struct Cut<'a, R> {
+    text: &'a str,
+    range: R,
+}
+
+impl<R> std::fmt::Display for Cut<'_, R>
+where
+    R: RangeBounds<usize>,
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        cut_to(
+            self.text,
+            (self.range.start_bound(), self.range.end_bound()),
+            f,
+        )
+    }
+}
+
+/// Cut a string by a list of ranges.
+/// Preserving its colors.
+///
+/// It returns the same as calling [`AnsiCut::cut`] for each range,
+/// but the string is parsed only once.
+///
+/// Ranges are defined in terms of `byte`s of the string not containing ANSI
+/// control sequences, they may overlap and be in any order.
+/// The results are returned in the same order as the ranges.
+///
+/// # Panics
+///
+/// Panics if a start or end indexes are not on a UTF-8 code point boundary.
+///
+/// # Examples
+///
+/// ```rust
+/// use owo_colors::{OwoColorize, colors::*};
+/// let colored_text = "When the night has come".fg::<Red>().to_string();
+/// let cuts = ansi_cut::cut_many(&colored_text, &[5..10, 0..4]);
+/// assert_eq!(cuts, vec!["the n".fg::<Red>().to_string(), "When".fg::<Red>().to_string()]);
+/// ```
+pub fn cut_many(s: &str, ranges: &[Range<usize>]) -> Vec<String> {
+    match try_cut_many(s, ranges) {
+        Ok(cuts) => cuts,
+        Err(err) => panic!("{}", err),
+    }
+}
+
+fn try_cut_many(s: &str, ranges: &[Range<usize>]) -> Result<Vec<String>, CutError> {
+    let mut state = AnsiState::default();
+    let mut cuts = ranges
+        .iter()
+        .map(|range| {
+            CutWriter::new(
+                range.start,
+                Some(range.end),
+                CutOptions::default(),
+                String::new(),
+            )
+        })
+        .collect::<Vec<_>>();
+    let mut index = 0;
+
+    for token in parse_ansi(s) {
+        match token {
+            Token::Text(text) => {
+                for cut in &mut cuts {
+                    cut.push_text(index, text, &state)?;
                 }
+
+                index += text.len();
             }
-            59 => {
-                state.undr_color = None;
-            }
-            60 => {
-                state.igrm_underline = true;
-            }
-            61 => {
-                state.igrm_double_underline = true;
-            }
-            62 => {
-                state.igrm_overline = true;
-            }
-            63 => {
-                state.igrm_double_overline = true;
-            }
-            64 => {
-                state.igrm_stress_marking = true;
-            }
-            65 => {
-                state.igrm_underline = false;
-                state.igrm_double_underline = false;
-                state.igrm_overline = false;
-                state.igrm_double_overline = false;
-                state.igrm_stress_marking = false;
-            }
-            73 => {
-                state.superscript = true;
+            Token::Escape(seq) => {
+                let seq_str = seq.to_string();
+                for cut in &mut cuts {
+                    cut.push_escape(&seq_str)?;
+                }
+
+                update_ansi_state_by_escape(&mut state, &seq);
+            }
+        }
+
+        if cuts.iter().all(|cut| cut.done) {
+            break;
+        }
+    }
+
+    let cuts = cuts.into_iter().map(|cut| cut.finish(&state));
+    Ok(cuts.collect::<Result<_, _>>()?)
+}
+
+/// Cut each line of a string by the same range.
+/// Preserving its colors.
+///
+/// It works like `cut -c`, the range is defined in terms of `char`s of a line
+/// not containing ANSI control sequences.
+///
+/// Lines are separated by `\n` or `\r\n` and the line endings are kept as they are.
+/// A style which is left open by one of the lines is reopened at the beginning of the next ones,
+/// so each line is closed and can be printed on its own.
+/// A line which has nothing in the range is empty.
+///
+/// # Examples
+///
+/// ```rust
+/// use owo_colors::{OwoColorize, colors::*};
+/// let colored_text = "When the night\nhas come".fg::<Red>().to_string();
+/// assert_eq!(
+///     ansi_cut::cut_lines(&colored_text, 1..4),
+///     format!("{}\n{}", "hen".fg::<Red>(), "as ".fg::<Red>()),
+/// );
+/// ```
+pub fn cut_lines<R>(s: &str, range: R) -> String
+where
+    R: RangeBounds<usize>,
+{
+    // a line with nothing in the range is left empty
+    let options = CutOptions::default().strict(true);
+
+    let mut buf = String::with_capacity(s.len());
+    for (line, ending) in styled_lines(s) {
+        let bounds = (range.start_bound(), range.end_bound());
+        buf.push_str(&cut_chars_with(&line, bounds, options));
+        buf.push_str(ending);
+    }
+
+    buf
+}
+
+/// Cut a rectangular block out of a multi-line string.
+/// Preserving its colors.
+///
+/// The lines are selected by `y` range and each of them is cut by `x` range
+/// which is defined in terms of display columns like in [`AnsiCut::cut_width`].
+/// Lines are counted from 0.
+///
+/// A style which is left open by one of the lines is reopened at the beginning of the next ones,
+/// so each line is closed and can be printed on its own.
+/// The lines are joined by `\n`.
+///
+/// Lines which are shorter than the `x` range are left short,
+/// see [`cut_block_with`] to pad them.
+/// A line which has nothing in the `x` range is empty.
+///
+/// # Examples
+///
+/// ```rust
+/// use owo_colors::{OwoColorize, colors::*};
+/// let colored_text = "When\nthe night\nhas come".fg::<Red>().to_string();
+/// assert_eq!(
+///     ansi_cut::cut_block(&colored_text, 1..5, 1..),
+///     format!("{}\n{}", "he n".fg::<Red>(), "as c".fg::<Red>()),
+/// );
+/// ```
+#[cfg(feature = "unicode-width")]
+pub fn cut_block<X, Y>(s: &str, x: X, y: Y) -> String
+where
+    X: RangeBounds<usize>,
+    Y: RangeBounds<usize>,
+{
+    cut_block_with(s, x, y, ShortLinePolicy::Keep)
+}
+
+/// The same as [`cut_block`] but with a control of what happens
+/// with lines which are shorter than the `x` range.
+///
+/// # Examples
+///
+/// ```rust
+/// use owo_colors::{OwoColorize, colors::*};
+/// use ansi_cut::ShortLinePolicy;
+/// let colored_text = "When\nthe night".fg::<Red>().to_string();
+/// assert_eq!(
+///     ansi_cut::cut_block_with(&colored_text, 2..6, .., ShortLinePolicy::Pad),
+///     format!("{}  \n{}", "en".fg::<Red>(), "e ni".fg::<Red>()),
+/// );
+/// ```
+#[cfg(feature = "unicode-width")]
+pub fn cut_block_with<X, Y>(s: &str, x: X, y: Y, policy: ShortLinePolicy) -> String
+where
+    X: RangeBounds<usize>,
+    Y: RangeBounds<usize>,
+{
+    let (x_start, x_end) = bounds_to_usize(x.start_bound(), x.end_bound());
+    let (y_start, y_end) = bounds_to_usize(y.start_bound(), y.end_bound());
+    let count = y_end.map_or(usize::MAX, |end| end.saturating_sub(y_start));
+    let bounds = (x.start_bound(), x.end_bound());
+    // a line with nothing in the range is left empty
+    let options = CutOptions::default().strict(true);
+
+    let mut buf = String::new();
+    let lines = styled_lines(s).skip(y_start).take(count);
+    for (i, (line, _)) in lines.enumerate() {
+        if i > 0 {
+            buf.push('\n');
+        }
+
+        match policy {
+            ShortLinePolicy::Keep => buf.push_str(&cut_width_with(
+                &line,
+                bounds,
+                WideCharPolicy::Exclude,
+                options,
+            )),
+            ShortLinePolicy::Pad => {
+                let cut = cut_width_with(&line, bounds, WideCharPolicy::Pad, options);
+                buf.push_str(&cut);
+
+                if let Some(x_end) = x_end {
+                    let width = srip_ansi_sequences(&cut)
+                        .chars()
+                        .map(char_width)
+                        .sum::<usize>();
+                    let expected_width = x_end.saturating_sub(x_start);
+                    push_spaces(&mut buf, expected_width.saturating_sub(width));
+                }
+            }
+        }
+    }
+
+    buf
+}
+
+/// Returns an iterator over lines of a string.
+/// Preserving its colors.
+///
+/// Lines are separated by `\n` or `\r\n` which are not included in the lines,
+/// a trailing line ending doesn't make an extra empty line, like in [`str::lines`].
+///
+/// Each line starts with a style which is active at its beginning and is closed at its end,
+/// so it can be printed on its own.
+///
+/// # Examples
+///
+/// ```rust
+/// use owo_colors::{OwoColorize, colors::*};
+/// let colored_text = "When the night\nhas come\n".fg::<Red>().to_string();
+/// let mut lines = ansi_cut::lines(&colored_text);
+/// assert_eq!(lines.next(), Some("When the night".fg::<Red>().to_string()));
+/// assert_eq!(lines.next(), Some("has come".fg::<Red>().to_string()));
+/// assert_eq!(lines.next(), None);
+/// ```
+pub fn lines(s: &str) -> AnsiLines<'_> {
+    AnsiLines {
+        lines: s.split_inclusive('\n'),
+        state: AnsiState::default(),
+        carried: "",
+    }
+}
+
+/// Returns an iterator over lines of a string together with their endings.
+/// Preserving its colors.
+///
+/// The lines are the same as the ones returned by [`lines`],
+/// so joining each of them with its ending gives back the visible content of the string.
+///
+/// Escape sequences which are placed between `\r` and `\n` are moved to the following line.
+///
+/// # Examples
+///
+/// ```rust
+/// use owo_colors::{OwoColorize, colors::*};
+/// use ansi_cut::LineEnding;
+/// let colored_text = "When the night\r\nhas come".fg::<Red>().to_string();
+/// let mut lines = ansi_cut::lines_with_endings(&colored_text);
+/// assert_eq!(
+///     lines.next(),
+///     Some(("When the night".fg::<Red>().to_string(), LineEnding::CrLf))
+/// );
+/// assert_eq!(
+///     lines.next(),
+///     Some(("has come".fg::<Red>().to_string(), LineEnding::None))
+/// );
+/// assert_eq!(lines.next(), None);
+/// ```
+pub fn lines_with_endings(s: &str) -> impl Iterator<Item = (String, LineEnding)> + '_ {
+    let mut lines = lines(s);
+    std::iter::from_fn(move || lines.next_line())
+}
+
+/// AnsiLines is an iterator over lines of a string,
+/// which is created by [`lines`].
+#[derive(Debug, Clone)]
+pub struct AnsiLines<'a> {
+    lines: std::str::SplitInclusive<'a, char>,
+    // a style which is left open by the previous lines
+    state: AnsiState,
+    // escape sequences which were placed between `\r` and `\n` of the previous line
+    carried: &'a str,
+}
+
+impl<'a> AnsiLines<'a> {
+    fn next_line(&mut self) -> Option<(String, LineEnding)> {
+        let (mut line, ending, carried) = split_line_ending_escapes(self.lines.next()?);
+
+        // styles which are set right at the beginning of a line
+        // are reopened together with the ones left by the previous lines
+        let mut prefix = String::new();
+        for token in parse_ansi(std::mem::replace(&mut self.carried, carried)) {
+            match token {
+                Token::Escape(seq) if seq.is_sgr() => {
+                    update_ansi_state_by_escape(&mut self.state, &seq)
+                }
+                token => prefix.push_str(&token.to_string()),
+            }
+        }
+
+        if prefix.is_empty() {
+            while let Some((Token::Escape(seq), rest)) = split_first_token(line) {
+                if !seq.is_sgr() {
+                    break;
+                }
+
+                update_ansi_state_by_escape(&mut self.state, &seq);
+                line = rest;
+            }
+        }
+
+        // escape sequences after a trailing line ending don't make a line
+        if ending == LineEnding::None && srip_ansi_sequences(line).is_empty() {
+            update_ansi_state_by_str(&mut self.state, line);
+            return None;
+        }
+
+        let mut buf = String::new();
+        if !line.is_empty() || !prefix.is_empty() {
+            open_ansi_sequences(&self.state, &mut buf);
+            buf.push_str(&prefix);
+            buf.push_str(line);
+            update_ansi_state_by_str(&mut self.state, line);
+            complete_ansi_sequences(&self.state, &mut buf);
+        }
+
+        Some((buf, ending))
+    }
+}
+
+impl Iterator for AnsiLines<'_> {
+    type Item = String;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_line().map(|(line, _)| line)
+    }
+}
+
+impl std::iter::FusedIterator for AnsiLines<'_> {}
+
+/// Returns the first `n` lines of a string.
+/// Preserving its colors.
+///
+/// Lines are counted like in [`lines`] and keep their endings,
+/// styles which are left open by the last of them are closed before its ending.
+///
+/// # Examples
+///
+/// ```rust
+/// use owo_colors::{OwoColorize, colors::*};
+/// let colored_text = "When\nthe night\nhas come".fg::<Red>().to_string();
+/// assert_eq!(
+///     ansi_cut::head_lines(&colored_text, 2),
+///     format!("{}\n", "When\nthe night".fg::<Red>()),
+/// );
+/// ```
+pub fn head_lines(s: &str, n: usize) -> String {
+    if n == 0 {
+        return String::new();
+    }
+
+    let end = s
+        .match_indices('\n')
+        .nth(n - 1)
+        .map_or(s.len(), |(i, _)| i + 1);
+
+    let mut state = AnsiState::default();
+    update_ansi_state_by_str(&mut state, &s[..end]);
+
+    let mut buf = String::with_capacity(end);
+    push_closed_lines(&mut buf, &s[..end], &state);
+    buf
+}
+
+/// Returns the last `n` lines of a string.
+/// Preserving its colors.
+///
+/// Lines are counted like in [`lines`], so a trailing line ending doesn't make an extra line,
+/// but blank lines do. The lines keep their endings.
+///
+/// The result starts with a style which is active at the beginning of the first line
+/// and styles which are left open by the last line are closed before its ending.
+/// The lines are found by a scan from the end of the string,
+/// the lines before them are only parsed to get the style.
+///
+/// # Examples
+///
+/// ```rust
+/// use owo_colors::{OwoColorize, colors::*};
+/// let colored_text = format!("{}\n", "When\nthe night\nhas come".fg::<Red>());
+/// assert_eq!(
+///     ansi_cut::tail_lines(&colored_text, 2),
+///     format!("{}\n", "the night\nhas come".fg::<Red>()),
+/// );
+/// ```
+pub fn tail_lines(s: &str, n: usize) -> String {
+    if n == 0 {
+        return String::new();
+    }
+
+    // escape sequences after a trailing line ending don't make a line
+    let text_end = match s.rfind('\n') {
+        Some(i) if srip_ansi_sequences(&s[i + 1..]).is_empty() => i,
+        _ => s.len(),
+    };
+
+    let start = s[..text_end]
+        .rmatch_indices('\n')
+        .nth(n - 1)
+        .map_or(0, |(i, _)| i + 1);
+
+    let mut state = AnsiState::default();
+    update_ansi_state_by_str(&mut state, &s[..start]);
+
+    let mut buf = String::with_capacity(s.len() - start);
+    open_ansi_sequences(&state, &mut buf);
+    update_ansi_state_by_str(&mut state, &s[start..]);
+    push_closed_lines(&mut buf, &s[start..], &state);
+    buf
+}
+
+// Pushes lines closing a given state before the ending of the last one.
+fn push_closed_lines(buf: &mut String, lines: &str, state: &AnsiState) {
+    let (lines, ending) = split_line_ending(lines);
+    buf.push_str(lines);
+    complete_ansi_sequences(state, buf);
+    buf.push_str(ending);
+}
+
+/// Inserts a prefix at the beginning of every line of a string.
+/// Preserving its colors.
+///
+/// The prefix is placed outside of any style,
+/// a style which is left open by the previous line is closed before it and reopened after it.
+/// The line endings are kept as they are.
+///
+/// Blank lines are left as they are, see [`indent_with`] to indent them too.
+///
+/// # Examples
+///
+/// ```rust
+/// use owo_colors::{OwoColorize, colors::*};
+/// let colored_text = "When the night\nhas come".fg::<Red>().to_string();
+/// assert_eq!(
+///     ansi_cut::indent(&colored_text, "│ "),
+///     format!("│ {}\n│ {}", "When the night".fg::<Red>(), "has come".fg::<Red>()),
+/// );
+/// ```
+pub fn indent(s: &str, prefix: &str) -> String {
+    indent_with(s, prefix, BlankLinePolicy::Keep)
+}
+
+/// The same as [`indent`] but with a control of what happens with blank lines.
+///
+/// # Examples
+///
+/// ```rust
+/// use ansi_cut::BlankLinePolicy;
+/// assert_eq!(ansi_cut::indent_with("a\n\nb", "> ", BlankLinePolicy::Keep), "> a\n\n> b");
+/// assert_eq!(ansi_cut::indent_with("a\n\nb", "> ", BlankLinePolicy::Indent), "> a\n> \n> b");
+/// ```
+pub fn indent_with(s: &str, prefix: &str, policy: BlankLinePolicy) -> String {
+    let mut buf = String::with_capacity(s.len());
+    for (line, ending) in lines_with_endings(s) {
+        let is_blank = srip_ansi_sequences(&line).trim().is_empty();
+        if !is_blank || policy == BlankLinePolicy::Indent {
+            buf.push_str(prefix);
+        }
+
+        buf.push_str(&line);
+        buf.push_str(ending.as_str());
+    }
+
+    buf
+}
+
+/// Prefixes every line of a string with its number.
+/// Preserving its colors.
+///
+/// Lines are numbered from `start`, the numbers are right-aligned to the width of the largest one
+/// and followed by a ` │ ` separator.
+///
+/// The gutter is styled by `gutter_style` escape sequences which are closed at its end,
+/// the styles of the lines are not affected by it and are reopened after it.
+/// The line endings are kept as they are.
+///
+/// # Examples
+///
+/// ```rust
+/// use owo_colors::{OwoColorize, colors::*};
+/// let colored_text = "When the night\nhas come".fg::<Red>().to_string();
+/// assert_eq!(
+///     ansi_cut::number_lines(&colored_text, 9, ""),
+///     format!(" 9 │ {}\n10 │ {}", "When the night".fg::<Red>(), "has come".fg::<Red>()),
+/// );
+/// ```
+pub fn number_lines(s: &str, start: usize, gutter_style: &str) -> String {
+    let lines = lines_with_endings(s).collect::<Vec<_>>();
+    let width = number_width(start + lines.len().saturating_sub(1));
+
+    let mut buf = String::with_capacity(s.len());
+    for (i, (line, ending)) in lines.into_iter().enumerate() {
+        push_gutter(&mut buf, Some(start + i), width, gutter_style);
+        buf.push_str(&line);
+        buf.push_str(ending.as_str());
+    }
+
+    buf
+}
+
+/// The same as [`number_lines`] but the lines are wrapped to `width` display columns first,
+/// like in [`wrap`].
+///
+/// Only the first part of a wrapped line gets a number,
+/// its continuations get a blank gutter and are separated by `\n`.
+///
+/// # Panics
+///
+/// Panics if width is 0.
+///
+/// # Examples
+///
+/// ```rust
+/// use owo_colors::{OwoColorize, colors::*};
+/// let colored_text = "When the night\nhas come".fg::<Red>().to_string();
+/// assert_eq!(
+///     ansi_cut::number_lines_wrapped(&colored_text, 1, "", 9),
+///     format!(
+///         "1 │ {}\n  │ {}\n2 │ {}",
+///         "When the".fg::<Red>(),
+///         "night".fg::<Red>(),
+///         "has come".fg::<Red>(),
+///     ),
+/// );
+/// ```
+#[cfg(feature = "unicode-width")]
+pub fn number_lines_wrapped(s: &str, start: usize, gutter_style: &str, width: usize) -> String {
+    assert!(width > 0);
+
+    let lines = lines_with_endings(s).collect::<Vec<_>>();
+    let number_width = number_width(start + lines.len().saturating_sub(1));
+
+    let mut buf = String::with_capacity(s.len());
+    for (i, (line, ending)) in lines.into_iter().enumerate() {
+        let mut parts = wrap(&line, width);
+        if parts.is_empty() {
+            parts.push(String::new());
+        }
+
+        for (j, part) in parts.iter().enumerate() {
+            let number = if j == 0 { Some(start + i) } else { None };
+            push_gutter(&mut buf, number, number_width, gutter_style);
+            buf.push_str(part);
+            if j + 1 < parts.len() {
+                buf.push('\n');
+            }
+        }
+
+        buf.push_str(ending.as_str());
+    }
+
+    buf
+}
+
+// Returns a number of digits of a number.
+fn number_width(n: usize) -> usize {
+    n.to_string().len()
+}
+
+// Pushes a gutter with a line number or a blank one.
+fn push_gutter(buf: &mut String, number: Option<usize>, width: usize, style: &str) {
+    let mut state = AnsiState::default();
+    update_ansi_state_by_str(&mut state, style);
+
+    buf.push_str(style);
+    match number {
+        Some(number) => buf.push_str(&format!("{:>width$} │", number, width = width)),
+        None => buf.push_str(&format!("{:width$} │", "", width = width)),
+    }
+    complete_ansi_sequences(&state, buf);
+    buf.push(' ');
+}
+
+/// Removes up to `n` leading whitespace characters from every line of a string.
+/// Preserving its colors.
+///
+/// Only whitespace is removed, a line which has less of it loses all of it.
+/// Each line starts with a style which is active at its beginning and is closed at its end.
+/// The line endings are kept as they are.
+///
+/// # Examples
+///
+/// ```rust
+/// use owo_colors::{OwoColorize, colors::*};
+/// let colored_text = "    When the night\n  has come".fg::<Red>().to_string();
+/// assert_eq!(
+///     ansi_cut::dedent(&colored_text, 3),
+///     format!("{}\n{}", " When the night".fg::<Red>(), "has come".fg::<Red>()),
+/// );
+/// ```
+pub fn dedent(s: &str, n: usize) -> String {
+    let mut buf = String::with_capacity(s.len());
+    for (line, ending) in lines_with_endings(s) {
+        let stripped = srip_ansi_sequences(&line);
+        let start = stripped
+            .char_indices()
+            .take(n)
+            .find(|(_, c)| !c.is_whitespace())
+            .map_or_else(|| chars_to_bytes(&stripped, n), |(i, _)| i);
+
+        if start == 0 {
+            buf.push_str(&line);
+        } else {
+            let options = CutOptions::default().minimal(true).strict(true);
+            buf.push_str(&cut_with(&line, start.., options));
+        }
+
+        buf.push_str(ending.as_str());
+    }
+
+    buf
+}
+
+// Returns lines of a string together with their endings,
+// a style which is left open by the previous lines is reopened at the beginning of each line.
+fn styled_lines(s: &str) -> impl Iterator<Item = (String, &str)> {
+    let mut state = AnsiState::default();
+    s.split_inclusive('\n').map(move |line| {
+        let (line, ending) = split_line_ending(line);
+
+        let mut buf = String::new();
+        open_ansi_sequences(&state, &mut buf);
+        buf.push_str(line);
+
+        update_ansi_state_by_str(&mut state, line);
+
+        (buf, ending)
+    })
+}
+
+// Splits a line into its content, its ending
+// and escape sequences which are placed between `\r` and `\n` of the ending.
+fn split_line_ending_escapes(line: &str) -> (&str, LineEnding, &str) {
+    let Some(line) = line.strip_suffix('\n') else {
+        return (line, LineEnding::None, "");
+    };
+
+    match line.rfind('\r') {
+        Some(i) if srip_ansi_sequences(&line[i + 1..]).is_empty() => {
+            (&line[..i], LineEnding::CrLf, &line[i + 1..])
+        }
+        _ => (line, LineEnding::Lf, ""),
+    }
+}
+
+// Splits a line into its content and its ending.
+fn split_line_ending(line: &str) -> (&str, &str) {
+    if let Some(line) = line.strip_suffix("\r\n") {
+        (line, "\r\n")
+    } else if let Some(line) = line.strip_suffix('\n') {
+        (line, "\n")
+    } else {
+        (line, "")
+    }
+}
+
+/// AnsiIndexedStr is a string which is parsed once,
+/// so it can be cut many times cheaply.
+///
+/// It keeps positions of all text blocks and ANSI control sequences of the string
+/// together with a style active at each of them,
+/// so a cut doesn't need to go through the whole string again.
+///
+/// The cuts are exactly the same as the ones made by [`AnsiCut`].
+///
+/// # Examples
+///
+/// ```rust
+/// use ansi_cut::{AnsiCut, AnsiIndexedStr};
+/// use owo_colors::{OwoColorize, colors::*};
+///
+/// let colored_text = "When the night has come".fg::<Red>().to_string();
+/// let indexed = AnsiIndexedStr::new(&colored_text);
+/// assert_eq!(indexed.cut(5..14), colored_text.cut(5..14));
+/// assert_eq!(indexed.cut(15..), colored_text.cut(15..));
+/// ```
+#[derive(Debug, Clone)]
+pub struct AnsiIndexedStr<'a> {
+    text: &'a str,
+    tokens: Vec<IndexedToken<'a>>,
+    // all escape sequences of the string one after another
+    escapes: String,
+    end: Offsets,
+    state: AnsiState,
+}
+
+#[derive(Debug, Clone)]
+struct IndexedToken<'a> {
+    kind: IndexedTokenKind<'a>,
+    // offsets of the token start in the string not containing ANSI sequences
+    offsets: Offsets,
+    // a position of the token start in escapes
+    escapes: usize,
+    // a state which is active before the token
+    state: AnsiState,
+}
+
+#[derive(Debug, Clone)]
+enum IndexedTokenKind<'a> {
+    Text(&'a str),
+    // a range in escapes
+    Escape(Range<usize>),
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+struct Offsets {
+    bytes: usize,
+    chars: usize,
+    #[cfg(feature = "unicode-width")]
+    width: usize,
+}
+
+impl<'a> AnsiIndexedStr<'a> {
+    /// Parses a string and builds an index over it.
+    pub fn new(text: &'a str) -> Self {
+        let mut tokens = Vec::new();
+        let mut escapes = String::new();
+        let mut offsets = Offsets::default();
+        let mut state = AnsiState::default();
+
+        for token in parse_ansi(text) {
+            let escapes_start = escapes.len();
+            let kind = match token {
+                Token::Text(text) => IndexedTokenKind::Text(text),
+                Token::Escape(ref seq) => {
+                    escapes.push_str(&seq.to_string());
+                    IndexedTokenKind::Escape(escapes_start..escapes.len())
+                }
+            };
+
+            tokens.push(IndexedToken {
+                kind,
+                offsets,
+                escapes: escapes_start,
+                state: state.clone(),
+            });
+
+            match token {
+                Token::Text(text) => {
+                    offsets.bytes += text.len();
+                    offsets.chars += text.chars().count();
+                    #[cfg(feature = "unicode-width")]
+                    {
+                        offsets.width += text.chars().map(char_width).sum::<usize>();
+                    }
+                }
+                Token::Escape(seq) => update_ansi_state_by_escape(&mut state, &seq),
+            }
+        }
+
+        Self {
+            text,
+            tokens,
+            escapes,
+            end: offsets,
+            state,
+        }
+    }
+
+    /// Returns the original string.
+    pub fn as_str(&self) -> &'a str {
+        self.text
+    }
+
+    /// Cut string from the beginning of the range to the end.
+    /// Preserving its colors.
+    ///
+    /// The same as [`AnsiCut::cut`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if a start or end indexes are not on a UTF-8 code point boundary.
+    pub fn cut<R>(&self, range: R) -> String
+    where
+        R: RangeBounds<usize>,
+    {
+        let (start, end) = bounds_to_usize(range.start_bound(), range.end_bound());
+        self.cut_bytes(start, end, CutOptions::default())
+    }
+
+    /// Cut string from the beginning of the range to the end.
+    /// Preserving its colors.
+    ///
+    /// The same as [`AnsiCut::cut_chars`].
+    pub fn cut_chars<R>(&self, range: R) -> String
+    where
+        R: RangeBounds<usize>,
+    {
+        let (start, end) = bounds_to_usize(range.start_bound(), range.end_bound());
+        let start = self.chars_to_bytes(start);
+        let end = end.map(|end| self.chars_to_bytes(end));
+
+        self.cut_bytes(start, end, CutOptions::default())
+    }
+
+    /// Cut string from the beginning of the range to the end.
+    /// Preserving its colors.
+    ///
+    /// The same as [`AnsiCut::cut_width`].
+    #[cfg(feature = "unicode-width")]
+    pub fn cut_width<R>(&self, range: R) -> String
+    where
+        R: RangeBounds<usize>,
+    {
+        let (start, end) = bounds_to_usize(range.start_bound(), range.end_bound());
+        let start = self.width_to_start_byte(start);
+        let end = end.map(|end| self.width_to_end_byte(end).max(start));
+
+        self.cut_bytes(start, end, CutOptions::default())
+    }
+
+    /// Returns a style which is active at a given index.
+    ///
+    /// Index is defined in terms of `byte`s of the string not containing ANSI
+    /// control sequences.
+    ///
+    /// Returns `None` if the index is out of the string.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use ansi_cut::AnsiIndexedStr;
+    /// use owo_colors::{OwoColorize, colors::*};
+    ///
+    /// let colored_text = format!("{} {}", "When".fg::<Red>(), "the night");
+    /// let indexed = AnsiIndexedStr::new(&colored_text);
+    /// assert_eq!(indexed.style_at(1).unwrap().to_string(), "\u{1b}[31m");
+    /// assert_eq!(indexed.style_at(6).unwrap().to_string(), "");
+    /// assert!(indexed.style_at(100).is_none());
+    /// ```
+    pub fn style_at(&self, index: usize) -> Option<Style> {
+        if index >= self.end.bytes {
+            return None;
+        }
+
+        let i = self.find_token(|offsets| offsets.bytes <= index);
+        let state = self.tokens[i].state.clone();
+
+        Some(Style { state })
+    }
+
+    // It does the same as try_cut_str but starts from the token the cut begins at.
+    fn cut_bytes(
+        &self,
+        lower_bound: usize,
+        upper_bound: Option<usize>,
+        options: CutOptions,
+    ) -> String {
+        match self.try_cut_to(lower_bound, upper_bound, options, String::new()) {
+            Ok(buf) => buf,
+            Err(err) => panic!("{}", err),
+        }
+    }
+
+    fn try_cut_to<W>(
+        &self,
+        lower_bound: usize,
+        upper_bound: Option<usize>,
+        options: CutOptions,
+        out: W,
+    ) -> Result<W, CutError>
+    where
+        W: fmt::Write,
+    {
+        // all tokens which end before the cut starts only give their escape sequences
+        let key = upper_bound.map_or(lower_bound, |upper| upper.min(lower_bound));
+        let first = self.tokens.partition_point(|t| match t.kind {
+            IndexedTokenKind::Text(text) => t.offsets.bytes + text.len() < key,
+            IndexedTokenKind::Escape(_) => t.offsets.bytes < key,
+        });
+
+        let mut cut = CutWriter::new(lower_bound, upper_bound, options, out);
+        match self.tokens.get(first) {
+            Some(token) => cut.push_escape(&self.escapes[..token.escapes])?,
+            None => cut.push_escape(self.escapes.as_str())?,
+        }
+
+        for token in &self.tokens[first..] {
+            match &token.kind {
+                IndexedTokenKind::Text(text) => {
+                    cut.push_text(token.offsets.bytes, text, &token.state)?;
+                    if cut.done {
+                        return Ok(cut.finish(&token.state)?);
+                    }
+                }
+                IndexedTokenKind::Escape(range) => cut.push_escape(&self.escapes[range.clone()])?,
+            }
+        }
+
+        Ok(cut.finish(&self.state)?)
+    }
+
+    // Returns an index of the last token which starts before a given offset,
+    // which is a text block containing the offset if it's in the string.
+    fn find_token<F>(&self, f: F) -> usize
+    where
+        F: Fn(&Offsets) -> bool,
+    {
+        self.tokens.partition_point(|t| f(&t.offsets)) - 1
+    }
+
+    fn chars_to_bytes(&self, chars: usize) -> usize {
+        if chars >= self.end.chars {
+            return self.end.bytes;
+        }
+
+        let token = &self.tokens[self.find_token(|offsets| offsets.chars <= chars)];
+        match token.kind {
+            IndexedTokenKind::Text(text) => {
+                token.offsets.bytes + chars_to_bytes(text, chars - token.offsets.chars)
+            }
+            IndexedTokenKind::Escape(_) => unreachable!(),
+        }
+    }
+
+    // It's the same as width_to_start_byte but on the whole string.
+    #[cfg(feature = "unicode-width")]
+    fn width_to_start_byte(&self, column: usize) -> usize {
+        if column == 0 {
+            return 0;
+        }
+
+        if column >= self.end.width {
+            return self.end.bytes;
+        }
+
+        let first = self.find_token(|offsets| offsets.width <= column);
+
+        let mut width = self.tokens[first].offsets.width;
+        for token in &self.tokens[first..] {
+            if let IndexedTokenKind::Text(text) = token.kind {
+                for (i, c) in text.char_indices() {
+                    let w = char_width(c);
+                    if width >= column && w > 0 {
+                        return token.offsets.bytes + i;
+                    }
+
+                    width += w;
+                }
+            }
+        }
+
+        self.end.bytes
+    }
+
+    // It's the same as width_to_end_byte but on the whole string.
+    #[cfg(feature = "unicode-width")]
+    fn width_to_end_byte(&self, column: usize) -> usize {
+        if column >= self.end.width {
+            return self.end.bytes;
+        }
+
+        let token = &self.tokens[self.find_token(|offsets| offsets.width <= column)];
+        match token.kind {
+            IndexedTokenKind::Text(text) => {
+                let (i, _) = width_to_end_byte(text, column - token.offsets.width);
+                token.offsets.bytes + i
+            }
+            IndexedTokenKind::Escape(_) => unreachable!(),
+        }
+    }
+}
+
+/// Style is a snapshot of ANSI styles which are active at some point of a string.
+///
+/// It's displayed as ANSI control sequences which open the styles,
+/// the same ones [`AnsiCut::cut_minimal`] starts with.
+///
+/// Styles are equal if they have the same attributes,
+/// no matter in which order they were opened.
+#[derive(Debug, Clone, Default)]
+pub struct Style {
+    state: AnsiState,
+}
+
+impl PartialEq for Style {
+    fn eq(&self, other: &Self) -> bool {
+        // the order of opened styles and whether there was a reset don't change how a text looks
+        let normalize = |state: &AnsiState| AnsiState {
+            reset: false,
+            opened: Vec::new(),
+            ..state.clone()
+        };
+
+        normalize(&self.state) == normalize(&other.state)
+    }
+}
+
+impl Eq for Style {}
+
+impl Style {
+    /// Returns a foreground color.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use ansi_cut::{AnsiIndexedStr, Color};
+    /// use owo_colors::{OwoColorize, colors::*};
+    ///
+    /// let colored_text = "When".fg::<Red>().bg::<BrightBlue>().to_string();
+    /// let style = AnsiIndexedStr::new(&colored_text).style_at(0).unwrap();
+    /// assert_eq!(style.fg(), Some(Color::Basic(1)));
+    /// assert_eq!(style.bg(), Some(Color::Basic(12)));
+    /// assert!(!style.is_bold());
+    /// ```
+    pub fn fg(&self) -> Option<Color> {
+        self.state.fg_color.as_ref().map(Color::from)
+    }
+
+    /// Returns a background color.
+    pub fn bg(&self) -> Option<Color> {
+        self.state.bg_color.as_ref().map(Color::from)
+    }
+
+    /// Returns an underline color.
+    pub fn underline_color(&self) -> Option<Color> {
+        self.state.undr_color.as_ref().map(Color::from)
+    }
+
+    /// Checks whether a text is bold.
+    pub fn is_bold(&self) -> bool {
+        self.state.bold
+    }
+
+    /// Checks whether a text is faint.
+    pub fn is_faint(&self) -> bool {
+        self.state.faint
+    }
+
+    /// Checks whether a text is italic.
+    pub fn is_italic(&self) -> bool {
+        self.state.italic
+    }
+
+    /// Checks whether a text is underlined.
+    ///
+    /// Curly, dotted and dashed underlines (`4:3`, `4:4`, `4:5`) are considered underlines too.
+    pub fn is_underline(&self) -> bool {
+        self.state.underline || self.state.underline_style.is_some()
+    }
+
+    /// Checks whether a text is double underlined.
+    pub fn is_double_underline(&self) -> bool {
+        self.state.double_underline
+    }
+
+    /// Checks whether a text is blinking either slowly or rapidly.
+    pub fn is_blink(&self) -> bool {
+        self.state.slow_blink || self.state.rapid_blink
+    }
+
+    /// Checks whether foreground and background colors of a text are swapped.
+    pub fn is_inverse(&self) -> bool {
+        self.state.inverse
+    }
+
+    /// Checks whether a text is hidden.
+    pub fn is_hidden(&self) -> bool {
+        self.state.hide
+    }
+
+    /// Checks whether a text is crossed out.
+    pub fn is_crossed_out(&self) -> bool {
+        self.state.crossedout
+    }
+
+    /// Checks whether a text is overlined.
+    pub fn is_overlined(&self) -> bool {
+        self.state.overlined
+    }
+
+    /// Returns a URI of a hyperlink set by OSC 8.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// let text = "\u{1b}]8;;http://example.com\u{1b}\\link";
+    /// let style = ansi_cut::unclosed_styles(text);
+    /// assert_eq!(style.hyperlink(), Some("http://example.com"));
+    /// ```
+    pub fn hyperlink(&self) -> Option<&str> {
+        self.state.link.as_ref().map(|link| link.uri.as_str())
+    }
+
+    /// Checks whether there's no active style, so nothing is needed to be closed.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use ansi_cut::AnsiIndexedStr;
+    /// use owo_colors::{OwoColorize, colors::*};
+    ///
+    /// let colored_text = format!("{} {}", "When".fg::<Red>(), "the night");
+    /// let indexed = AnsiIndexedStr::new(&colored_text);
+    /// assert!(!indexed.style_at(0).unwrap().is_empty());
+    /// assert!(indexed.style_at(5).unwrap().is_empty());
+    /// ```
+    pub fn is_empty(&self) -> bool {
+        is_closed(&self.state)
+    }
+}
+
+impl std::fmt::Display for Style {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write_open_ansi_sequences(&self.state, f)
+    }
+}
+
+/// Color is a color set by ANSI control sequences.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Color {
+    /// One of 16 basic colors, 0..8 are normal ones and 8..16 are bright ones.
+    Basic(u8),
+    /// A color from a 256 color palette.
+    Indexed(u8),
+    /// A true color.
+    Rgb(u8, u8, u8),
+}
+
+impl From<&AnsiColor> for Color {
+    fn from(color: &AnsiColor) -> Self {
+        match *color {
+            // the index is an SGR parameter of either a foreground or a background color
+            AnsiColor::Bit4 { index } => match index {
+                30..=37 => Color::Basic(index - 30),
+                40..=47 => Color::Basic(index - 40),
+                90..=97 => Color::Basic(index - 90 + 8),
+                100..=107 => Color::Basic(index - 100 + 8),
+                _ => Color::Basic(index),
+            },
+            AnsiColor::Bit8 { index } => Color::Indexed(index),
+            AnsiColor::Bit24 { r, g, b } => Color::Rgb(r, g, b),
+        }
+    }
+}
+
+/// Returns a style which is left open at the end of a string.
+///
+/// It's the style a text written after the string would have,
+/// an empty one if the string closes everything it opens.
+///
+/// # Examples
+///
+/// ```rust
+/// let style = ansi_cut::unclosed_styles("\u{1b}[1;31mWhen\u{1b}[39m the night");
+/// assert_eq!(style.fg(), None);
+/// assert!(style.is_bold());
+/// assert_eq!(style.to_string(), "\u{1b}[1m");
+///
+/// let style = ansi_cut::unclosed_styles("\u{1b}[1;31mWhen\u{1b}[0m the night");
+/// assert!(style.is_empty());
+/// ```
+pub fn unclosed_styles(s: &str) -> Style {
+    let mut state = AnsiState::default();
+    update_ansi_state_by_str(&mut state, s);
+    Style { state }
+}
+
+/// Returns a style which is active at the end of a string,
+/// so a text written after it can be styled the same way.
+///
+/// It's the same as [`unclosed_styles`].
+///
+/// # Examples
+///
+/// ```rust
+/// use owo_colors::{OwoColorize, colors::*};
+///
+/// let fragment = format!("{}{}", "\u{1b}[1m", "When the".fg::<Red>());
+/// let style = ansi_cut::trailing_style(&fragment);
+/// assert!(style.is_bold());
+/// assert_eq!(style.fg(), None);
+///
+/// // a continuation is styled as the end of the fragment
+/// let next = format!("{}night", style);
+/// assert_eq!(ansi_cut::trailing_style(&next), style);
+/// ```
+pub fn trailing_style(s: &str) -> Style {
+    unclosed_styles(s)
+}
+
+/// Checks whether a string leaves some styles open at its end,
+/// like colors, attributes or a hyperlink.
+///
+/// See [`unclosed_styles`] to get the styles.
+///
+/// # Examples
+///
+/// ```rust
+/// use owo_colors::{OwoColorize, colors::*};
+///
+/// assert!(!ansi_cut::has_unclosed_styles(&"When the night".fg::<Red>().to_string()));
+/// assert!(ansi_cut::has_unclosed_styles("\u{1b}[31mWhen the night"));
+/// assert!(ansi_cut::has_unclosed_styles("\u{1b}]8;;http://example.com\u{1b}\\link"));
+/// ```
+pub fn has_unclosed_styles(s: &str) -> bool {
+    !unclosed_styles(s).is_empty()
+}
+
+/// Closes styles which are left open at the end of a string,
+/// so it can be concatenated with other text without changing its style.
+///
+/// Styles are closed as [`ResetPolicy::PerAttribute`] does.
+/// A string which closes everything it opens is returned as it is,
+/// except that an escape sequence which is cut off at its end is dropped like [`AnsiCut::cut`] does,
+/// so it doesn't swallow the closing sequences.
+///
+/// # Examples
+///
+/// ```rust
+/// assert_eq!(ansi_cut::close("\u{1b}[1;31mWhen the night"), "\u{1b}[1;31mWhen the night\u{1b}[39;22m");
+/// assert_eq!(ansi_cut::close("\u{1b}[31mWhen\u{1b}[0m the night"), "\u{1b}[31mWhen\u{1b}[0m the night");
+/// ```
+pub fn close(s: &str) -> String {
+    close_with(s, ResetPolicy::default())
+}
+
+/// The same as [`close`] but styles are closed according to a policy.
+///
+/// [`ResetPolicy::ReuseOriginal`] closes styles as [`ResetPolicy::PerAttribute`] does
+/// as there's nothing after the string to be reused.
+///
+/// # Examples
+///
+/// ```rust
+/// use ansi_cut::ResetPolicy;
+///
+/// let s = "\u{1b}[1;31mWhen the night";
+/// assert_eq!(ansi_cut::close_with(s, ResetPolicy::FullReset), "\u{1b}[1;31mWhen the night\u{1b}[0m");
+/// assert_eq!(ansi_cut::close_with(s, ResetPolicy::None), s);
+/// ```
+pub fn close_with(s: &str, policy: ResetPolicy) -> String {
+    let mut buf = s.to_owned();
+    close_in_place_with(&mut buf, policy);
+    buf
+}
+
+/// The same as [`close`] but the closing sequences are appended to the string itself.
+///
+/// # Examples
+///
+/// ```rust
+/// let mut s = String::from("\u{1b}[1;31mWhen the night");
+/// ansi_cut::close_in_place(&mut s);
+/// assert_eq!(s, "\u{1b}[1;31mWhen the night\u{1b}[39;22m");
+/// ```
+pub fn close_in_place(s: &mut String) {
+    close_in_place_with(s, ResetPolicy::default())
+}
+
+/// The same as [`close_with`] but the closing sequences are appended to the string itself.
+pub fn close_in_place_with(s: &mut String, policy: ResetPolicy) {
+    if is_plain(s) {
+        return;
+    }
+
+    let mut state = AnsiState::default();
+    let mut tokens = parse_ansi(s);
+    for token in &mut tokens {
+        if let Token::Escape(seq) = token {
+            update_ansi_state_by_escape(&mut state, &seq);
+        }
+    }
+
+    let truncated = tokens.truncated.len();
+    s.truncate(s.len() - truncated);
+
+    if is_closed(&state) {
+        return;
+    }
+
+    match policy {
+        ResetPolicy::PerAttribute | ResetPolicy::ReuseOriginal => {
+            complete_ansi_sequences(&state, s)
+        }
+        ResetPolicy::ParserCompatible => {
+            // writing into a string never fails
+            let _ = write_complete_ansi_sequences(&state, s, true);
+        }
+        ResetPolicy::FullReset => s.push_str("\u{1b}[0m"),
+        ResetPolicy::None => {}
+    }
+}
+
+// Returns the first token of a string and the rest of the string,
+// tokens are the same as parse_ansi produces.
+fn split_first_token(s: &str) -> Option<(Token<'_>, &str)> {
+    let mut tokens = parse_ansi(s);
+    let token = tokens.next()?;
+
+    Some((token, tokens.rest))
+}
+
+/// Resolves carriage returns of a string, so it looks the way it's rendered by a terminal.
+/// Preserving its colors.
+///
+/// Text which follows `\r` overwrites the beginning of its line,
+/// the overwritten chars are replaced together with their styles,
+/// while the rest of the line keeps its own ones.
+/// Columns are counted in chars, or in display columns with the `unicode-width` feature,
+/// in which case a wide char which is partially overwritten is replaced by spaces.
+///
+/// `\r\n` is a line ending and it's kept as it is,
+/// while `\r` at the end of a line overwrites nothing and it's dropped.
+/// Each line is closed like the ones of [`lines`] are,
+/// and escape sequences other than styles are dropped from lines which are overwritten.
+///
+/// # Examples
+///
+/// ```rust
+/// use owo_colors::{OwoColorize, colors::*};
+/// let progress = format!("downloading {}\rdone", "10%".fg::<Red>());
+/// assert_eq!(
+///     ansi_cut::resolve_cr(&progress),
+///     format!("doneloading {}", "10%".fg::<Red>()),
+/// );
+///
+/// assert_eq!(ansi_cut::resolve_cr("a\r\nb\r"), "a\r\nb");
+/// ```
+pub fn resolve_cr(s: &str) -> String {
+    let mut buf = String::with_capacity(s.len());
+    for (line, ending) in lines_with_endings(s) {
+        buf.push_str(&resolve_line_cr(&line));
+        buf.push_str(ending.as_str());
+    }
+
+    buf
+}
+
+// Overwrites the beginning of a line by each part of it which follows `\r`.
+fn resolve_line_cr(line: &str) -> Cow<'_, str> {
+    if !srip_ansi_sequences(line).contains('\r') {
+        return Cow::Borrowed(line);
+    }
+
+    // each column keeps what's written in it together with the style it was written with,
+    // the second column of a wide char is empty
+    let mut cells: Vec<(String, AnsiState)> = Vec::new();
+    let mut state = AnsiState::default();
+    let mut column = 0;
+    for token in parse_ansi(line) {
+        match token {
+            Token::Text(text) => {
+                for c in text.chars() {
+                    if c == '\r' {
+                        column = 0;
+                        continue;
+                    }
+
+                    let width = cr_column_width(c);
+
+                    // zero width chars go with the char written before them
+                    if width == 0 {
+                        if let Some(cell) = cells[..column].iter_mut().rfind(|(s, _)| !s.is_empty())
+                        {
+                            cell.0.push(c);
+                            continue;
+                        }
+                    }
+
+                    let width = width.max(1);
+                    clear_cells(&mut cells, column, column + width);
+                    for i in column..column + width {
+                        let text = if i == column {
+                            c.to_string()
+                        } else {
+                            String::new()
+                        };
+                        match cells.get_mut(i) {
+                            Some(cell) => *cell = (text, state.clone()),
+                            None => cells.push((text, state.clone())),
+                        }
+                    }
+
+                    column += width;
+                }
+            }
+            Token::Escape(seq) => update_ansi_state_by_escape(&mut state, &seq),
+        }
+    }
+
+    let mut buf = String::with_capacity(line.len());
+    let mut state = AnsiState::default();
+    for (text, style) in cells {
+        if text.is_empty() {
+            continue;
+        }
+
+        if !is_same_style(&state, &style) {
+            let (closing, opening) = diff_ansi_states(&state, &style);
+            complete_ansi_sequences(&closing, &mut buf);
+            open_ansi_sequences(&opening, &mut buf);
+            state = style;
+        }
+
+        buf.push_str(&text);
+    }
+
+    complete_ansi_sequences(&state, &mut buf);
+
+    Cow::Owned(buf)
+}
+
+// Replaces wide chars which are going to be partially overwritten in a range of columns by spaces.
+fn clear_cells(cells: &mut [(String, AnsiState)], start: usize, end: usize) {
+    // a wide char which starts before the range
+    if start < cells.len() && cells[start].0.is_empty() && start > 0 {
+        let mut i = start - 1;
+        while cells[i].0.is_empty() && i > 0 {
+            i -= 1;
+        }
+
+        for cell in &mut cells[i..start] {
+            cell.0 = String::from(" ");
+        }
+    }
+
+    // a wide char which ends after the range
+    let mut i = end;
+    while i < cells.len() && cells[i].0.is_empty() {
+        cells[i].0 = String::from(" ");
+        i += 1;
+    }
+}
+
+// Returns a number of columns a char takes when carriage returns are resolved.
+//
+// Without unicode-width each char is a column.
+fn cr_column_width(c: char) -> usize {
+    #[cfg(feature = "unicode-width")]
+    {
+        if c.is_control() {
+            return 1;
+        }
+
+        char_width(c)
+    }
+
+    #[cfg(not(feature = "unicode-width"))]
+    {
+        let _ = c;
+        1
+    }
+}
+
+/// Converts backspace overstrikes, which `man` and `nroff` use, into SGR styles.
+///
+/// `X\x08X` is turned into a bold `X` (`\x1b[1m`...`\x1b[22m`)
+/// and `_\x08X` (or `X\x08_`) into an underlined one (`\x1b[4m`...`\x1b[24m`),
+/// adjacent chars of the same style are put into a single span.
+/// `_\x08X\x08X` is both bold and underlined.
+///
+/// A backspace followed by a different char works like in a terminal, the last char wins.
+/// Backspaces which have nothing to overstrike,
+/// like ones at the beginning of a line or at the end of a string, are dropped.
+///
+/// # Examples
+///
+/// ```rust
+/// let page = "N\x08NA\x08AM\x08ME\x08E ls _\x08f_\x08i_\x08l_\x08e";
+/// assert_eq!(
+///     ansi_cut::convert_overstrike(page),
+///     "\u{1b}[1mNAME\u{1b}[22m ls \u{1b}[4mfile\u{1b}[24m",
+/// );
+/// ```
+pub fn convert_overstrike(s: &str) -> String {
+    let mut buf = String::with_capacity(s.len());
+    let mut style = OverstrikeStyle::default();
+    let mut cell: Option<(char, OverstrikeStyle)> = None;
+    let mut is_backspaced = false;
+    for token in parse_ansi(s) {
+        match token {
+            Token::Text(text) => {
+                for c in text.chars() {
+                    if c == '\u{8}' {
+                        is_backspaced = matches!(cell, Some((c, _)) if !c.is_control());
+                        continue;
+                    }
+
+                    match cell.as_mut() {
+                        Some(cell) if is_backspaced && !c.is_control() => overstrike(cell, c),
+                        _ => {
+                            if let Some(cell) = cell.take() {
+                                push_overstrike_cell(&mut buf, &mut style, cell);
+                            }
+
+                            cell = Some((c, OverstrikeStyle::default()));
+                        }
+                    }
+
+                    is_backspaced = false;
+                }
+            }
+            Token::Escape(seq) => {
+                if let Some(cell) = cell.take() {
+                    push_overstrike_cell(&mut buf, &mut style, cell);
+                }
+
+                is_backspaced = false;
+                buf.push_str(&seq.to_string());
+
+                // a style which is closed by the sequence, like by `\x1b[0m`, isn't closed again
+                if style != OverstrikeStyle::default() {
+                    let mut state = AnsiState {
+                        bold: style.bold,
+                        underline: style.underline,
+                        ..AnsiState::default()
+                    };
+                    update_ansi_state_by_escape(&mut state, &seq);
+                    style = OverstrikeStyle {
+                        bold: state.bold,
+                        underline: state.underline,
+                    };
+                }
+            }
+        }
+    }
+
+    if let Some(cell) = cell.take() {
+        push_overstrike_cell(&mut buf, &mut style, cell);
+    }
+
+    switch_overstrike_style(&mut buf, &mut style, OverstrikeStyle::default());
+
+    buf
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+struct OverstrikeStyle {
+    bold: bool,
+    underline: bool,
+}
+
+// Puts a char over the one which was backspaced.
+fn overstrike(cell: &mut (char, OverstrikeStyle), c: char) {
+    let (base, style) = cell;
+    if *base == '_' && c != '_' {
+        style.underline = true;
+        *base = c;
+    } else if c == '_' && *base != '_' {
+        style.underline = true;
+    } else if c == *base {
+        style.bold = true;
+    } else {
+        *cell = (c, OverstrikeStyle::default());
+    }
+}
+
+fn push_overstrike_cell(
+    buf: &mut String,
+    current: &mut OverstrikeStyle,
+    (c, style): (char, OverstrikeStyle),
+) {
+    switch_overstrike_style(buf, current, style);
+    buf.push(c);
+}
+
+// Closes the styles which are not used anymore and opens the new ones.
+fn switch_overstrike_style(buf: &mut String, current: &mut OverstrikeStyle, next: OverstrikeStyle) {
+    if current.underline && !next.underline {
+        buf.push_str("\u{1b}[24m");
+    }
+
+    if current.bold && !next.bold {
+        buf.push_str("\u{1b}[22m");
+    }
+
+    if !current.bold && next.bold {
+        buf.push_str("\u{1b}[1m");
+    }
+
+    if !current.underline && next.underline {
+        buf.push_str("\u{1b}[4m");
+    }
+
+    *current = next;
+}
+
+/// Metrics are sizes of the visible text of a string, which are returned by [`measure`].
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Metrics {
+    /// A length in bytes of the string not containing ANSI control sequences.
+    pub bytes: usize,
+    /// A number of chars in the string not containing ANSI control sequences.
+    pub chars: usize,
+    /// A number of display columns the string takes, line breaks take none.
+    #[cfg(feature = "unicode-width")]
+    pub width: usize,
+    /// A number of lines, the same as [`str::lines`] gives for the visible text.
+    pub lines: usize,
+    /// A number of display columns the widest line takes.
+    #[cfg(feature = "unicode-width")]
+    pub max_line_width: usize,
+}
+
+/// Measures the visible text of a string in a single pass,
+/// the string not containing ANSI control sequences is never built.
+///
+/// A width is measured the same way as in [`chunks_width`],
+/// control chars like `\r` or `\t` take no columns.
+///
+/// # Examples
+///
+/// ```rust
+/// use owo_colors::{OwoColorize, colors::*};
+/// let colored_text = format!("{}\n{}", "When the night".fg::<Red>(), "你好".fg::<Blue>());
+/// let metrics = ansi_cut::measure(&colored_text);
+/// assert_eq!(metrics.bytes, 21);
+/// assert_eq!(metrics.chars, 17);
+/// assert_eq!(metrics.lines, 2);
+/// ```
+pub fn measure(s: &str) -> Metrics {
+    let mut metrics = Metrics::default();
+    #[cfg(feature = "unicode-width")]
+    let mut line_width = 0;
+    let mut is_line_open = false;
+
+    for token in parse_ansi(s) {
+        let text = match token {
+            Token::Text(text) => text,
+            Token::Escape(_) => continue,
+        };
+
+        metrics.bytes += text.len();
+        for c in text.chars() {
+            metrics.chars += 1;
+
+            if c == '\n' {
+                metrics.lines += 1;
+                is_line_open = false;
+                #[cfg(feature = "unicode-width")]
+                {
+                    metrics.max_line_width = metrics.max_line_width.max(line_width);
+                    line_width = 0;
+                }
+
+                continue;
+            }
+
+            is_line_open = true;
+            #[cfg(feature = "unicode-width")]
+            {
+                let w = char_width(c);
+                metrics.width += w;
+                line_width += w;
+            }
+        }
+    }
+
+    if is_line_open {
+        metrics.lines += 1;
+        #[cfg(feature = "unicode-width")]
+        {
+            metrics.max_line_width = metrics.max_line_width.max(line_width);
+        }
+    }
+
+    metrics
+}
+
+/// Returns a length in bytes of a string not containing ANSI control sequences,
+/// which is what indexes of [`AnsiCut::cut`] are defined in.
+///
+/// # Examples
+///
+/// ```rust
+/// use owo_colors::{OwoColorize, colors::*};
+/// assert_eq!(ansi_cut::visible_len(&"😀 night".fg::<Red>().to_string()), 10);
+/// ```
+pub fn visible_len(s: &str) -> usize {
+    measure(s).bytes
+}
+
+/// Returns a number of chars in a string not containing ANSI control sequences,
+/// which is what indexes of [`AnsiCut::cut_chars`] are defined in.
+///
+/// # Examples
+///
+/// ```rust
+/// use owo_colors::{OwoColorize, colors::*};
+/// assert_eq!(ansi_cut::visible_chars(&"😀 night".fg::<Red>().to_string()), 7);
+/// ```
+pub fn visible_chars(s: &str) -> usize {
+    measure(s).chars
+}
+
+/// Returns a number of display columns a string takes,
+/// which is what indexes of [`AnsiCut::cut_width`] are defined in.
+///
+/// # Examples
+///
+/// ```rust
+/// use owo_colors::{OwoColorize, colors::*};
+/// assert_eq!(ansi_cut::visible_width(&"😀 night".fg::<Red>().to_string()), 8);
+/// ```
+#[cfg(feature = "unicode-width")]
+pub fn visible_width(s: &str) -> usize {
+    measure(s).width
+}
+
+/// Repeats a string `n` times.
+/// Preserving its colors.
+///
+/// Each repetition looks like the string rendered on its own.
+/// If all visible text of the string has the same style,
+/// the style is opened once, the text is repeated and the style is closed once,
+/// otherwise every repetition opens and closes its styles.
+///
+/// A string without visible text gives an empty string.
+///
+/// # Examples
+///
+/// ```rust
+/// use owo_colors::{OwoColorize, colors::*};
+/// let separator = "─".fg::<Red>().to_string();
+/// assert_eq!(ansi_cut::ansi_repeat(&separator, 3), "───".fg::<Red>().to_string());
+/// ```
+pub fn ansi_repeat(s: &str, n: usize) -> String {
+    let mut state = AnsiState::default();
+    let mut text_state: Option<(AnsiState, String)> = None;
+    let mut is_constant = true;
+    let mut text = String::new();
+    for token in parse_ansi(s) {
+        match token {
+            Token::Text(block) => {
+                let mut style = String::new();
+                open_ansi_sequences(&state, &mut style);
+                match &text_state {
+                    Some((_, text_style)) => is_constant &= *text_style == style,
+                    None => text_state = Some((state.clone(), style)),
+                }
+
+                text.push_str(block);
+            }
+            Token::Escape(seq) if seq.is_sgr() => update_ansi_state_by_escape(&mut state, &seq),
+            Token::Escape(_) => is_constant = false,
+        }
+    }
+
+    let Some((state, style)) = text_state.filter(|_| n > 0) else {
+        return String::new();
+    };
+
+    if !is_constant {
+        return cut_str(s, 0, None).repeat(n);
+    }
+
+    let mut buf = String::with_capacity(style.len() + text.len() * n);
+    buf.push_str(&style);
+    for _ in 0..n {
+        buf.push_str(&text);
+    }
+    complete_ansi_sequences(&state, &mut buf);
+
+    buf
+}
+
+/// Extends a background color which is active at the end of a string to the end of a line.
+///
+/// With [`FillMode::EraseToEol`] `\x1b[K` is inserted right after the last visible character,
+/// before the sequences which close the background.
+/// With `FillMode::PadSpaces` the string is padded like in `pad_right_with`
+/// with [`PadStyle::Background`].
+///
+/// If no background is active at the last visible character nothing special is done,
+/// the string is returned unchanged, or just padded by not styled spaces with `FillMode::PadSpaces`.
+///
+/// # Examples
+///
+/// ```rust
+/// use owo_colors::{OwoColorize, colors::*};
+/// use ansi_cut::FillMode;
+/// let colored_text = "When the night".bg::<Blue>().to_string();
+/// assert_eq!(
+///     ansi_cut::fill_line(&colored_text, FillMode::EraseToEol),
+///     "\u{1b}[44mWhen the night\u{1b}[K\u{1b}[49m",
+/// );
+/// ```
+pub fn fill_line(s: &str, mode: FillMode) -> String {
+    match mode {
+        FillMode::EraseToEol => {
+            let Some(text_end) = background_end(s) else {
+                return s.to_string();
+            };
+
+            let mut buf = String::with_capacity(s.len() + 3);
+            buf.push_str(&s[..text_end]);
+            buf.push_str("\u{1b}[K");
+            buf.push_str(&s[text_end..]);
+            buf
+        }
+        #[cfg(feature = "unicode-width")]
+        FillMode::PadSpaces(width) => pad_right_with(s, width, ' ', PadStyle::Background),
+    }
+}
+
+// Returns a byte index right after the last visible character
+// if a background color is active at it.
+fn background_end(s: &str) -> Option<usize> {
+    let mut state = AnsiState::default();
+    let mut text_state = None;
+    let mut text_end = 0;
+    let mut rest = s;
+    while let Some((token, next)) = split_first_token(rest) {
+        match token {
+            Token::Text(_) => {
+                text_end = s.len() - next.len();
+                text_state = Some(state.clone());
+            }
+            Token::Escape(seq) if seq.is_sgr() => update_ansi_state_by_escape(&mut state, &seq),
+            Token::Escape(_) => {}
+        }
+
+        rest = next;
+    }
+
+    let has_background = text_state.is_some_and(|state| state.bg_color.is_some());
+    has_background.then_some(text_end)
+}
+
+// Bounds are byte index
+// It's not safe to go over grapheme boundres.
+fn cut<S, R>(string: S, bounds: R) -> String
+where
+    S: AsRef<str>,
+    R: RangeBounds<usize>,
+{
+    let string = string.as_ref();
+    let (start, end) = bounds_to_usize(bounds.start_bound(), bounds.end_bound());
+
+    cut_str(string, start, end)
+}
+
+// Bounds are byte index
+fn cut_with<S, R>(string: S, bounds: R, options: CutOptions) -> String
+where
+    S: AsRef<str>,
+    R: RangeBounds<usize>,
+{
+    let string = string.as_ref();
+    let (start, end) = bounds_to_usize(bounds.start_bound(), bounds.end_bound());
+
+    match try_cut_str(string, start, end, options) {
+        Ok(buf) => buf,
+        Err(err) => panic!("{}", err),
+    }
+}
+
+// Bounds are byte index
+fn cut_open<S, R>(string: S, bounds: R) -> (String, Style)
+where
+    S: AsRef<str>,
+    R: RangeBounds<usize>,
+{
+    let string = string.as_ref();
+    let (start, end) = bounds_to_usize(bounds.start_bound(), bounds.end_bound());
+
+    let options = CutOptions::default().reset(ResetPolicy::None);
+    match try_cut_to_with_state(string, start, end, options, String::new()) {
+        Ok((buf, state)) => (buf, Style { state }),
+        Err(err) => panic!("{}", err),
+    }
+}
+
+// Bounds are byte index
+fn cut_cow<R>(string: &str, bounds: R) -> Cow<'_, str>
+where
+    R: RangeBounds<usize>,
+{
+    let (start, end) = bounds_to_usize(bounds.start_bound(), bounds.end_bound());
+    match find_borrowed_cut(string, start, end) {
+        Some(range) => Cow::Borrowed(&string[range]),
+        None => Cow::Owned(cut_str(string, start, end)),
+    }
+}
+
+// Returns a range of the original string which is the same as a cut of it.
+//
+// It goes the same way as try_cut_str does but instead of copying the parts of a cut
+// it checks that they follow each other in the original string.
+// It also checks that escape sequences are printed the same way as they are written,
+// because a cut prints them instead of copying.
+fn find_borrowed_cut(
+    string: &str,
+    lower_bound: usize,
+    upper_bound: Option<usize>,
+) -> Option<Range<usize>> {
+    let mut state = AnsiState::default();
+    let mut found: Option<Range<usize>> = None;
+    // a position of the first visible char of a cut
+    let mut text_start = None;
+    let mut index = 0;
+    let mut pos = 0;
+
+    let mut push = |part: Range<usize>| match &mut found {
+        Some(found) if found.end == part.start => {
+            found.end = part.end;
+            true
+        }
+        Some(_) => false,
+        None => {
+            found = Some(part);
+            true
+        }
+    };
+
+    for token in parse_ansi(string) {
+        match token {
+            Token::Text(text) => {
+                pos = text.as_ptr() as usize - string.as_ptr() as usize;
+
+                if matches!(upper_bound, Some(upper_bound) if upper_bound <= index) {
+                    break;
+                }
+
+                let block_end_index = index + text.len();
+                if lower_bound <= block_end_index {
+                    let start = lower_bound.saturating_sub(index);
+                    let end = match upper_bound {
+                        Some(upper_bound) if upper_bound < block_end_index => upper_bound - index,
+                        _ => text.len(),
+                    };
+
+                    text.get(start..end)?;
+
+                    if start < end && text_start.is_none() {
+                        text_start = Some(pos + start);
+                    }
+
+                    if !push(pos + start..pos + end) {
+                        return None;
+                    }
+
+                    if end < text.len() {
+                        break;
+                    }
+                }
+
+                index = block_end_index;
+                pos += text.len();
+            }
+            Token::Escape(seq) => {
+                let seq_str = seq.to_string();
+                if !string[pos..].starts_with(&seq_str) || !push(pos..pos + seq_str.len()) {
+                    return None;
+                }
+
+                pos += seq_str.len();
+
+                update_ansi_state_by_escape(&mut state, &seq);
+            }
+        }
+    }
+
+    let mut closing = String::new();
+    complete_ansi_sequences(&state, &mut closing);
+    if !closing.is_empty() {
+        return None;
+    }
+
+    let found = found.unwrap_or(0..0);
+
+    // a cut prunes escape sequences which it starts with
+    if lower_bound > 0 {
+        let skipped = &string[found.start..text_start.unwrap_or(found.end)];
+        if prune_escapes(skipped) != skipped {
+            return None;
+        }
+    }
+
+    Some(found)
+}
+
+// Bounds are byte index
+fn cut_out<S, R>(string: S, bounds: R) -> String
+where
+    S: AsRef<str>,
+    R: RangeBounds<usize>,
+{
+    let (start, end) = bounds_to_usize(bounds.start_bound(), bounds.end_bound());
+    replace_range_str(string.as_ref(), start, end, "", InsertStyle::Inherit)
+}
+
+// Bounds are byte index
+fn replace_range_visible<S, R>(string: S, bounds: R, replacement: &str) -> String
+where
+    S: AsRef<str>,
+    R: RangeBounds<usize>,
+{
+    let (start, end) = bounds_to_usize(bounds.start_bound(), bounds.end_bound());
+    replace_range_str(
+        string.as_ref(),
+        start,
+        end,
+        replacement,
+        InsertStyle::Inherit,
+    )
+}
+
+// Index is char index
+fn insert_str_visible<S>(string: S, index: usize, text: &str, style: InsertStyle) -> String
+where
+    S: AsRef<str>,
+{
+    let string = string.as_ref();
+    let stripped = srip_ansi_sequences(string);
+    let index = chars_to_bytes(&stripped, index);
+
+    replace_range_str(string, index, Some(index), text, style)
+}
+
+fn replace_range_str(
+    string: &str,
+    start: usize,
+    end: Option<usize>,
+    replacement: &str,
+    style: InsertStyle,
+) -> String {
+    let end = end.map_or(usize::MAX, |end| end.max(start));
+    // an isolated replacement is put before escape sequences at the start of the range
+    // so there's no need to close and reopen them
+    let take_leading_escapes = start < end || style == InsertStyle::Isolate;
+
+    let mut state = AnsiState::default();
+    // a style which is active in the output at the end of the replacement,
+    // it's set while we're inside of the range
+    let mut seam_state = None;
+    let mut is_range_passed = false;
+    let mut buf = String::new();
+    let mut index = 0;
+
+    for token in parse_ansi(string) {
+        match token {
+            Token::Text(mut text) => {
+                let block_start = index;
+                index += text.len();
+
+                if !is_range_passed {
+                    if seam_state.is_none() {
+                        if index <= start {
+                            buf.push_str(text);
+                            continue;
+                        }
+
+                        let (head, tail) = split_text(text, start - block_start, block_start);
+                        buf.push_str(head);
+                        text = tail;
+                        seam_state = Some(push_replacement(&mut buf, &state, replacement, style));
+                    }
+
+                    if index <= end {
+                        continue;
+                    }
+
+                    let text_start = index - text.len();
+                    text = split_text(text, end - text_start, text_start).1;
+
+                    if let Some(seam_state) = seam_state.take() {
+                        let (closing, opening) = diff_ansi_states(&seam_state, &state);
+                        complete_ansi_sequences(&closing, &mut buf);
+                        open_ansi_sequences(&opening, &mut buf);
+                    }
+
+                    is_range_passed = true;
+                }
+
+                buf.push_str(text);
+            }
+            Token::Escape(seq) => {
+                if !is_range_passed
+                    && seam_state.is_none()
+                    && index >= start
+                    && take_leading_escapes
+                {
+                    seam_state = Some(push_replacement(&mut buf, &state, replacement, style));
+                }
+
+                if seam_state.is_none() {
+                    buf.push_str(&seq.to_string());
+                }
+
+                update_ansi_state_by_escape(&mut state, &seq);
+            }
+        }
+    }
+
+    if !is_range_passed && seam_state.is_none() {
+        seam_state = Some(push_replacement(&mut buf, &state, replacement, style));
+    }
+
+    if let Some(seam_state) = seam_state {
+        let (closing, _) = diff_ansi_states(&seam_state, &state);
+        complete_ansi_sequences(&closing, &mut buf);
+    }
+
+    buf
+}
+
+// Pushes a replacement and returns a style active after it.
+fn push_replacement(
+    buf: &mut String,
+    state: &AnsiState,
+    replacement: &str,
+    style: InsertStyle,
+) -> AnsiState {
+    let mut state = match style {
+        InsertStyle::Inherit => state.clone(),
+        InsertStyle::Isolate => {
+            complete_ansi_sequences(state, buf);
+            AnsiState::default()
+        }
+    };
+
+    buf.push_str(replacement);
+    update_ansi_state_by_str(&mut state, replacement);
+
+    state
+}
+
+// Splits a text block at an offset,
+// index is a position of the text block in the string not containing ANSI sequences.
+fn split_text(text: &str, offset: usize, index: usize) -> (&str, &str) {
+    if !text.is_char_boundary(offset) {
+        panic!("{}", char_boundary_error(text, offset, index));
+    }
+
+    text.split_at(offset)
+}
+
+fn split_at_visible<S>(string: S, mid: usize) -> (String, String)
+where
+    S: AsRef<str>,
+{
+    let string = string.as_ref();
+    let mut state = AnsiState::default();
+    let mut head = String::new();
+    let mut tail = String::new();
+    let mut head_done = false;
+    let mut tail_started = false;
+    let mut index = 0;
+
+    for token in parse_ansi(string) {
+        if !head_done && index >= mid {
+            complete_ansi_sequences(&state, &mut head);
+            head_done = true;
+        }
+
+        match token {
+            Token::Text(mut text) => {
+                if !head_done {
+                    let split = mid - index;
+                    if split >= text.len() {
+                        head.push_str(text);
+                        index += text.len();
+                        continue;
+                    }
+
+                    if !text.is_char_boundary(split) {
+                        panic!("{}", char_boundary_error(text, split, index));
+                    }
+
+                    head.push_str(&text[..split]);
+                    complete_ansi_sequences(&state, &mut head);
+                    head_done = true;
+
+                    text = &text[split..];
+                }
+
+                if !tail_started {
+                    open_ansi_sequences(&state, &mut tail);
+                    tail_started = true;
+                }
+
+                tail.push_str(text);
+                index += text.len();
+            }
+            Token::Escape(seq) => {
+                if !head_done {
+                    head.push_str(&seq.to_string());
+                } else if tail_started {
+                    tail.push_str(&seq.to_string());
+                }
+
+                update_ansi_state_by_escape(&mut state, &seq);
+            }
+        }
+    }
+
+    if !head_done {
+        complete_ansi_sequences(&state, &mut head);
+    }
+
+    if tail_started {
+        complete_ansi_sequences(&state, &mut tail);
+    }
+
+    (head, tail)
+}
+
+#[cfg(feature = "unicode-width")]
+fn split_at_width<S>(string: S, cols: usize, policy: WideCharPolicy) -> (String, String)
+where
+    S: AsRef<str>,
+{
+    let string = string.as_ref();
+    let stripped = srip_ansi_sequences(string);
+    let (mid, pad) = width_to_end_byte(&stripped, cols);
+
+    let (mut head, tail) = split_at_visible(string, mid);
+    if policy == WideCharPolicy::Pad {
+        push_spaces(&mut head, pad);
+    }
+
+    (head, tail)
+}
+
+fn skip_visible<S>(string: S, n: usize) -> String
+where
+    S: AsRef<str>,
+{
+    let string = string.as_ref();
+    if n == 0 {
+        return cut_str(string, 0, None);
+    }
+
+    let stripped = srip_ansi_sequences(string);
+    let start = chars_to_bytes(&stripped, n);
+
+    // it's safe to unwrap as start is on a char boundary
+    try_cut_str(string, start, None, CutOptions::default().minimal(true)).unwrap()
+}
+
+fn tail_visible<S>(string: S, n: usize) -> String
+where
+    S: AsRef<str>,
+{
+    let string = string.as_ref();
+    let blocks = parse_ansi(string)
+        .filter_map(|token| match token {
+            Token::Text(text) => Some(text),
+            Token::Escape(_) => None,
+        })
+        .collect::<Vec<_>>();
+
+    let mut start = blocks.iter().map(|text| text.len()).sum::<usize>();
+    let mut chars = blocks.iter().rev().flat_map(|text| text.chars().rev());
+    for _ in 0..n {
+        match chars.next() {
+            Some(c) => start -= c.len_utf8(),
+            None => break,
+        }
+    }
+
+    if start == 0 {
+        return cut_str(string, 0, None);
+    }
+
+    // it's safe to unwrap as start is on a char boundary
+    try_cut_str(string, start, None, CutOptions::default().minimal(true)).unwrap()
+}
+
+// Bounds are byte index which are snapped to char boundaries
+fn cut_lossy<S, R>(string: S, bounds: R) -> String
+where
+    S: AsRef<str>,
+    R: RangeBounds<usize>,
+{
+    let string = string.as_ref();
+    let (start, end) = bounds_to_usize(bounds.start_bound(), bounds.end_bound());
+
+    let stripped = srip_ansi_sequences(string);
+    let start = ceil_char_boundary(&stripped, start);
+    let end = end.map(|end| floor_char_boundary(&stripped, end).max(start));
+
+    cut_str(string, start, end)
+}
+
+fn ceil_char_boundary(s: &str, mut index: usize) -> usize {
+    while index < s.len() && !s.is_char_boundary(index) {
+        index += 1;
+    }
+
+    index
+}
+
+fn floor_char_boundary(s: &str, mut index: usize) -> usize {
+    while index < s.len() && !s.is_char_boundary(index) {
+        index -= 1;
+    }
+
+    index
+}
+
+// Bounds are char index
+fn cut_chars<S, R>(string: S, bounds: R) -> String
+where
+    S: AsRef<str>,
+    R: RangeBounds<usize>,
+{
+    cut_chars_with(string, bounds, CutOptions::default())
+}
+
+// Bounds are char index
+fn cut_chars_with<S, R>(string: S, bounds: R, options: CutOptions) -> String
+where
+    S: AsRef<str>,
+    R: RangeBounds<usize>,
+{
+    let string = string.as_ref();
+    let (start, end) = bounds_to_usize(bounds.start_bound(), bounds.end_bound());
+
+    let stripped = srip_ansi_sequences(string);
+    let start = chars_to_bytes(&stripped, start);
+    let end = end.map(|end| chars_to_bytes(&stripped, end).max(start));
+
+    match try_cut_str(string, start, end, options) {
+        Ok(buf) => buf,
+        Err(err) => panic!("{}", err),
+    }
+}
+
+// Bounds are display columns
+#[cfg(feature = "unicode-width")]
+fn cut_width<S, R>(string: S, bounds: R, policy: WideCharPolicy) -> String
+where
+    S: AsRef<str>,
+    R: RangeBounds<usize>,
+{
+    cut_width_with(string, bounds, policy, CutOptions::default())
+}
+
+// Bounds are display columns
+#[cfg(feature = "unicode-width")]
+fn cut_width_with<S, R>(string: S, bounds: R, policy: WideCharPolicy, options: CutOptions) -> String
+where
+    S: AsRef<str>,
+    R: RangeBounds<usize>,
+{
+    let string = string.as_ref();
+    let (start, end) = bounds_to_usize(bounds.start_bound(), bounds.end_bound());
+
+    let stripped = srip_ansi_sequences(string);
+    let (start_byte, mut pad_left) = width_to_start_byte(&stripped, start);
+    let (end, pad_right) = match end {
+        Some(end) => {
+            let (end_byte, pad) = width_to_end_byte(&stripped, end);
+
+            // an empty range or one inside of a wide char can't take more columns than it has
+            let width = end.saturating_sub(start);
+            pad_left = pad_left.min(width);
+            (Some(end_byte.max(start_byte)), pad.min(width - pad_left))
+        }
+        None => (None, 0),
+    };
+    let start = start_byte;
+
+    let cut = match try_cut_str(string, start, end, options) {
+        Ok(buf) => buf,
+        Err(err) => panic!("{}", err),
+    };
+
+    match policy {
+        WideCharPolicy::Exclude => cut,
+        WideCharPolicy::Pad => {
+            let mut buf = String::with_capacity(cut.len() + pad_left + pad_right);
+            push_spaces(&mut buf, pad_left);
+            buf.push_str(&cut);
+            push_spaces(&mut buf, pad_right);
+            buf
+        }
+    }
+}
+
+#[cfg(feature = "unicode-width")]
+fn push_spaces(buf: &mut String, n: usize) {
+    for _ in 0..n {
+        buf.push(' ');
+    }
+}
+
+#[cfg(feature = "unicode-width")]
+fn char_width(c: char) -> usize {
+    // control chars like `\r` or `\t` take no columns by themselves
+    if c.is_control() {
+        return 0;
+    }
+
+    unicode_width::UnicodeWidthChar::width(c).unwrap_or(0)
+}
+
+// Returns a byte index of the first not zero width character which starts at or after the column,
+// zero width characters are kept together with a character they follow.
+//
+// The second value is a number of columns skipped because of a split wide character.
+#[cfg(feature = "unicode-width")]
+fn width_to_start_byte(s: &str, column: usize) -> (usize, usize) {
+    if column == 0 {
+        return (0, 0);
+    }
+
+    let mut width = 0;
+    for (i, c) in s.char_indices() {
+        let w = char_width(c);
+        if width >= column && w > 0 {
+            return (i, width - column);
+        }
+
+        width += w;
+    }
+
+    (s.len(), width.saturating_sub(column))
+}
+
+// Returns a byte index right after the last character which ends at or before the column,
+// including zero width characters which follow it.
+//
+// The second value is a number of columns left because of a split wide character.
+#[cfg(feature = "unicode-width")]
+fn width_to_end_byte(s: &str, column: usize) -> (usize, usize) {
+    let mut width = 0;
+    for (i, c) in s.char_indices() {
+        let w = char_width(c);
+        if width + w > column {
+            return (i, column - width);
+        }
+
+        width += w;
+    }
+
+    (s.len(), 0)
+}
+
+// Bounds are grapheme index
+#[cfg(feature = "unicode-segmentation")]
+fn cut_graphemes<S, R>(string: S, bounds: R) -> String
+where
+    S: AsRef<str>,
+    R: RangeBounds<usize>,
+{
+    let string = string.as_ref();
+    let (start, end) = bounds_to_usize(bounds.start_bound(), bounds.end_bound());
+
+    let stripped = srip_ansi_sequences(string);
+    let start = graphemes_to_bytes(&stripped, start);
+    let end = end.map(|end| graphemes_to_bytes(&stripped, end));
+
+    cut_str(string, start, end)
+}
+
+#[cfg(feature = "unicode-segmentation")]
+fn graphemes_to_bytes(s: &str, graphemes: usize) -> usize {
+    use unicode_segmentation::UnicodeSegmentation;
+
+    s.grapheme_indices(true)
+        .nth(graphemes)
+        .map_or(s.len(), |(i, _)| i)
+}
+
+fn chars_to_bytes(s: &str, chars: usize) -> usize {
+    s.chars().map(|c| c.len_utf8()).take(chars).sum()
+}
+
+fn try_cut<S, R>(string: S, bounds: R) -> Result<String, AnsiCutError>
+where
+    S: AsRef<str>,
+    R: RangeBounds<usize>,
+{
+    try_cut_with(string, bounds, CutOptions::default())
+}
+
+fn try_cut_with<S, R>(string: S, bounds: R, options: CutOptions) -> Result<String, AnsiCutError>
+where
+    S: AsRef<str>,
+    R: RangeBounds<usize>,
+{
+    let string = string.as_ref();
+    let (start, end) = bounds_to_usize(bounds.start_bound(), bounds.end_bound());
+    if let Some(end) = end {
+        if start > end {
+            return Err(AnsiCutError::StartAfterEnd { start, end });
+        }
+    }
+
+    try_cut_str(string, start, end, options)
+}
+
+fn cut_str(string: &str, lower_bound: usize, upper_bound: Option<usize>) -> String {
+    match try_cut_str(string, lower_bound, upper_bound, CutOptions::default()) {
+        Ok(buf) => buf,
+        Err(err) => panic!("{}", err),
+    }
+}
+
+// Cuts a string and closes all styles which are left open.
+fn try_cut_str(
+    string: &str,
+    lower_bound: usize,
+    upper_bound: Option<usize>,
+    options: CutOptions,
+) -> Result<String, AnsiCutError> {
+    match try_cut_to(string, lower_bound, upper_bound, options, String::new()) {
+        Ok(buf) => Ok(buf),
+        Err(CutError::Index(err)) => Err(err),
+        Err(CutError::Write(_)) => unreachable!("writing into a string never fails"),
+    }
+}
+
+fn try_cut_to<W>(
+    string: &str,
+    lower_bound: usize,
+    upper_bound: Option<usize>,
+    options: CutOptions,
+    out: W,
+) -> Result<W, CutError>
+where
+    W: fmt::Write,
+{
+    let (out, _) = try_cut_to_with_state(string, lower_bound, upper_bound, options, out)?;
+    Ok(out)
+}
+
+// The same as try_cut_to but also returns a state active at the end of the cut.
+fn try_cut_to_with_state<W>(
+    string: &str,
+    lower_bound: usize,
+    upper_bound: Option<usize>,
+    options: CutOptions,
+    out: W,
+) -> Result<(W, AnsiState), CutError>
+where
+    W: fmt::Write,
+{
+    let (lower_bound, upper_bound) = match options.grapheme {
+        GraphemePolicy::Allow => (lower_bound, upper_bound),
+        policy => {
+            let mut stripped = srip_ansi_sequences(string);
+            stripped.retain(|c| options.is_counted(c));
+            let lower_bound = apply_grapheme_policy(&stripped, lower_bound, policy)?;
+            let upper_bound = match upper_bound {
+                Some(bound) => Some(apply_grapheme_policy(&stripped, bound, policy)?),
+                None => None,
+            };
+
+            (lower_bound, upper_bound)
+        }
+    };
+
+    // a plain string is cut as a slice,
+    // the general way is taken on an error so it's reported the same way
+    if options.count_controls && is_plain(string) {
+        let end = upper_bound.map_or(string.len(), |bound| bound.min(string.len()));
+        if let Some(text) = string.get(lower_bound.min(end)..end) {
+            let mut out = out;
+            if !text.is_empty() {
+                out.write_str(text)?;
+            }
+
+            return Ok((out, AnsiState::default()));
+        }
+    }
+
+    let mut asci_state = AnsiState::default();
+    let mut cut = CutWriter::new(lower_bound, upper_bound, options, out);
+    let mut index = 0;
+
+    for token in parse_ansi(string) {
+        push_token(&mut cut, token, &mut asci_state, &mut index, options)?;
+
+        if cut.done {
+            break;
+        }
+    }
+
+    let out = cut.finish(&asci_state)?;
+
+    Ok((out, asci_state))
+}
+
+// Writes a token into a cut and updates a state and an index by it.
+fn push_token<W>(
+    cut: &mut CutWriter<W>,
+    token: Token<'_>,
+    state: &mut AnsiState,
+    index: &mut usize,
+    options: CutOptions,
+) -> Result<(), CutError>
+where
+    W: fmt::Write,
+{
+    match token {
+        Token::Text(text) if !options.count_controls => {
+            let mut rest = text;
+            while !rest.is_empty() {
+                let end = rest.find(|c| !options.is_counted(c)).unwrap_or(rest.len());
+                let (text, controls) = rest.split_at(end);
+                if !text.is_empty() {
+                    cut.push_text(*index, text, state)?;
+                    *index += text.len();
+                }
+
+                // a control char is a single byte
+                if !controls.is_empty() {
+                    cut.push_control(&controls[..1])?;
+                }
+
+                rest = controls.get(1..).unwrap_or_default();
+            }
+        }
+        Token::Text(text) => {
+            cut.push_text(*index, text, state)?;
+            *index += text.len();
+        }
+        Token::Escape(seq) => {
+            match seq {
+                Escape::C1(raw) if options.c1 == C1Policy::Keep => cut.push_escape(raw)?,
+                _ => cut.push_escape(&seq)?,
+            }
+
+            update_ansi_state_by_escape(state, &seq);
+        }
+    }
+
+    Ok(())
+}
+
+// CutFormatter is a cut which is written by a formatter piece by piece,
+// so a value can be cut without being collected into a string first.
+//
+// An escape sequence which is split between pieces is held until the rest of it is written.
+// Once the cut is done an error is returned to stop formatting.
+struct CutFormatter<W> {
+    cut: CutWriter<W>,
+    options: CutOptions,
+    state: AnsiState,
+    index: usize,
+    // the beginning of an escape sequence which is cut off at the end of a written piece
+    pending: String,
+    error: Option<CutError>,
+}
+
+impl<W> CutFormatter<W>
+where
+    W: fmt::Write,
+{
+    fn new(cut: CutWriter<W>, options: CutOptions) -> Self {
+        Self {
+            cut,
+            options,
+            state: AnsiState::default(),
+            index: 0,
+            pending: String::new(),
+            error: None,
+        }
+    }
+
+    fn push(&mut self, s: &str) -> Result<(), CutError> {
+        let mut tokens = parse_ansi(s);
+        for token in &mut tokens {
+            push_token(
+                &mut self.cut,
+                token,
+                &mut self.state,
+                &mut self.index,
+                self.options,
+            )?;
+
+            if self.cut.done {
+                return Ok(());
+            }
+        }
+
+        self.pending.push_str(tokens.truncated);
+
+        Ok(())
+    }
+
+    // A truncated escape sequence which is left pending is dropped as it's done for a string.
+    fn finish(self) -> Result<W, CutError> {
+        if let Some(err) = self.error {
+            return Err(err);
+        }
+
+        Ok(self.cut.finish(&self.state)?)
+    }
+}
+
+impl<W> fmt::Write for CutFormatter<W>
+where
+    W: fmt::Write,
+{
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        if self.cut.done || self.error.is_some() {
+            return Err(fmt::Error);
+        }
+
+        let result = if self.pending.is_empty() {
+            self.push(s)
+        } else {
+            let mut buf = std::mem::take(&mut self.pending);
+            buf.push_str(s);
+            self.push(&buf)
+        };
+
+        if let Err(err) = result {
+            self.error = Some(err);
+            return Err(fmt::Error);
+        }
+
+        Ok(())
+    }
+}
+
+// Checks an index against a grapheme policy and returns an index to be used.
+//
+// An index which is not on a char boundary is returned as it is,
+// so it's reported by a cut itself.
+fn apply_grapheme_policy(
+    s: &str,
+    mut index: usize,
+    policy: GraphemePolicy,
+) -> Result<usize, AnsiCutError> {
+    if !s.is_char_boundary(index) || is_grapheme_boundary(s, index) {
+        return Ok(index);
+    }
+
+    if policy == GraphemePolicy::Error {
+        return Err(AnsiCutError::NotAGraphemeBoundary { index });
+    }
+
+    while !is_grapheme_boundary(s, index) {
+        index += s[index..].chars().next().map_or(0, char::len_utf8);
+    }
+
+    Ok(index)
+}
+
+// Checks whether an index doesn't split a char from marks which extend it.
+//
+// It's not a full segmentation, but it's cheap as ASCII chars are never checked against tables.
+fn is_grapheme_boundary(s: &str, index: usize) -> bool {
+    if index == 0 || index >= s.len() {
+        return true;
+    }
+
+    let next = s[index..].chars().next();
+    let prev = s[..index].chars().next_back();
+    match (prev, next) {
+        (_, Some(c)) if c.is_ascii() => true,
+        (Some('\u{200d}'), _) => false,
+        (_, Some(c)) => !is_extending_char(c),
+        _ => true,
+    }
+}
+
+fn is_extending_char(c: char) -> bool {
+    matches!(
+        c,
+        '\u{300}'..='\u{36f}'
+            | '\u{1ab0}'..='\u{1aff}'
+            | '\u{1dc0}'..='\u{1dff}'
+            | '\u{200d}'
+            | '\u{20d0}'..='\u{20ff}'
+            | '\u{fe00}'..='\u{fe0f}'
+            | '\u{fe20}'..='\u{fe2f}'
+            | '\u{1f3fb}'..='\u{1f3ff}'
+            | '\u{e0020}'..='\u{e007f}'
+            | '\u{e0100}'..='\u{e01ef}'
+    )
+}
+
+// CutWriter is a cut of a string which is written token by token.
+//
+// If minimal is set, escape sequences met before the lower bound are not copied,
+// instead the style active at the lower bound is emitted;
+// in which case a cut with nothing in range is an empty string.
+struct CutWriter<W = String> {
+    lower_bound: usize,
+    upper_bound: Option<usize>,
+    reset: ResetPolicy,
+    unknown: UnknownPolicy,
+    strict: bool,
+    drop_osc: bool,
+    control: ControlPolicy,
+    started: bool,
+    done: bool,
+    // whether no text was written yet
+    empty: bool,
+    // escape sequences which are held until the first text in strict mode
+    pending: String,
+    // whether escape sequences met before the first text of a cut are collected to be pruned,
+    // which is only done if the cut doesn't start at the beginning of a string
+    pruning: bool,
+    // escape sequences met before the first text of a cut
+    skipped: String,
+    // a position in the string not containing ANSI sequences which the written text ends at
+    index: usize,
+    // a state which is left open at the end of the cut
+    // while it's waiting for an escape sequence to close it (see ResetPolicy::ReuseOriginal)
+    closing: Option<AnsiState>,
+    out: W,
+}
+
+enum CutError {
+    Index(AnsiCutError),
+    Write(fmt::Error),
+}
+
+// Makes an error for an offset inside of a text block which is not on a char boundary,
+// index is a position of the text block in the string not containing ANSI sequences.
+fn char_boundary_error(text: &str, offset: usize, index: usize) -> AnsiCutError {
+    let prev = (0..offset)
+        .rev()
+        .find(|&i| text.is_char_boundary(i))
+        .unwrap_or(0);
+    let ch = text[prev..].chars().next().unwrap_or_default();
+
+    AnsiCutError::NotACharBoundary {
+        index: index + offset,
+        ch,
+        prev_boundary: index + prev,
+        next_boundary: index + prev + ch.len_utf8(),
+    }
+}
+
+impl From<AnsiCutError> for CutError {
+    fn from(err: AnsiCutError) -> Self {
+        Self::Index(err)
+    }
+}
+
+impl fmt::Display for CutError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CutError::Index(err) => err.fmt(f),
+            CutError::Write(err) => err.fmt(f),
+        }
+    }
+}
+
+impl From<fmt::Error> for CutError {
+    fn from(err: fmt::Error) -> Self {
+        Self::Write(err)
+    }
+}
+
+impl<W> CutWriter<W>
+where
+    W: fmt::Write,
+{
+    fn new(lower_bound: usize, upper_bound: Option<usize>, options: CutOptions, out: W) -> Self {
+        Self {
+            lower_bound,
+            upper_bound,
+            reset: options.reset,
+            unknown: options.unknown,
+            strict: options.strict,
+            drop_osc: options.drop_osc,
+            control: options.control,
+            started: !options.minimal,
+            done: false,
+            empty: true,
+            pending: String::new(),
+            pruning: !options.minimal && lower_bound > 0,
+            skipped: String::new(),
+            index: 0,
+            closing: None,
+            out,
+        }
+    }
+
+    // Index is a position of the text block in the string not containing ANSI sequences.
+    fn push_text(&mut self, index: usize, text: &str, state: &AnsiState) -> Result<(), CutError> {
+        if self.done || self.closing.is_some() {
+            return Ok(());
+        }
+
+        if matches!(self.upper_bound, Some(upper_bound) if upper_bound <= index) {
+            self.close(state)?;
+            return Ok(());
+        }
+
+        let block_end_index = index + text.len();
+        self.index = block_end_index;
+        if self.lower_bound > block_end_index
+            || (!self.started && self.lower_bound == block_end_index)
+        {
+            return Ok(());
+        };
+
+        let mut start = 0;
+        if self.lower_bound > index {
+            start = self.lower_bound - index;
+        }
+
+        let mut end = text.len();
+        let mut done = false;
+        if let Some(upper_bound) = self.upper_bound {
+            if upper_bound > index && upper_bound < block_end_index {
+                end = upper_bound - index;
+                done = true;
+            }
+        }
+
+        match text.get(start..end) {
+            Some(text) => {
+                if self.strict && text.is_empty() {
+                    if done {
+                        self.close(state)?;
+                    }
+
+                    return Ok(());
+                }
+
+                if !self.started {
+                    write_open_ansi_sequences(state, &mut self.out)?;
+                    if self.unknown == UnknownPolicy::ReplayRaw {
+                        for code in &state.unknown {
+                            write!(self.out, "\u{1b}[{}m", code)?;
+                        }
+                    }
+
+                    self.started = true;
+                }
+
+                if !text.is_empty() {
+                    self.flush_skipped()?;
+                }
+
+                if self.empty {
+                    self.empty = false;
+                    self.out.write_str(&self.pending)?;
+                }
+
+                self.out.write_str(text)?;
+                if done {
+                    self.close(state)?;
+                }
+
+                Ok(())
+            }
+            None => {
+                let offset = if text.is_char_boundary(start) {
+                    end
+                } else {
+                    start
+                };
+
+                Err(char_boundary_error(text, offset, index).into())
+            }
+        }
+    }
+
+    fn push_escape<D>(&mut self, seq: &D) -> fmt::Result
+    where
+        D: fmt::Display + ?Sized,
+    {
+        if self.drop_osc || self.control != ControlPolicy::Preserve {
+            let mut seq = seq.to_string();
+            if self.drop_osc {
+                seq = drop_osc_sequences(&seq);
+            }
+
+            if self.control != ControlPolicy::Preserve {
+                let is_after_end = matches!(self.upper_bound, Some(upper) if upper <= self.index);
+                seq = apply_control_policy(&seq, self.control, is_after_end);
+            }
+
+            return self.write_escape(seq.as_str());
+        }
+
+        self.write_escape(seq)
+    }
+
+    // Writes a control char which doesn't count toward indexes,
+    // it's copied like an escape sequence but it's never reused to close a cut.
+    fn push_control(&mut self, control: &str) -> fmt::Result {
+        if self.closing.is_some() {
+            return Ok(());
+        }
+
+        self.write_escape(control)
+    }
+
+    fn write_escape<D>(&mut self, seq: &D) -> fmt::Result
+    where
+        D: fmt::Display + ?Sized,
+    {
+        if let Some(state) = self.closing.take() {
+            let seq = seq.to_string();
+            let mut next_state = state.clone();
+            update_ansi_state_by_str(&mut next_state, &seq);
+
+            self.done = true;
+            if is_closed(&next_state) {
+                self.out.write_str(&seq)?;
+            } else if is_partial_closer(&state, &next_state) {
+                // styles which the sequence leaves open are closed after it
+                self.out.write_str(&seq)?;
+                write_complete_ansi_sequences(&next_state, &mut self.out, false)?;
+            } else {
+                write_complete_ansi_sequences(&state, &mut self.out, false)?;
+            }
+
+            return Ok(());
+        }
+
+        if self.started && !self.done {
+            if self.pruning {
+                self.skipped.push_str(&seq.to_string());
+            } else if self.strict && self.empty {
+                self.pending.push_str(&seq.to_string());
+            } else {
+                write!(self.out, "{}", seq)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn close(&mut self, state: &AnsiState) -> fmt::Result {
+        if self.done || self.closing.is_some() {
+            return Ok(());
+        }
+
+        self.done = true;
+        if !self.started || (self.strict && self.empty) {
+            return Ok(());
+        }
+
+        self.flush_skipped()?;
+
+        // unknown codes can be closed only by a full reset
+        let state = match self.unknown {
+            UnknownPolicy::FullResetOnUnknown => Cow::Borrowed(state),
+            UnknownPolicy::IgnoreUnknown | UnknownPolicy::ReplayRaw => Cow::Owned(AnsiState {
+                unknown: Vec::new(),
+                ..state.clone()
+            }),
+        };
+
+        match self.reset {
+            ResetPolicy::PerAttribute => {
+                write_complete_ansi_sequences(&state, &mut self.out, false)
+            }
+            ResetPolicy::ParserCompatible => {
+                write_complete_ansi_sequences(&state, &mut self.out, true)
+            }
+            ResetPolicy::FullReset if !is_closed(&state) => self.out.write_str("\u{1b}[0m"),
+            ResetPolicy::ReuseOriginal if !is_closed(&state) => {
+                self.done = false;
+                self.closing = Some(state.into_owned());
+                Ok(())
+            }
+            _ => Ok(()),
+        }
+    }
+
+    fn flush_skipped(&mut self) -> fmt::Result {
+        if !self.pruning {
+            return Ok(());
+        }
+
+        self.pruning = false;
+        let escapes = prune_escapes(&self.skipped);
+        if !escapes.is_empty() {
+            self.out.write_str(&escapes)?;
+        }
+
+        Ok(())
+    }
+
+    fn finish(mut self, state: &AnsiState) -> Result<W, fmt::Error> {
+        self.close(state)?;
+
+        // there's no escape sequence left to be reused
+        if let Some(state) = self.closing.take() {
+            write_complete_ansi_sequences(&state, &mut self.out, false)?;
+        }
+
+        Ok(self.out)
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+struct AnsiState {
+    fg_color: Option<AnsiColor>,
+    bg_color: Option<AnsiColor>,
+    undr_color: Option<AnsiColor>,
+    bold: bool,
+    faint: bool,
+    italic: bool,
+    underline: bool,
+    double_underline: bool,
+    // a curly (3), dotted (4) or dashed (5) underline set by `4:n`
+    underline_style: Option<u8>,
+    slow_blink: bool,
+    rapid_blink: bool,
+    inverse: bool,
+    hide: bool,
+    crossedout: bool,
+    reset: bool,
+    framed: bool,
+    encircled: bool,
+    font: Option<u8>,
+    fraktur: bool,
+    proportional_spacing: bool,
+    overlined: bool,
+    igrm_underline: bool,
+    igrm_double_underline: bool,
+    igrm_overline: bool,
+    igrm_double_overline: bool,
+    igrm_stress_marking: bool,
+    superscript: bool,
+    subscript: bool,
+    link: Option<Hyperlink>,
+    // parameters of codes which are not known as they were written
+    unknown: Vec<String>,
+    // sequences which look like SGR but are not modeled, like `\x1b[>4;2m`, as they were written,
+    // they are replayed as they are since there's no way to know what they do
+    raw: Vec<String>,
+    // codes which close styles in the order the styles were opened
+    opened: Vec<u8>,
+}
+
+// A hyperlink set by OSC 8, params are a list of `key=value` pairs like `id=1`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Hyperlink {
+    params: String,
+    uri: String,
+}
+
+// A color of a foreground, a background or an underline.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum AnsiColor {
+    // A basic color which is kept as its own SGR parameter (30..=37, 40..=47, 90..=97, 100..=107).
+    Bit4 { index: u8 },
+    // A 256 color palette index set by `38;5;n`, `48;5;n` or `58;5;n`.
+    Bit8 { index: u8 },
+    // A true color set by `38;2;r;g;b`, `48;2;r;g;b` or `58;2;r;g;b`.
+    Bit24 { r: u8, g: u8, b: u8 },
+}
+
+fn update_ansi_state(state: &mut AnsiState, mode: &[u8]) {
+    let mut ptr = mode;
+    loop {
+        if ptr.is_empty() {
+            break;
+        }
+
+        let tag = ptr[0];
+
+        match tag {
+            0 => {
+                // a hyperlink is not a graphic rendition so it stays
+                let link = state.link.take();
+                *state = AnsiState::default();
+                state.link = link;
+                state.reset = true;
+            }
+            1 => state.bold = true,
+            2 => state.faint = true,
+            3 => state.italic = true,
+            4 => {
+                state.underline = true;
+                state.underline_style = None;
+            }
+            5 => state.slow_blink = true,
+            6 => state.rapid_blink = true,
+            7 => state.inverse = true,
+            8 => state.hide = true,
+            9 => state.crossedout = true,
+            10 => state.font = None,
+            n @ 11..=19 => state.font = Some(n),
+            20 => state.fraktur = true,
+            21 => {
+                state.double_underline = true;
+                state.underline_style = None;
+            }
+            22 => {
+                state.faint = false;
+                state.bold = false;
+            }
+            23 => {
+                state.italic = false;
+                state.fraktur = false;
+            }
+            24 => {
+                state.underline = false;
+                state.double_underline = false;
+                state.underline_style = None;
+            }
+            25 => {
+                state.slow_blink = false;
+                state.rapid_blink = false;
+            }
+            26 => {
+                state.proportional_spacing = true;
+            }
+            27 => {
+                state.inverse = false;
+            }
+            28 => {
+                state.hide = false;
+            }
+            29 => {
+                state.crossedout = false;
+            }
+            n @ 30..=37 | n @ 90..=97 => {
+                state.fg_color = Some(AnsiColor::Bit4 { index: n });
+            }
+            38 => {
+                if let Some((color, n)) = parse_ansi_color(&ptr[1..]) {
+                    state.fg_color = Some(color);
+                    ptr = &ptr[n..];
+                }
+            }
+            39 => {
+                state.fg_color = None;
+            }
+            n @ 40..=47 | n @ 100..=107 => {
+                state.bg_color = Some(AnsiColor::Bit4 { index: n });
+            }
+            48 => {
+                if let Some((color, n)) = parse_ansi_color(&ptr[1..]) {
+                    state.bg_color = Some(color);
+                    ptr = &ptr[n..];
+                }
+            }
+            49 => {
+                state.bg_color = None;
+            }
+            50 => {
+                state.proportional_spacing = false;
+            }
+            51 => {
+                state.framed = true;
+            }
+            52 => {
+                state.encircled = true;
+            }
+            53 => {
+                state.overlined = true;
+            }
+            54 => {
+                state.encircled = false;
+                state.framed = false;
+            }
+            55 => {
+                state.overlined = false;
+            }
+            58 => {
+                if let Some((color, n)) = parse_ansi_color(&ptr[1..]) {
+                    state.undr_color = Some(color);
+                    ptr = &ptr[n..];
+                }
+            }
+            59 => {
+                state.undr_color = None;
+            }
+            60 => {
+                state.igrm_underline = true;
+            }
+            61 => {
+                state.igrm_double_underline = true;
+            }
+            62 => {
+                state.igrm_overline = true;
+            }
+            63 => {
+                state.igrm_double_overline = true;
+            }
+            64 => {
+                state.igrm_stress_marking = true;
+            }
+            65 => {
+                state.igrm_underline = false;
+                state.igrm_double_underline = false;
+                state.igrm_overline = false;
+                state.igrm_double_overline = false;
+                state.igrm_stress_marking = false;
+            }
+            73 => {
+                state.superscript = true;
             }
             74 => {
                 state.subscript = true;
             }
-            75 => {
-                state.subscript = false;
-                state.superscript = false;
+            75 => {
+                state.subscript = false;
+                state.superscript = false;
+            }
+            _ => add_unknown_code(state, &tag.to_string()),
+        }
+
+        if let Some(code) = closing_code(tag) {
+            mark_opened(state, code);
+        }
+
+        ptr = &ptr[1..];
+    }
+}
+
+// Checks that a state has no styles which need to be closed.
+fn is_closed(state: &AnsiState) -> bool {
+    let mut buf = String::new();
+    complete_ansi_sequences(state, &mut buf);
+    buf.is_empty()
+}
+
+// Checks whether switching from one state to another closes some styles
+// but opens none, so a sequence which does it can be used to close a cut.
+fn is_partial_closer(from: &AnsiState, to: &AnsiState) -> bool {
+    let (closing, opening) = diff_ansi_states(from, to);
+    from.unknown == to.unknown && from.raw == to.raw && is_closed(&opening) && !is_closed(&closing)
+}
+
+// A token of a string, either a text or an escape sequence.
+//
+// Sequences are recognized by ansi-parser first,
+// the ones it doesn't know (any other SGR, CSI, OSC, DCS, SOS, PM and APC ones
+// and C1 introducers) are recognized by the crate itself, see `AnsiTokens`.
+#[derive(Debug, Clone, PartialEq)]
+enum Token<'a> {
+    Text(&'a str),
+    Escape(Escape<'a>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Escape<'a> {
+    // A sequence recognized by ansi-parser together with how it's written,
+    // which is what's printed as its parameters may be written differently than ansi-parser does.
+    Sequence(AnsiSequence, &'a str),
+    // Parameters of a SGR sequence as they are written.
+    Sgr(&'a str),
+    // An OSC sequence as it's written, including its terminator.
+    Osc(&'a str),
+    // A CSI sequence which ansi-parser doesn't recognize as it's written, like `\x1b[2K`.
+    Csi(&'a str),
+    // A CSI or OSC sequence with a C1 introducer as it's written,
+    // it's printed in its 7-bit form.
+    C1(&'a str),
+    // A DCS, SOS, PM or APC sequence as it's written, including its terminator,
+    // like `\x1bPq#0\x1b\\` which is a sixel image.
+    Str(&'a str),
+}
+
+impl<'a> Escape<'a> {
+    fn is_sgr(&self) -> bool {
+        match self {
+            Escape::Sequence(AnsiSequence::SetGraphicsMode(_), _) | Escape::Sgr(_) => true,
+            Escape::C1(seq) => seq.starts_with('\u{9b}') && seq.ends_with('m'),
+            _ => false,
+        }
+    }
+
+    // Returns the sequence as it's written if it's a C1 one,
+    // so it's kept as it is when a string of escape sequences is parsed again.
+    fn as_written(&self) -> Cow<'a, str> {
+        match self {
+            Escape::C1(seq) => Cow::Borrowed(seq),
+            escape => Cow::Owned(escape.to_string()),
+        }
+    }
+}
+
+impl fmt::Display for Escape<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Escape::Sgr(params) => write!(f, "\u{1b}[{}m", params),
+            Escape::Sequence(_, seq) | Escape::Osc(seq) | Escape::Csi(seq) | Escape::Str(seq) => {
+                f.write_str(seq)
+            }
+            Escape::C1(seq) => f.write_str(&c1_to_7bit(seq)),
+        }
+    }
+}
+
+impl fmt::Display for Token<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Token::Text(text) => f.write_str(text),
+            Token::Escape(escape) => escape.fmt(f),
+        }
+    }
+}
+
+// Splits a string into text blocks and escape sequences.
+fn parse_ansi(s: &str) -> AnsiTokens<'_> {
+    AnsiTokens {
+        rest: s,
+        truncated: "",
+    }
+}
+
+struct AnsiTokens<'a> {
+    rest: &'a str,
+    // an escape sequence which is cut off at the end of the string and so is dropped
+    truncated: &'a str,
+}
+
+impl<'a> Iterator for AnsiTokens<'a> {
+    type Item = Token<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let s = self.rest;
+            if s.is_empty() {
+                return None;
+            }
+
+            if !s.starts_with(ESCAPE_INTRODUCERS) {
+                let end = s.find(ESCAPE_INTRODUCERS).unwrap_or(s.len());
+                self.rest = &s[end..];
+
+                return Some(Token::Text(&s[..end]));
+            }
+
+            if is_truncated_escape(s) {
+                self.truncated = s;
+                self.rest = "";
+                return None;
+            }
+
+            if let Some((seq, rest)) = parse_c1(s) {
+                self.rest = rest;
+                return Some(Token::Escape(Escape::C1(seq)));
+            }
+
+            if let Some((seq, rest)) = parse_osc(s) {
+                self.rest = rest;
+                return Some(Token::Escape(Escape::Osc(seq)));
+            }
+
+            if let Some((seq, rest)) = parse_string_sequence(s) {
+                self.rest = rest;
+                return Some(Token::Escape(Escape::Str(seq)));
+            }
+
+            if let Ok((rest, seq)) = ansi_parser::parse_escape(s) {
+                self.rest = rest;
+                let written = &s[..s.len() - rest.len()];
+                return Some(Token::Escape(Escape::Sequence(seq, written)));
+            }
+
+            if let Some((params, rest)) = parse_sgr(s) {
+                self.rest = rest;
+                return Some(Token::Escape(Escape::Sgr(params)));
+            }
+
+            if let Some((seq, rest)) = parse_csi(s) {
+                self.rest = rest;
+                return Some(Token::Escape(Escape::Csi(seq)));
+            }
+
+            // an introducer which doesn't start a sequence is dropped and what follows it is a text,
+            // so it can't be glued to a sequence which is written after it
+            self.rest = &s[s.chars().next().map_or(0, char::len_utf8)..];
+        }
+    }
+}
+
+// Chars escape sequences start with, ESC and C1 CSI, OSC, DCS, SOS, PM and APC.
+const ESCAPE_INTRODUCERS: [char; 7] = [
+    '\u{1b}', '\u{9b}', '\u{9d}', '\u{90}', '\u{98}', '\u{9e}', '\u{9f}',
+];
+
+// A cursor forward is replaced by at most this many spaces by ControlPolicy::Normalize.
+const MAX_CURSOR_FORWARD: usize = 1024;
+
+// A CSI sequence with longer parameters is not considered to be a sequence,
+// so a malicious one can't make us scan a whole string looking for its end.
+const MAX_CSI_PARAMS_LEN: usize = 1024;
+
+// Finds a final byte of a CSI sequence which parameters are given,
+// returns its index.
+fn find_csi_final(params: &str) -> Option<usize> {
+    // parameter and intermediate bytes followed by a final one
+    let end = params
+        .bytes()
+        .take(MAX_CSI_PARAMS_LEN + 1)
+        .position(|b| !(0x20..=0x3f).contains(&b))?;
+    if end > MAX_CSI_PARAMS_LEN || !(0x40..=0x7e).contains(&params.as_bytes()[end]) {
+        return None;
+    }
+
+    Some(end)
+}
+
+// Checks whether parameters of a CSI sequence are cut off before its final byte.
+fn is_truncated_csi(params: &str) -> bool {
+    params.len() <= MAX_CSI_PARAMS_LEN && params.bytes().all(|b| (0x20..=0x3f).contains(&b))
+}
+
+// Parses a SGR sequence at the beginning of a string,
+// returns its parameters and the rest of the string.
+fn parse_sgr(s: &str) -> Option<(&str, &str)> {
+    let s = s.strip_prefix("\u{1b}[")?;
+    let end = s.find(|c: char| !matches!(c, '0'..='9' | ';' | ':'))?;
+    if end > MAX_CSI_PARAMS_LEN {
+        return None;
+    }
+
+    let rest = s[end..].strip_prefix('m')?;
+
+    Some((&s[..end], rest))
+}
+
+// Parses a CSI sequence at the beginning of a string,
+// returns the whole sequence and the rest of the string.
+fn parse_csi(s: &str) -> Option<(&str, &str)> {
+    let params = s.strip_prefix("\u{1b}[")?;
+    let end = find_csi_final(params)?;
+
+    Some(s.split_at(s.len() - params.len() + end + 1))
+}
+
+// Checks whether a string is an escape sequence which is cut off,
+// a lone ESC or an unfinished CSI, OSC, DCS, SOS, PM or APC one.
+fn is_truncated_escape(s: &str) -> bool {
+    if let Some(params) = s.strip_prefix('\u{9b}') {
+        return is_truncated_csi(params);
+    }
+
+    if let Some(body) = s.strip_prefix('\u{9d}') {
+        let body = body.strip_suffix('\u{1b}').unwrap_or(body);
+        return !body.contains(['\u{7}', '\u{1b}', '\u{9c}']);
+    }
+
+    if let Some(body) = strip_string_sequence_introducer(s) {
+        let body = body.strip_suffix('\u{1b}').unwrap_or(body);
+        return !body.contains(['\u{7}', '\u{1b}', '\u{9c}']);
+    }
+
+    let s = match s.strip_prefix('\u{1b}') {
+        Some(s) => s,
+        None => return false,
+    };
+
+    if let Some(params) = s.strip_prefix('[') {
+        return is_truncated_csi(params);
+    }
+
+    if let Some(body) = s.strip_prefix(']') {
+        let body = body.strip_suffix('\u{1b}').unwrap_or(body);
+        return !body.contains(['\u{7}', '\u{1b}']);
+    }
+
+    s.is_empty()
+}
+
+// Parses an OSC sequence at the beginning of a string, which is terminated either by BEL or by ST,
+// returns the whole sequence and the rest of the string.
+fn parse_osc(s: &str) -> Option<(&str, &str)> {
+    let body = s.strip_prefix("\u{1b}]")?;
+    let end = body.find(['\u{7}', '\u{1b}'])?;
+    let terminator = match &body[end..] {
+        t if t.starts_with('\u{7}') => 1,
+        t if t.starts_with("\u{1b}\\") => 2,
+        _ => return None,
+    };
+
+    let len = s.len() - body.len() + end + terminator;
+    Some(s.split_at(len))
+}
+
+// Parses a DCS, SOS, PM or APC sequence at the beginning of a string,
+// returns the whole sequence and the rest of the string.
+//
+// It's terminated by ST or BEL,
+// while an ESC which doesn't start ST aborts it, so the ESC starts the rest.
+fn parse_string_sequence(s: &str) -> Option<(&str, &str)> {
+    let body = strip_string_sequence_introducer(s)?;
+    let end = body.find(['\u{7}', '\u{1b}', '\u{9c}'])?;
+    let terminator = match &body[end..] {
+        t if t.starts_with("\u{1b}\\") => 2,
+        t if t.starts_with('\u{1b}') => 0,
+        t => t.chars().next().map_or(0, char::len_utf8),
+    };
+
+    let len = s.len() - body.len() + end + terminator;
+    Some(s.split_at(len))
+}
+
+// Returns a body of a DCS, SOS, PM or APC sequence if a string starts with one.
+fn strip_string_sequence_introducer(s: &str) -> Option<&str> {
+    [
+        "\u{1b}P", "\u{1b}X", "\u{1b}^", "\u{1b}_", "\u{90}", "\u{98}", "\u{9e}", "\u{9f}",
+    ]
+    .iter()
+    .find_map(|introducer| s.strip_prefix(introducer))
+}
+
+// Parses a CSI or OSC sequence with a C1 introducer at the beginning of a string,
+// returns the whole sequence and the rest of the string.
+fn parse_c1(s: &str) -> Option<(&str, &str)> {
+    let len = if let Some(params) = s.strip_prefix('\u{9b}') {
+        let end = find_csi_final(params)?;
+        s.len() - params.len() + end + 1
+    } else {
+        let body = s.strip_prefix('\u{9d}')?;
+        let end = body.find(['\u{7}', '\u{1b}', '\u{9c}'])?;
+        let terminator = match &body[end..] {
+            t if t.starts_with('\u{7}') => 1,
+            t if t.starts_with('\u{9c}') || t.starts_with("\u{1b}\\") => 2,
+            _ => return None,
+        };
+
+        s.len() - body.len() + end + terminator
+    };
+
+    Some(s.split_at(len))
+}
+
+// Applies a policy to escape sequences of a string other than SGR and OSC ones.
+//
+// A cursor forward after the end of a cut is dropped as its spaces wouldn't be in the range.
+fn apply_control_policy(s: &str, policy: ControlPolicy, is_after_end: bool) -> String {
+    let mut buf = String::new();
+    for token in parse_ansi(s) {
+        match token {
+            Token::Escape(escape) => match control_replacement(&escape, policy, is_after_end) {
+                Some(replacement) => buf.push_str(&replacement),
+                None => buf.push_str(&escape.as_written()),
+            },
+            Token::Text(text) => buf.push_str(text),
+        }
+    }
+
+    buf
+}
+
+// Returns what a control sequence is replaced by or None if it's kept.
+fn control_replacement(
+    escape: &Escape<'_>,
+    policy: ControlPolicy,
+    is_after_end: bool,
+) -> Option<String> {
+    if escape.is_sgr() || is_osc(escape) || policy == ControlPolicy::Preserve {
+        return None;
+    }
+
+    if policy == ControlPolicy::Strip {
+        return Some(String::new());
+    }
+
+    match escape {
+        Escape::Sequence(AnsiSequence::CursorForward(_), _) if is_after_end => Some(String::new()),
+        Escape::Sequence(AnsiSequence::CursorForward(n), _) => {
+            Some(" ".repeat(std::cmp::min(*n as usize, MAX_CURSOR_FORWARD)))
+        }
+        Escape::Sequence(AnsiSequence::EraseLine, _) => Some(String::new()),
+        Escape::Csi(seq) if seq.ends_with('K') => Some(String::new()),
+        Escape::C1(seq) => match parse_ansi(&c1_to_7bit(seq)).next() {
+            Some(Token::Escape(escape)) => control_replacement(&escape, policy, is_after_end),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+fn is_osc(escape: &Escape<'_>) -> bool {
+    match escape {
+        Escape::Osc(_) => true,
+        Escape::C1(seq) => seq.starts_with('\u{9d}'),
+        _ => false,
+    }
+}
+
+// Converts a C1 sequence into its 7-bit form, e.g. `\u{9b}31m` into `\x1b[31m`.
+fn c1_to_7bit(seq: &str) -> String {
+    if let Some(params) = seq.strip_prefix('\u{9b}') {
+        return format!("\u{1b}[{}", params);
+    }
+
+    let body = seq.strip_prefix('\u{9d}').unwrap_or(seq);
+    match body.strip_suffix('\u{9c}') {
+        Some(body) => format!("\u{1b}]{}\u{1b}\\", body),
+        None => format!("\u{1b}]{}", body),
+    }
+}
+
+// Removes OSC sequences other than hyperlinks from a string of escape sequences.
+fn drop_osc_sequences(s: &str) -> String {
+    parse_ansi(s)
+        .filter(|token| match token {
+            Token::Escape(Escape::Osc(seq)) => seq.starts_with("\u{1b}]8;"),
+            Token::Escape(Escape::C1(seq)) => {
+                !seq.starts_with('\u{9d}') || seq.starts_with("\u{9d}8;")
+            }
+            _ => true,
+        })
+        .map(|token| match token {
+            Token::Escape(escape) => escape.as_written().into_owned(),
+            token => token.to_string(),
+        })
+        .collect()
+}
+
+// Drops escape sequences which make no difference to a text which follows them.
+//
+// These are all sequences before the last full reset, except hyperlinks which it doesn't close,
+// and SGR sequences each parameter of which is overridden by a later one.
+fn prune_escapes(s: &str) -> String {
+    let escapes = parse_ansi(s)
+        .filter_map(|token| match token {
+            Token::Escape(escape) => Some(escape),
+            Token::Text(_) => None,
+        })
+        .collect::<Vec<_>>();
+
+    let last_reset = escapes.iter().rposition(is_full_reset).unwrap_or(0);
+    let mut kept = escapes
+        .into_iter()
+        .enumerate()
+        .filter(|(i, escape)| *i >= last_reset || is_hyperlink(escape))
+        .map(|(_, escape)| escape)
+        .collect::<Vec<_>>();
+
+    let state = escapes_state(&kept);
+    let mut i = 0;
+    while i < kept.len() {
+        if kept[i].is_sgr() || is_hyperlink(&kept[i]) {
+            let escape = kept.remove(i);
+            if is_same_style(&escapes_state(&kept), &state) {
+                continue;
+            }
+
+            kept.insert(i, escape);
+        }
+
+        i += 1;
+    }
+
+    kept.iter().map(|escape| escape.as_written()).collect()
+}
+
+fn is_full_reset(escape: &Escape<'_>) -> bool {
+    match escape {
+        Escape::Sequence(AnsiSequence::SetGraphicsMode(mode), _) => {
+            mode.iter().all(|&code| code == 0)
+        }
+        Escape::Sgr(params) => params
+            .split(';')
+            .all(|param| param.bytes().all(|b| b == b'0')),
+        Escape::C1(seq) if escape.is_sgr() => {
+            parse_ansi(&c1_to_7bit(seq)).all(|token| match token {
+                Token::Escape(escape) => is_full_reset(&escape),
+                Token::Text(_) => false,
+            })
+        }
+        _ => false,
+    }
+}
+
+// Checks whether an escape sequence is a part of a style,
+// which is written by open_ansi_sequences.
+fn is_style_escape(escape: &Escape<'_>) -> bool {
+    match escape {
+        Escape::Csi(seq) => seq.ends_with('m'),
+        escape => escape.is_sgr() || is_hyperlink(escape),
+    }
+}
+
+fn is_hyperlink(escape: &Escape<'_>) -> bool {
+    match escape {
+        Escape::Osc(seq) => seq.starts_with("\u{1b}]8;"),
+        Escape::C1(seq) => seq.starts_with("\u{9d}8;"),
+        _ => false,
+    }
+}
+
+fn escapes_state(escapes: &[Escape<'_>]) -> AnsiState {
+    let mut state = AnsiState::default();
+    for escape in escapes {
+        update_ansi_state_by_escape(&mut state, escape);
+    }
+
+    state
+}
+
+// Checks whether 2 states render a text the same way,
+// regardless of the order the styles were opened in.
+fn is_same_style(lhs: &AnsiState, rhs: &AnsiState) -> bool {
+    let mut lhs_buf = String::new();
+    let mut rhs_buf = String::new();
+    open_ansi_sequences(lhs, &mut lhs_buf);
+    open_ansi_sequences(rhs, &mut rhs_buf);
+
+    lhs_buf == rhs_buf && lhs.unknown == rhs.unknown
+}
+
+// Updates a state by all escape sequences of a string.
+fn update_ansi_state_by_str(state: &mut AnsiState, string: &str) {
+    for token in parse_ansi(string) {
+        if let Token::Escape(seq) = token {
+            update_ansi_state_by_escape(state, &seq);
+        }
+    }
+}
+
+// Updates a state by an escape sequence if it's a SGR one.
+fn update_ansi_state_by_escape(state: &mut AnsiState, escape: &Escape<'_>) {
+    match escape {
+        // `\x1b[m` is the same as `\x1b[0m`
+        Escape::Sequence(AnsiSequence::SetGraphicsMode(mode), _) if mode.is_empty() => {
+            update_ansi_state(state, &[0])
+        }
+        Escape::Sequence(AnsiSequence::SetGraphicsMode(mode), _) => {
+            update_ansi_state(state, mode.as_ref())
+        }
+        Escape::Csi(seq) if seq.ends_with('m') => add_raw_sequence(state, seq),
+        Escape::Sequence(..) | Escape::Csi(_) | Escape::Str(_) => {}
+        Escape::Sgr(params) => update_ansi_state_by_params(state, params),
+        Escape::Osc(seq) => update_ansi_state_by_osc(state, seq),
+        Escape::C1(seq) => update_ansi_state_by_str(state, &c1_to_7bit(seq)),
+    }
+}
+
+// Updates a state by an OSC sequence, only hyperlinks (OSC 8) are tracked.
+//
+// A link is opened by `ESC ] 8 ; params ; uri ST` and closed by the same sequence with an empty uri.
+fn update_ansi_state_by_osc(state: &mut AnsiState, seq: &str) {
+    let payload = seq.strip_prefix("\u{1b}]").unwrap_or(seq);
+    let payload = payload
+        .strip_suffix('\u{7}')
+        .or_else(|| payload.strip_suffix("\u{1b}\\"))
+        .unwrap_or(payload);
+
+    let link = match payload
+        .strip_prefix("8;")
+        .and_then(|link| link.split_once(';'))
+    {
+        Some(link) => link,
+        None => return,
+    };
+
+    state.link = match link {
+        (_, "") => None,
+        (params, uri) => Some(Hyperlink {
+            params: params.to_owned(),
+            uri: uri.to_owned(),
+        }),
+    };
+}
+
+// Updates a state by raw parameters of a SGR sequence,
+// a parameter may have sub-parameters separated by colons (`38:2::r:g:b`, `4:3`).
+//
+// A parameter which is out of range (above 255), together with a color it's part of,
+// is kept as an unknown code, e.g. `38;5;300`.
+fn update_ansi_state_by_params(state: &mut AnsiState, params: &str) {
+    let mut mode = Vec::new();
+    let mut params = params.split(';').peekable();
+    while let Some(param) = params.next() {
+        if param.contains(':') {
+            update_ansi_state(state, &mode);
+            mode.clear();
+            update_ansi_state_by_subparams(state, param);
+            continue;
+        }
+
+        // an extended color takes the parameters which follow it
+        let group_len = match parse_sgr_param(param) {
+            Some(38 | 48 | 58) => match params.peek().and_then(|p| parse_sgr_param(p)) {
+                Some(5) => 3,
+                Some(2) => 5,
+                _ => 1,
+            },
+            _ => 1,
+        };
+
+        let mut group = vec![param];
+        group.extend(params.by_ref().take(group_len - 1));
+
+        let values = group
+            .iter()
+            .map(|p| parse_sgr_param(p).and_then(|n| u8::try_from(n).ok()))
+            .collect::<Option<Vec<_>>>();
+        match values {
+            Some(values) => mode.extend(values),
+            None => {
+                update_ansi_state(state, &mode);
+                mode.clear();
+                add_unknown_code(state, &group.join(";"));
+            }
+        }
+    }
+
+    update_ansi_state(state, &mode);
+}
+
+// Parses a SGR parameter, an empty one is the same as 0.
+fn parse_sgr_param(param: &str) -> Option<u16> {
+    match param {
+        "" => Some(0),
+        param => param.parse().ok(),
+    }
+}
+
+// Updates a state by a parameter with colon separated sub-parameters.
+//
+// Extended colors may go with a color space id (`38:2:id:r:g:b`)
+// which is usually empty, or without it (`38:2:r:g:b`).
+fn update_ansi_state_by_subparams(state: &mut AnsiState, param: &str) {
+    let values = param
+        .split(':')
+        .map(|value| match value {
+            "" => Ok(None),
+            value => value.parse::<u8>().map(Some),
+        })
+        .collect::<Result<Vec<_>, _>>();
+    let values = match values {
+        Ok(values) => values,
+        Err(_) => {
+            add_unknown_code(state, param);
+            return;
+        }
+    };
+
+    let color = match values.as_slice() {
+        [Some(_), Some(5), Some(index)] => Some(AnsiColor::Bit8 { index: *index }),
+        [Some(_), Some(2), Some(r), Some(g), Some(b)]
+        | [Some(_), Some(2), _, Some(r), Some(g), Some(b), ..] => Some(AnsiColor::Bit24 {
+            r: *r,
+            g: *g,
+            b: *b,
+        }),
+        _ => None,
+    };
+
+    match (values.as_slice(), color) {
+        ([Some(38), ..], Some(color)) => {
+            state.fg_color = Some(color);
+            mark_opened(state, 39);
+        }
+        ([Some(48), ..], Some(color)) => {
+            state.bg_color = Some(color);
+            mark_opened(state, 49);
+        }
+        ([Some(58), ..], Some(color)) => {
+            state.undr_color = Some(color);
+            mark_opened(state, 59);
+        }
+        ([Some(4), Some(0)], _) => update_ansi_state(state, &[24]),
+        ([Some(4), Some(1)], _) => update_ansi_state(state, &[4]),
+        ([Some(4), Some(2)], _) => update_ansi_state(state, &[21]),
+        ([Some(4), Some(n @ 3..=5)], _) => {
+            state.underline_style = Some(*n);
+            mark_opened(state, 24);
+        }
+        _ => add_unknown_code(state, param),
+    }
+}
+
+// Returns a code which closes a style set by a given code.
+fn closing_code(code: u8) -> Option<u8> {
+    match code {
+        1 | 2 => Some(22),
+        3 | 20 => Some(23),
+        4 | 21 => Some(24),
+        5 | 6 => Some(25),
+        7 => Some(27),
+        8 => Some(28),
+        9 => Some(29),
+        11..=19 => Some(10),
+        26 => Some(50),
+        30..=38 | 90..=97 => Some(39),
+        40..=48 | 100..=107 => Some(49),
+        51 | 52 => Some(54),
+        53 => Some(55),
+        58 => Some(59),
+        60..=64 => Some(65),
+        73 | 74 => Some(75),
+        _ => None,
+    }
+}
+
+// Moves a closing code to the end of the opened ones.
+fn mark_opened(state: &mut AnsiState, code: u8) {
+    state.opened.retain(|&c| c != code);
+    state.opened.push(code);
+}
+
+fn add_raw_sequence(state: &mut AnsiState, seq: &str) {
+    if !state.raw.iter().any(|s| s == seq) {
+        state.raw.push(seq.to_owned());
+    }
+}
+
+fn add_unknown_code(state: &mut AnsiState, code: &str) {
+    if !state.unknown.iter().any(|c| c == code) {
+        state.unknown.push(code.to_owned());
+    }
+}
+
+// Parses parameters of an extended color which follow 38, 48 or 58,
+// returns the color and a number of parameters it takes.
+fn parse_ansi_color(buf: &[u8]) -> Option<(AnsiColor, usize)> {
+    match buf {
+        [5, index, ..] => Some((AnsiColor::Bit8 { index: *index }, 2)),
+        [2, r, g, b, ..] => Some((
+            AnsiColor::Bit24 {
+                r: *r,
+                g: *g,
+                b: *b,
+            },
+            4,
+        )),
+        _ => None,
+    }
+}
+
+fn open_ansi_sequences(state: &AnsiState, buf: &mut String) {
+    // writing into a string never fails
+    let _ = write_open_ansi_sequences(state, buf);
+}
+
+fn write_open_ansi_sequences<W>(state: &AnsiState, w: &mut W) -> fmt::Result
+where
+    W: fmt::Write + ?Sized,
+{
+    let mut sgr = SgrWriter::new(w);
+
+    macro_rules! emit_color {
+        ($color:expr, $base:expr) => {
+            match $color {
+                AnsiColor::Bit4 { index } => sgr.push(&[*index])?,
+                AnsiColor::Bit8 { index } => sgr.push(&[$base, 5, *index])?,
+                AnsiColor::Bit24 { r, g, b } => sgr.push(&[$base, 2, *r, *g, *b])?,
+            }
+        };
+    }
+
+    let flags = [
+        (state.bold, 1),
+        (state.faint, 2),
+        (state.italic, 3),
+        (state.underline, 4),
+        (state.slow_blink, 5),
+        (state.rapid_blink, 6),
+        (state.inverse, 7),
+        (state.hide, 8),
+        (state.crossedout, 9),
+        (state.fraktur, 20),
+        (state.double_underline, 21),
+        (state.proportional_spacing, 26),
+        (state.framed, 51),
+        (state.encircled, 52),
+        (state.overlined, 53),
+        (state.igrm_underline, 60),
+        (state.igrm_double_underline, 61),
+        (state.igrm_overline, 62),
+        (state.igrm_double_overline, 63),
+        (state.igrm_stress_marking, 64),
+        (state.superscript, 73),
+        (state.subscript, 74),
+    ];
+
+    for (is_set, code) in flags.iter() {
+        if *is_set {
+            sgr.push(&[*code])?;
+        }
+    }
+
+    if let Some(style) = state.underline_style {
+        sgr.push_subparams(&[4, style])?;
+    }
+
+    if let Some(font) = state.font {
+        sgr.push(&[font])?;
+    }
+
+    if let Some(color) = &state.fg_color {
+        emit_color!(color, 38);
+    }
+
+    if let Some(color) = &state.bg_color {
+        emit_color!(color, 48);
+    }
+
+    if let Some(color) = &state.undr_color {
+        emit_color!(color, 58);
+    }
+
+    sgr.flush()?;
+
+    if let Some(link) = &state.link {
+        write!(sgr.w, "\u{1b}]8;{};{}\u{1b}\\", link.params, link.uri)?;
+    }
+
+    for seq in &state.raw {
+        sgr.w.write_str(seq)?;
+    }
+
+    Ok(())
+}
+
+// SgrWriter packs SGR parameters into as few sequences as possible.
+//
+// A sequence is kept to 1, 2, 3 or 5 parameters, which is what ansi-parser parses,
+// so the output can be read by tools built on it, the crate itself reads any number of them.
+// A group of parameters (like a color) is never split between sequences.
+struct SgrWriter<'a, W: ?Sized> {
+    w: &'a mut W,
+    params: [u8; 5],
+    len: usize,
+}
+
+impl<'a, W> SgrWriter<'a, W>
+where
+    W: fmt::Write + ?Sized,
+{
+    fn new(w: &'a mut W) -> Self {
+        Self {
+            w,
+            params: [0; 5],
+            len: 0,
+        }
+    }
+
+    fn push(&mut self, group: &[u8]) -> fmt::Result {
+        let len = self.len + group.len();
+        if len > self.params.len() || len == 4 {
+            self.flush()?;
+        }
+
+        self.params[self.len..self.len + group.len()].copy_from_slice(group);
+        self.len += group.len();
+
+        Ok(())
+    }
+
+    // Writes a parameter with colon separated sub-parameters (like `4:3`) as its own sequence.
+    fn push_subparams(&mut self, params: &[u8]) -> fmt::Result {
+        self.flush()?;
+
+        self.w.write_str("\u{1b}[")?;
+        for (i, param) in params.iter().enumerate() {
+            if i > 0 {
+                self.w.write_char(':')?;
+            }
+
+            write!(self.w, "{}", param)?;
+        }
+        self.w.write_char('m')
+    }
+
+    fn flush(&mut self) -> fmt::Result {
+        if self.len == 0 {
+            return Ok(());
+        }
+
+        self.w.write_str("\u{1b}[")?;
+        for (i, param) in self.params[..self.len].iter().enumerate() {
+            if i > 0 {
+                self.w.write_char(';')?;
+            }
+
+            write!(self.w, "{}", param)?;
+        }
+        self.w.write_char('m')?;
+
+        self.len = 0;
+
+        Ok(())
+    }
+}
+
+// Returns styles which need to be closed and opened to switch from one state to another.
+fn diff_ansi_states(from: &AnsiState, to: &AnsiState) -> (AnsiState, AnsiState) {
+    let mut closing = AnsiState::default();
+    let mut opening = AnsiState::default();
+
+    // attributes which are closed together
+    macro_rules! group {
+        ($($field:ident),+) => {
+            if $(from.$field != to.$field)||+ {
+                $(
+                    closing.$field = from.$field;
+                    opening.$field = to.$field;
+                )+
+            }
+        };
+    }
+
+    // attributes which can be overridden without being closed
+    macro_rules! value {
+        ($field:ident) => {
+            if from.$field != to.$field {
+                if to.$field.is_some() {
+                    opening.$field = to.$field.clone();
+                } else {
+                    closing.$field = from.$field.clone();
+                }
+            }
+        };
+    }
+
+    group!(bold, faint);
+    group!(italic);
+    group!(underline, double_underline, underline_style);
+    group!(slow_blink, rapid_blink);
+    group!(inverse);
+    group!(hide);
+    group!(crossedout);
+    group!(fraktur);
+    group!(proportional_spacing);
+    group!(framed, encircled);
+    group!(overlined);
+    group!(
+        igrm_underline,
+        igrm_double_underline,
+        igrm_overline,
+        igrm_double_overline,
+        igrm_stress_marking
+    );
+    group!(superscript, subscript);
+    value!(font);
+    value!(fg_color);
+    value!(bg_color);
+    value!(undr_color);
+    value!(link);
+
+    closing.opened = from.opened.clone();
+
+    (closing, opening)
+}
+
+fn complete_ansi_sequences(state: &AnsiState, buf: &mut String) {
+    // writing into a string never fails
+    let _ = write_complete_ansi_sequences(state, buf, false);
+}
+
+// Closes styles of a state by a single SGR sequence,
+// or by a few ones which ansi-parser can parse if it's packed.
+fn write_complete_ansi_sequences<W>(state: &AnsiState, w: &mut W, is_packed: bool) -> fmt::Result
+where
+    W: fmt::Write + ?Sized,
+{
+    if !state.unknown.is_empty() && state.reset {
+        w.write_str("\u{1b}[0m")?;
+    }
+
+    let closers = [
+        (state.font.is_some(), 10),
+        (state.bold || state.faint, 22),
+        (state.italic || state.fraktur, 23),
+        (
+            state.underline || state.double_underline || state.underline_style.is_some(),
+            24,
+        ),
+        (state.slow_blink || state.rapid_blink, 25),
+        (state.inverse, 27),
+        (state.hide, 28),
+        (state.crossedout, 29),
+        (state.fg_color.is_some(), 39),
+        (state.bg_color.is_some(), 49),
+        (state.proportional_spacing, 50),
+        (state.encircled || state.framed, 54),
+        (state.overlined, 55),
+        (
+            state.igrm_underline
+                || state.igrm_double_underline
+                || state.igrm_overline
+                || state.igrm_double_overline
+                || state.igrm_stress_marking,
+            65,
+        ),
+        (state.undr_color.is_some(), 59),
+        (state.subscript || state.superscript, 75),
+    ];
+
+    let is_open = |code: u8| closers.iter().any(|&(is_set, c)| is_set && c == code);
+
+    // styles are closed in reverse order they were opened
+    let codes = state
+        .opened
+        .iter()
+        .rev()
+        .copied()
+        .filter(|&code| is_open(code))
+        .chain(
+            closers
+                .iter()
+                .filter(|&&(is_set, code)| is_set && !state.opened.contains(&code))
+                .map(|&(_, code)| code),
+        );
+
+    if is_packed {
+        let mut sgr = SgrWriter::new(&mut *w);
+        for code in codes {
+            sgr.push(&[code])?;
+        }
+
+        sgr.flush()?;
+    } else {
+        let mut codes = codes.peekable();
+        if codes.peek().is_some() {
+            w.write_str("\u{1b}[")?;
+            for (i, code) in codes.enumerate() {
+                if i > 0 {
+                    w.write_char(';')?;
+                }
+
+                write!(w, "{}", code)?;
+            }
+
+            w.write_char('m')?;
+        }
+    }
+
+    if state.link.is_some() {
+        w.write_str("\u{1b}]8;;\u{1b}\\")?;
+    }
+
+    if !state.unknown.is_empty() {
+        w.write_str("\u{1b}[0m")?;
+    }
+
+    Ok(())
+}
+
+fn bounds_to_usize(left: Bound<&usize>, right: Bound<&usize>) -> (usize, Option<usize>) {
+    let start = match left {
+        Bound::Included(x) => *x,
+        Bound::Excluded(x) => x.saturating_add(1),
+        Bound::Unbounded => 0,
+    };
+
+    let end = match right {
+        Bound::Included(y) => Some(y.saturating_add(1)),
+        Bound::Excluded(y) => Some(*y),
+        Bound::Unbounded => None,
+    };
+
+    (start, end)
+}
+
+fn srip_ansi_sequences(string: &str) -> String {
+    strip(string).into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_ansi_color_test() {
+        let tests: Vec<(&[u8], _)> = vec![
+            (&[5, 200], Some((AnsiColor::Bit8 { index: 200 }, 2))),
+            (
+                &[5, 100, 123, 39],
+                Some((AnsiColor::Bit8 { index: 100 }, 2)),
+            ),
+            (&[5], None),
+            (
+                &[2, 100, 123, 39],
+                Some((
+                    AnsiColor::Bit24 {
+                        r: 100,
+                        g: 123,
+                        b: 39,
+                    },
+                    4,
+                )),
+            ),
+            (
+                &[2, 100, 123, 39, 1, 2, 3],
+                Some((
+                    AnsiColor::Bit24 {
+                        r: 100,
+                        g: 123,
+                        b: 39,
+                    },
+                    4,
+                )),
+            ),
+            (&[2, 100, 123], None),
+            (&[2, 100], None),
+            (&[2], None),
+            (b"2;", None),
+            (b"5;", None),
+            (&[1, 2, 3], None),
+            (&[], None),
+        ];
+
+        for (i, (bytes, expected)) in tests.into_iter().enumerate() {
+            assert_eq!(parse_ansi_color(bytes), expected, "test={}", i);
+        }
+    }
+
+    #[test]
+    fn update_ansi_state_codes_test() {
+        let is_unknown = |code: u8| matches!(code, 56 | 57 | 66..=72 | 76..=89 | 98 | 99);
+
+        for code in 0..=107u8 {
+            let mode: &[u8] = match code {
+                38 | 48 | 58 => &[code, 5, 1],
+                _ => &[code],
+            };
+
+            let mut state = AnsiState::default();
+            update_ansi_state(&mut state, mode);
+            assert_eq!(!state.unknown.is_empty(), is_unknown(code), "code={}", code);
+
+            // closing a state must leave nothing to be closed
+            let mut closers = String::new();
+            complete_ansi_sequences(&state, &mut closers);
+            update_ansi_state_by_str(&mut state, &closers);
+            assert!(is_closed(&state), "code={} closers={:?}", code, closers);
+        }
+    }
+
+    #[test]
+    fn update_ansi_state_extended_color_followed_by_params_test() {
+        let mut state = AnsiState::default();
+        update_ansi_state(&mut state, &[38, 5, 208, 1]);
+        assert_eq!(state.fg_color, Some(AnsiColor::Bit8 { index: 208 }));
+        assert!(state.bold);
+        assert!(!state.faint);
+
+        let mut state = AnsiState::default();
+        update_ansi_state(&mut state, &[48, 2, 1, 2, 3, 4, 31]);
+        assert_eq!(state.bg_color, Some(AnsiColor::Bit24 { r: 1, g: 2, b: 3 }));
+        assert_eq!(state.fg_color, Some(AnsiColor::Bit4 { index: 31 }));
+        assert!(state.underline);
+        assert!(!state.bold && !state.faint && !state.italic);
+
+        let mut state = AnsiState::default();
+        update_ansi_state(&mut state, &[58, 5, 3, 38, 2, 9, 8, 7, 3]);
+        assert_eq!(state.undr_color, Some(AnsiColor::Bit8 { index: 3 }));
+        assert_eq!(state.fg_color, Some(AnsiColor::Bit24 { r: 9, g: 8, b: 7 }));
+        assert!(state.italic);
+        assert!(!state.faint && !state.bold);
+
+        let s = "\u{1b}[38;5;208;1mABC\u{1b}[0m";
+        assert_eq!("\u{1b}[38;5;208;1mA\u{1b}[22;39m", s.cut(..1));
+        assert_eq!(
+            "\u{1b}[1m\u{1b}[38;5;208mB\u{1b}[22;39m",
+            s.cut_minimal(1..2)
+        );
+    }
+
+    #[test]
+    fn cut_colored_fg_test() {
+        let colored_s = "\u{1b}[30mTEXT\u{1b}[39m";
+        assert_eq!(colored_s, colored_s.cut(..));
+        assert_eq!(colored_s, colored_s.cut(0..4));
+        assert_eq!("\u{1b}[30mEXT\u{1b}[39m", colored_s.cut(1..));
+        assert_eq!("\u{1b}[30mTEX\u{1b}[39m", colored_s.cut(..3));
+        assert_eq!("\u{1b}[30mEX\u{1b}[39m", colored_s.cut(1..3));
+
+        assert_eq!("TEXT", srip_ansi_sequences(&colored_s.cut(..)));
+        assert_eq!("TEX", srip_ansi_sequences(&colored_s.cut(..3)));
+        assert_eq!("EX", srip_ansi_sequences(&colored_s.cut(1..3)));
+
+        let colored_s = "\u{1b}[30mTEXT\u{1b}[39m \u{1b}[31mTEXT\u{1b}[39m";
+        assert_eq!(colored_s, colored_s.cut(..));
+        assert_eq!(colored_s, colored_s.cut(0..9));
+        assert_eq!(
+            "\u{1b}[30mXT\u{1b}[39m \u{1b}[31mTEXT\u{1b}[39m",
+            colored_s.cut(2..)
+        );
+        assert_eq!(
+            "\u{1b}[30mTEXT\u{1b}[39m \u{1b}[31mT\u{1b}[39m",
+            colored_s.cut(..6)
+        );
+        assert_eq!(
+            "\u{1b}[30mXT\u{1b}[39m \u{1b}[31mT\u{1b}[39m",
+            colored_s.cut(2..6)
+        );
+
+        assert_eq!("TEXT TEXT", srip_ansi_sequences(&colored_s.cut(..)));
+        assert_eq!("TEXT T", srip_ansi_sequences(&colored_s.cut(..6)));
+        assert_eq!("XT T", srip_ansi_sequences(&colored_s.cut(2..6)));
+
+        assert_eq!("\u{1b}[30m\u{1b}[39m", cut("\u{1b}[30m\u{1b}[39m", ..));
+    }
+
+    #[test]
+    fn cut_colored_bg_test() {
+        let colored_s = "\u{1b}[40mTEXT\u{1b}[49m";
+        assert_eq!(colored_s, colored_s.cut(..));
+        assert_eq!(colored_s, colored_s.cut(0..4));
+        assert_eq!("\u{1b}[40mEXT\u{1b}[49m", colored_s.cut(1..));
+        assert_eq!("\u{1b}[40mTEX\u{1b}[49m", colored_s.cut(..3));
+        assert_eq!("\u{1b}[40mEX\u{1b}[49m", colored_s.cut(1..3));
+
+        // it's kept for compatibility, cut_strict returns an empty string
+        assert_eq!("\u{1b}[40m\u{1b}[49m", colored_s.cut(3..3));
+
+        assert_eq!("TEXT", srip_ansi_sequences(&colored_s.cut(..)));
+        assert_eq!("TEX", srip_ansi_sequences(&colored_s.cut(..3)));
+        assert_eq!("EX", srip_ansi_sequences(&colored_s.cut(1..3)));
+
+        let colored_s = "\u{1b}[40mTEXT\u{1b}[49m \u{1b}[41mTEXT\u{1b}[49m";
+        assert_eq!(colored_s, colored_s.cut(..));
+        assert_eq!(colored_s, colored_s.cut(0..9));
+        assert_eq!(
+            "\u{1b}[40mXT\u{1b}[49m \u{1b}[41mTEXT\u{1b}[49m",
+            colored_s.cut(2..)
+        );
+        assert_eq!(
+            "\u{1b}[40mTEXT\u{1b}[49m \u{1b}[41mT\u{1b}[49m",
+            colored_s.cut(..6)
+        );
+        assert_eq!(
+            "\u{1b}[40mXT\u{1b}[49m \u{1b}[41mT\u{1b}[49m",
+            colored_s.cut(2..6)
+        );
+
+        assert_eq!("TEXT TEXT", srip_ansi_sequences(&colored_s.cut(..)));
+        assert_eq!("TEXT T", srip_ansi_sequences(&colored_s.cut(..6)));
+        assert_eq!("XT T", srip_ansi_sequences(&colored_s.cut(2..6)));
+
+        assert_eq!("\u{1b}[40m\u{1b}[49m", cut("\u{1b}[40m\u{1b}[49m", ..));
+    }
+
+    #[test]
+    fn cut_inverse_and_hidden_test() {
+        let s = "\u{1b}[7mAB\u{1b}[27m";
+        assert_eq!("\u{1b}[7mA\u{1b}[27m", s.cut(..1));
+
+        let s = "\u{1b}[7mSEL\u{1b}[27mrest";
+        assert_eq!("\u{1b}[7mEL\u{1b}[27mres", s.cut(1..6));
+        assert_eq!(
+            "\u{1b}[31m\u{1b}[7mEL\u{1b}[27mres\u{1b}[39m",
+            format!("\u{1b}[31m{}", s).cut(1..6)
+        );
+
+        let s = "\u{1b}[8msecret\u{1b}[28m";
+        assert_eq!("\u{1b}[8msec\u{1b}[28m", s.cut(..3));
+        assert_eq!("\u{1b}[8mret\u{1b}[28m", s.cut(3..));
+
+        let s = "\u{1b}[7;8mAB\u{1b}[28mCD";
+        assert_eq!("\u{1b}[7;8mA\u{1b}[28;27m", s.cut(..1));
+    }
+
+    #[test]
+    fn cut_colon_subparams_test() {
+        let s = "\u{1b}[38:2::255:128:0mABC\u{1b}[39m";
+        assert_eq!("\u{1b}[38:2::255:128:0mA\u{1b}[39m", s.cut(..1));
+        assert_eq!("\u{1b}[38:2::255:128:0mBC\u{1b}[39m", s.cut(1..));
+        assert_eq!("\u{1b}[38;2;255;128;0mBC\u{1b}[39m", s.cut_minimal(1..));
+
+        let s = "\u{1b}[48:2:1:2:3mABC\u{1b}[49m";
+        assert_eq!("\u{1b}[48:2:1:2:3mB\u{1b}[49m", s.cut(1..2));
+
+        let s = "\u{1b}[58:5:208;4:3mABC\u{1b}[4:0;59m";
+        assert_eq!("\u{1b}[58:5:208;4:3mA\u{1b}[24;59m", s.cut(..1));
+        assert_eq!("\u{1b}[58:5:208;4:3mBC\u{1b}[4:0;59m", s.cut(1..));
+        assert_eq!(
+            "\u{1b}[4:3m\u{1b}[58;5;208mBC\u{1b}[4:0;59m",
+            s.cut_minimal(1..)
+        );
+
+        // mixed separators
+        let s = "\u{1b}[1;38:5:208;48;2;1;2;3;4mABC\u{1b}[0m";
+        assert_eq!(
+            "\u{1b}[1;4;38;5;208m\u{1b}[48;2;1;2;3mB\u{1b}[24;49;39;22m",
+            s.cut_minimal(1..2)
+        );
+
+        let s = "\u{1b}[4:2mAB\u{1b}[24mC";
+        assert_eq!("\u{1b}[21mB\u{1b}[24mC", s.cut_minimal(1..));
+    }
+
+    #[test]
+    fn cut_control_policy_test() {
+        let s = "ab\u{1b}[2Ccd\u{1b}[2K\u{1b}[?25lef";
+        let options = |policy| CutOptions::default().control(policy);
+        assert_eq!("abcdef", srip_ansi_sequences(s));
+
+        assert_eq!("ab\u{1b}[2Ccd\u{1b}[2K\u{1b}[?25l", s.cut(..4));
+        assert_eq!(
+            s.cut(..4),
+            s.cut_with(..4, options(ControlPolicy::Preserve))
+        );
+        assert_eq!("abcd", s.cut_with(..4, options(ControlPolicy::Strip)));
+        assert_eq!(
+            "ab  cd\u{1b}[?25l",
+            s.cut_with(..4, options(ControlPolicy::Normalize))
+        );
+
+        // the spaces would be after the end of a cut
+        assert_eq!("ab", s.cut_with(..2, options(ControlPolicy::Normalize)));
+        // and before the start
+        assert_eq!("\u{1b}[2Cd\u{1b}[2K\u{1b}[?25lef", s.cut(3..));
+        assert_eq!(
+            "d\u{1b}[?25lef",
+            s.cut_with(3.., options(ControlPolicy::Normalize))
+        );
+
+        for policy in [ControlPolicy::Strip, ControlPolicy::Normalize] {
+            let cut = s.cut_with(1..5, options(policy));
+            assert_eq!("bcde", srip_ansi_sequences(&cut).replace(' ', ""));
+        }
+
+        let s = "\u{1b}[31mab\u{9b}2Ccd\u{9b}K\u{1b}[39m";
+        assert_eq!(
+            "\u{1b}[31mab  cd\u{1b}[39m",
+            s.cut_with(.., options(ControlPolicy::Normalize))
+        );
+        assert_eq!(
+            "\u{1b}[31mabcd\u{1b}[39m",
+            s.cut_with(.., options(ControlPolicy::Strip))
+        );
+
+        // a huge cursor forward is limited
+        let cut = "a\u{1b}[100000000Cb".cut_with(.., options(ControlPolicy::Normalize));
+        assert_eq!(format!("a{}b", " ".repeat(1024)), cut);
+
+        let s = "ab\u{1b}[2Ccd\u{1b}[2K\u{1b}[?25lef";
+        let chunks = |policy| {
+            chunks::Chunks::new(s)
+                .size(3)
+                .control(policy)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            chunks(ControlPolicy::Preserve),
+            ["ab\u{1b}[2Cc", "d\u{1b}[2K\u{1b}[?25lef"]
+        );
+        assert_eq!(chunks(ControlPolicy::Strip), ["abc", "def"]);
+        assert_eq!(
+            chunks(ControlPolicy::Normalize),
+            ["ab  c", "d\u{1b}[?25lef"]
+        );
+    }
+
+    #[test]
+    fn cut_c1_test() {
+        let s = "\u{9b}31mAB\u{1b}[1mCD\u{9b}0m\u{9d}8;;http://a.b\u{9c}EF\u{1b}]8;;\u{1b}\\";
+        assert_eq!("ABCDEF", srip_ansi_sequences(s));
+        assert_eq!("\u{1b}[31mB\u{1b}[1mC\u{1b}[22;39m", s.cut(1..3));
+        assert_eq!("\u{1b}[1;31mC\u{1b}[22;39m", s.cut_minimal(2..3));
+        assert_eq!(
+            "\u{1b}]8;;http://a.b\u{1b}\\E\u{1b}]8;;\u{1b}\\",
+            s.cut(4..5)
+        );
+        assert_eq!(
+            "\u{1b}[31mAB\u{1b}[1mCD\u{1b}[0m\u{1b}]8;;http://a.b\u{1b}\\EF\u{1b}]8;;\u{1b}\\",
+            s.cut(..)
+        );
+        assert_eq!(s.cut(1..5), AnsiIndexedStr::new(s).cut(1..5));
+        assert_eq!(
+            chunks(s, 4),
+            [
+                "\u{1b}[31mAB\u{1b}[1mCD\u{1b}[22;39m",
+                "\u{1b}]8;;http://a.b\u{1b}\\EF\u{1b}]8;;\u{1b}\\"
+            ]
+        );
+
+        let options = CutOptions::default().c1(C1Policy::Keep);
+        assert_eq!(
+            "\u{9b}31mB\u{1b}[1mC\u{1b}[22;39m",
+            s.cut_with(1..3, options)
+        );
+        assert_eq!(
+            "\u{9d}8;;http://a.b\u{9c}E\u{1b}]8;;\u{1b}\\",
+            s.cut_with(4..5, options)
+        );
+        // a style which is reopened is written in 7-bit form
+        assert_eq!(
+            "\u{1b}[1;31mC\u{1b}[22;39m",
+            s.cut_with(2..3, options.minimal(true))
+        );
+
+        let s = "A\u{9d}0;title\u{7}B";
+        assert_eq!("A\u{1b}]0;title\u{7}B", s.cut(..));
+        assert_eq!(s, s.cut_with(.., options));
+        assert_eq!("AB", s.cut_with(.., options.drop_osc(true)));
+
+        // an introducer which doesn't start a known sequence is dropped
+        assert_eq!("\u{1}AB", "\u{9b}\u{1}AB".cut(..3));
+        assert_eq!("\u{9c}A", "\u{9c}AB".cut(..3));
+    }
+
+    #[test]
+    fn cut_truncated_escape_test() {
+        let sequences = [
+            "\u{1b}[1;31m",
+            "\u{1b}[38;5;208m",
+            "\u{1b}[38:2::1:2:3m",
+            "\u{1b}[2J",
+            "\u{1b}]0;title\u{7}",
+            "\u{1b}]8;;http://a.b\u{1b}\\",
+            "\u{9b}1;31m",
+            "\u{9d}8;;http://a.b\u{9c}",
+        ];
+
+        for seq in sequences {
+            for (end, _) in seq.char_indices() {
+                let prefix = &seq[..end];
+
+                let s = format!("text {}", prefix);
+                assert_eq!("text ", s.cut(..), "prefix={:?}", prefix);
+                assert_eq!("text ", s.cut(..5), "prefix={:?}", prefix);
+                assert_eq!("ext ", s.cut_minimal(1..), "prefix={:?}", prefix);
+                assert_eq!(
+                    "text ",
+                    AnsiIndexedStr::new(&s).cut(..),
+                    "prefix={:?}",
+                    prefix
+                );
+                assert_eq!(chunks(&s, 2), ["te", "xt", " "], "prefix={:?}", prefix);
+                assert_eq!("text ", srip_ansi_sequences(&s), "prefix={:?}", prefix);
+
+                let s = format!("\u{1b}[31mtext{}", prefix);
+                assert_eq!("\u{1b}[31mext\u{1b}[39m", s.cut(1..), "prefix={:?}", prefix);
+            }
+        }
+
+        // a lone ESC which is not at the end is dropped, while the rest is kept as a text
+        assert_eq!("abc", "a\u{1b}bc".cut(..3));
+        assert_eq!("a[3\u{1}bc", "a\u{1b}[3\u{1}bc".cut(..6));
+        assert_eq!("a[3\u{1}b", "a\u{1b}[3\u{1}bc".cut(..5));
+    }
+
+    #[test]
+    fn cut_malformed_escapes_test() {
+        let invalid_utf8 = String::from_utf8_lossy(b"\x1b[3\xff1m\xfe\x1b[31mab\xc3\x1b[0m");
+        let cases = [
+            "\u{1b}\u{1b}\u{1b}[31ma\u{1b}\u{1b}",
+            "\u{1b}[31m\u{1b}xy\u{1b}[39m",
+            "a\u{1b}\u{9b}31mb\u{9b}39m",
+            "😀2\u{1b}\u{9b}:K",
+            "\u{9b}\u{9b}\u{9d}\u{9d}a\u{9c}b",
+            "a\u{1b}[;;;;;;;;;;;;;;;;;;;;;;mb",
+            "a\u{1b}[38;5mb\u{1b}[38;2;1mc\u{1b}[48;5;999md",
+            "a\u{1b}]8;;\u{1b}]8;;x\u{7}b\u{1b}]8;;\u{1b}\\",
+            "a\u{1b}]0;\u{9d}\u{1b}[31mb",
+            "\u{1b}[31ma\u{1b}[2\u{1b}[1mb\u{1b}[",
+            &invalid_utf8,
+        ];
+        let long_params = format!("a\u{1b}[{}mb", "1;".repeat(10_000));
+        let long_digits = format!("\u{1b}[31ma\u{1b}[{}", "1".repeat(20_000));
+        let interleaved = "\u{1b}[1\u{1b}".repeat(1_000);
+
+        for s in cases
+            .iter()
+            .copied()
+            .chain([&*long_params, &*long_digits, &*interleaved])
+        {
+            let text = srip_ansi_sequences(s);
+            let bounds = text
+                .char_indices()
+                .map(|(i, _)| i)
+                .chain([text.len()])
+                .take(20)
+                .collect::<Vec<_>>();
+            for &start in &bounds {
+                for &end in bounds.iter().filter(|&&end| end >= start) {
+                    let cut = s.cut(start..end);
+                    assert_eq!(text[start..end], srip_ansi_sequences(&cut), "s={:?}", s);
+                }
+            }
+
+            let chunks = chunks(s, 3);
+            assert_eq!(
+                text,
+                chunks
+                    .iter()
+                    .map(|c| srip_ansi_sequences(c))
+                    .collect::<String>()
+            );
+        }
+
+        // too long parameters are a text
+        assert_eq!(
+            format!("a[{}mb", "1;".repeat(10_000)),
+            srip_ansi_sequences(&long_params)
+        );
+        assert_eq!("\u{1b}[31ma\u{1b}[39m", long_digits.cut(..1));
+    }
+
+    #[test]
+    fn cut_keeps_escapes_verbatim_test() {
+        for s in [
+            "\u{1b}[01;031mA\u{1b}[0m",
+            "\u{1b}[00mA",
+            "\u{1b}[;1mA\u{1b}[m",
+            "\u{1b}[38;5;009mA\u{1b}[39m",
+            "\u{1b}[38;2;001;002;003mA\u{1b}[39m",
+            "\u{1b}[38:2::255:0:0mA\u{1b}[39m",
+            "\u{1b}[4:3mA\u{1b}[24m",
+            "\u{1b}[HA\u{1b}[1;1HB",
+            "\u{1b}[05AA\u{1b}[1CB\u{1b}[0K\u{1b}[K",
+            "\u{1b}[?25lA\u{1b}[?25h",
+            "\u{1b}[2JA\u{1b}[s\u{1b}[u",
+            "\u{1b}=A\u{1b}>",
+            "\u{1b}]0;title\u{1b}\\A\u{1b}]2;title\u{7}",
+        ] {
+            assert_eq!(s, s.cut(..), "s={:?}", s);
+            assert_eq!(s, s.cut_cow(..), "s={:?}", s);
+            let chunks = crate::chunks::Chunks::new(s).size(5).minimal_prefix(false);
+            assert_eq!(s, chunks.collect::<String>(), "s={:?}", s);
+        }
+
+        let s = "A\u{1b}[05AB\u{1b}[01mC\u{1b}[0m";
+        assert_eq!("\u{1b}[05AB\u{1b}[01mC\u{1b}[0m", s.cut(1..));
+        assert_eq!("\u{1b}[05A\u{1b}[01mC\u{1b}[0m", s.cut(2..));
+        assert_eq!("\u{1b}[05AB\u{1b}[01m\u{1b}[22m", s.cut(1..2));
+    }
+
+    #[test]
+    fn cut_osc_test() {
+        for title in ["\u{1b}]0;my title\u{7}", "\u{1b}]2;my title\u{1b}\\"] {
+            let s = format!("ab{}cd", title);
+            assert_eq!(format!("b{}c", title), s.cut(1..3));
+            assert_eq!(format!("{}cd", title), s.cut(2..));
+            assert_eq!("cd", s.cut_minimal(2..));
+            assert_eq!(format!("ab{}", title), s.cut(..2));
+            assert_eq!(format!("ab{}c", title), s.cut(..3));
+            assert_eq!(format!("b{}c", title), AnsiIndexedStr::new(&s).cut(1..3));
+            assert_eq!(chunks(&s, 2), ["ab".to_owned(), format!("{}cd", title)]);
+
+            let options = CutOptions::default().drop_osc(true);
+            assert_eq!("bc", s.cut_with(1..3, options));
+            assert_eq!("bc", AnsiIndexedStr::new(&s).cut_bytes(1, Some(3), options));
+        }
+
+        let s = "\u{1b}[31ma\u{1b}]0;t\u{7}b\u{1b}]8;;x\u{7}c\u{1b}]8;;\u{7}\u{1b}[39m";
+        assert_eq!(
+            "\u{1b}[31mab\u{1b}]8;;x\u{7}c\u{1b}]8;;\u{7}\u{1b}[39m",
+            s.cut_with(.., CutOptions::default().drop_osc(true))
+        );
+    }
+
+    #[test]
+    fn cut_hyperlink_test() {
+        let s = "\u{1b}]8;;https://example.com\u{1b}\\click me\u{1b}]8;;\u{1b}\\ after";
+        assert_eq!(
+            "\u{1b}]8;;https://example.com\u{1b}\\click\u{1b}]8;;\u{1b}\\",
+            s.cut(..5)
+        );
+        assert_eq!(
+            "\u{1b}]8;;https://example.com\u{1b}\\me\u{1b}]8;;\u{1b}\\",
+            s.cut_minimal(6..8)
+        );
+        assert_eq!(" after", s.cut_minimal(8..));
+        assert_eq!("click me after", srip_ansi_sequences(s));
+
+        // BEL terminated one with an id
+        let s = "\u{1b}]8;id=1;http://a.b\u{7}click me\u{1b}]8;;\u{7}";
+        assert_eq!(
+            "\u{1b}]8;id=1;http://a.b\u{7}cl\u{1b}]8;;\u{1b}\\",
+            s.cut(..2)
+        );
+        assert_eq!(
+            chunks(s, 4),
+            [
+                "\u{1b}]8;id=1;http://a.b\u{1b}\\clic\u{1b}]8;;\u{1b}\\",
+                "\u{1b}]8;id=1;http://a.b\u{1b}\\k me\u{1b}]8;;\u{7}",
+            ]
+        );
+
+        // a reset doesn't close a link
+        let s = "\u{1b}]8;;x\u{7}\u{1b}[31mab\u{1b}[0mcd\u{1b}]8;;\u{7}";
+        assert_eq!(
+            "\u{1b}]8;;x\u{1b}\\c\u{1b}]8;;\u{1b}\\",
+            s.cut_minimal(2..3)
+        );
+
+        // unterminated and unrelated OSC sequences
+        assert_eq!("\u{1b}]0;title\u{7}ab", "\u{1b}]0;title\u{7}abc".cut(..2));
+        assert_eq!("", "\u{1b}]8;;xabc".cut(..6));
+    }
+
+    #[test]
+    fn underline_styles_test() {
+        let tests = [
+            ("4:0", None, false, false),
+            ("4:1", None, true, false),
+            ("4:2", None, false, true),
+            ("4:3", Some(3), false, false),
+            ("4:4", Some(4), false, false),
+            ("4:5", Some(5), false, false),
+            ("4:3;4", None, true, false),
+            ("4:3;21", None, false, true),
+            ("4:3;24", None, false, false),
+            ("4:5;4:0", None, false, false),
+        ];
+
+        for (params, style, underline, double_underline) in tests {
+            let mut state = AnsiState::default();
+            update_ansi_state_by_params(&mut state, params);
+            assert_eq!(state.underline_style, style, "params={}", params);
+            assert_eq!(state.underline, underline, "params={}", params);
+            assert_eq!(
+                state.double_underline, double_underline,
+                "params={}",
+                params
+            );
+            assert!(state.unknown.is_empty(), "params={}", params);
+        }
+
+        let s = "\u{1b}[4:3m\u{1b}[58:2::255:0:0mabcdef\u{1b}[59m\u{1b}[4:0m";
+        assert_eq!(
+            "\u{1b}[4:3m\u{1b}[58;2;255;0;0mcd\u{1b}[59;24m",
+            s.cut_minimal(2..4)
+        );
+        assert_eq!(
+            chunks(s, 3),
+            [
+                "\u{1b}[4:3m\u{1b}[58;2;255;0;0mabc\u{1b}[59;24m",
+                "\u{1b}[4:3m\u{1b}[58;2;255;0;0mdef\u{1b}[59m\u{1b}[4:0m",
+            ]
+        );
+
+        // curly underline is replaced by a plain one
+        let s = "\u{1b}[4:3mab\u{1b}[4mcd\u{1b}[24m";
+        assert_eq!("\u{1b}[4:3mb\u{1b}[4mc\u{1b}[24m", s.cut(1..3));
+        assert_eq!("\u{1b}[4md\u{1b}[24m", s.cut_minimal(3..));
+
+        let style = AnsiIndexedStr::new(s).style_at(0).unwrap();
+        assert!(style.is_underline() && !style.is_double_underline());
+    }
+
+    #[test]
+    fn cut_unknown_policy_test() {
+        let cut = |s: &str, range: std::ops::Range<usize>, minimal: bool, policy| {
+            let options = CutOptions::default().minimal(minimal).unknown(policy);
+            s.cut_with(range, options)
+        };
+
+        let s = "\u{1b}[31mab\u{1b}[56mcde";
+
+        let policy = UnknownPolicy::FullResetOnUnknown;
+        assert_eq!(
+            "\u{1b}[31mb\u{1b}[56mc\u{1b}[39m\u{1b}[0m",
+            cut(s, 1..3, false, policy)
+        );
+        assert_eq!("\u{1b}[31md\u{1b}[39m\u{1b}[0m", cut(s, 3..4, true, policy));
+        assert_eq!("\u{1b}[31ma\u{1b}[39m", cut(s, 0..1, true, policy));
+
+        let policy = UnknownPolicy::IgnoreUnknown;
+        assert_eq!(
+            "\u{1b}[31mb\u{1b}[56mc\u{1b}[39m",
+            cut(s, 1..3, false, policy)
+        );
+        assert_eq!("\u{1b}[31md\u{1b}[39m", cut(s, 3..4, true, policy));
+
+        let policy = UnknownPolicy::ReplayRaw;
+        assert_eq!(
+            "\u{1b}[31mb\u{1b}[56mc\u{1b}[39m",
+            cut(s, 1..3, false, policy)
+        );
+        assert_eq!(
+            "\u{1b}[31m\u{1b}[56md\u{1b}[39m",
+            cut(s, 3..4, true, policy)
+        );
+
+        let options = CutOptions::default()
+            .unknown(UnknownPolicy::IgnoreUnknown)
+            .reset(ResetPolicy::FullReset);
+        assert_eq!("\u{1b}[56mab", "\u{1b}[56mabc".cut_with(..2, options));
+    }
+
+    #[test]
+    fn cut_raw_sequences_test() {
+        let s = "\u{1b}[>4;2m\u{1b}[31mab\u{1b}[0mcd";
+        assert_eq!("\u{1b}[31m\u{1b}[>4;2mb\u{1b}[0m", s.cut_minimal(1..2));
+        assert_eq!("\u{1b}[>4;2m\u{1b}[31mb\u{1b}[0m", s.cut(1..2));
+        assert_eq!("cd", s.cut_minimal(2..));
+        assert_eq!(
+            chunks(s, 1),
+            [
+                "\u{1b}[31m\u{1b}[>4;2ma\u{1b}[39m",
+                "\u{1b}[31m\u{1b}[>4;2mb\u{1b}[39m",
+                "c",
+                "d"
+            ]
+        );
+
+        // sequences are replayed in order they were met, each once
+        let s = "a\u{1b}[>4;2mb\u{1b}[?1mc\u{1b}[>4;2md";
+        assert_eq!("\u{1b}[>4;2m\u{1b}[?1mc\u{1b}[>4;2m", s.cut_minimal(2..3));
+        assert_eq!("\u{1b}[>4;2m\u{1b}[?1md", s.cut_minimal(3..));
+        assert_eq!(
+            chunks(s, 2),
+            ["a\u{1b}[>4;2mb", "\u{1b}[>4;2m\u{1b}[?1mc\u{1b}[>4;2md"]
+        );
+
+        // a reset clears them
+        let mut state = AnsiState::default();
+        update_ansi_state_by_str(&mut state, "\u{1b}[>4;2m\u{1b}[?1m");
+        assert_eq!(state.raw, ["\u{1b}[>4;2m", "\u{1b}[?1m"]);
+        update_ansi_state_by_str(&mut state, "\u{1b}[m");
+        assert!(state.raw.is_empty());
+
+        // other sequences are not replayed
+        assert_eq!("b", "\u{1b}[2Ja\u{1b}[1Cb".cut_minimal(1..));
+    }
+
+    #[test]
+    fn empty_sgr_params_test() {
+        let state = |s: &str| {
+            let mut state = AnsiState::default();
+            update_ansi_state_by_str(&mut state, s);
+            state
+        };
+
+        let s = state("\u{1b}[1;31m\u{1b}[m");
+        assert!(s.reset && is_closed(&s));
+
+        let s = state("\u{1b}[1;31m\u{1b}[;32m");
+        assert!(s.reset && !s.bold);
+        assert_eq!(s.fg_color, Some(AnsiColor::Bit4 { index: 32 }));
+
+        let s = state("\u{1b}[1;31m\u{1b}[;m");
+        assert!(s.reset && is_closed(&s));
+
+        assert_eq!("\u{1b}[31mab\u{1b}[mcd".cut(1..), "\u{1b}[31mb\u{1b}[mcd");
+        assert_eq!("\u{1b}[31mab\u{1b}[mcd".cut_minimal(2..), "cd");
+        assert_eq!("\u{1b}[31mab\u{1b}[mcd".cut(..3), "\u{1b}[31mab\u{1b}[mc");
+        assert_eq!(
+            "\u{1b}[1mab\u{1b}[;31mcd".cut_minimal(3..),
+            "\u{1b}[31md\u{1b}[39m"
+        );
+    }
+
+    #[test]
+    fn update_ansi_state_by_params_test() {
+        let mut state = AnsiState::default();
+        update_ansi_state_by_params(&mut state, "38:2::1:2:3;1");
+        assert_eq!(state.fg_color, Some(AnsiColor::Bit24 { r: 1, g: 2, b: 3 }));
+        assert!(state.bold);
+
+        let mut state = AnsiState::default();
+        update_ansi_state_by_params(&mut state, "38:2:0:1:2:3");
+        assert_eq!(state.fg_color, Some(AnsiColor::Bit24 { r: 1, g: 2, b: 3 }));
+        assert!(state.unknown.is_empty());
+
+        let mut state = AnsiState::default();
+        update_ansi_state_by_params(&mut state, "48;5;208;58:5:1;4:3;3");
+        assert_eq!(state.bg_color, Some(AnsiColor::Bit8 { index: 208 }));
+        assert_eq!(state.undr_color, Some(AnsiColor::Bit8 { index: 1 }));
+        assert_eq!(state.underline_style, Some(3));
+        assert!(state.italic);
+
+        update_ansi_state_by_params(&mut state, "4:0");
+        assert_eq!(state.underline_style, None);
+
+        update_ansi_state_by_params(&mut state, ";1");
+        assert!(state.reset && state.bold);
+        assert_eq!(state.bg_color, None);
+
+        let mut state = AnsiState::default();
+        update_ansi_state_by_params(&mut state, "38:9:1");
+        assert_eq!(state.unknown, ["38:9:1"]);
+
+        let mut state = AnsiState::default();
+        update_ansi_state_by_params(&mut state, "38:5:300;300;56;56");
+        assert_eq!(state.unknown, ["38:5:300", "300", "56"]);
+
+        // an out of range color doesn't take the parameters which follow it
+        let mut state = AnsiState::default();
+        update_ansi_state_by_params(&mut state, "38;5;300;1;48;2;1;256;3;4");
+        assert_eq!(state.unknown, ["38;5;300", "48;2;1;256;3"]);
+        assert_eq!(state.fg_color, None);
+        assert_eq!(state.bg_color, None);
+        assert!(state.bold && state.underline);
+
+        let mut state = AnsiState::default();
+        update_ansi_state_by_params(&mut state, "65535;99999;0;65536");
+        assert_eq!(state.unknown, ["65536"]);
+        assert!(state.reset);
+    }
+
+    #[test]
+    fn cut_large_sgr_params_test() {
+        for s in [
+            "\u{1b}[65535mab\u{1b}[0m",
+            "\u{1b}[38;5;300mab\u{1b}[0m",
+            "a\u{1b}[38;5;300m\u{1b}[65535mb\u{1b}[0m",
+        ] {
+            assert_eq!("ab", srip_ansi_sequences(s));
+            assert_eq!(s, s.cut(..));
+            assert_eq!("a", srip_ansi_sequences(&s.cut(..1)));
+            assert_eq!("b", srip_ansi_sequences(&s.cut(1..)));
+            assert_eq!(
+                ["a", "b"],
+                chunks(s, 1)
+                    .iter()
+                    .map(|c| srip_ansi_sequences(c))
+                    .collect::<Vec<_>>()[..]
+            );
+        }
+
+        let s = "\u{1b}[38;5;300;1mab\u{1b}[0m";
+        assert_eq!("\u{1b}[38;5;300;1ma\u{1b}[22m\u{1b}[0m", s.cut(..1));
+        assert_eq!("\u{1b}[1mb\u{1b}[0m", s.cut_minimal(1..));
+        assert_eq!(
+            "\u{1b}[1m\u{1b}[38;5;300mb\u{1b}[0m",
+            s.cut_with(
+                1..,
+                CutOptions::default()
+                    .minimal(true)
+                    .unknown(UnknownPolicy::ReplayRaw)
+            )
+        );
+    }
+
+    #[test]
+    fn cut_count_controls_test() {
+        let options = CutOptions::default().count_controls(false);
+
+        let s = "ab\rcd";
+        assert_eq!("ab\r", s.cut(..3));
+        assert_eq!("ab\r", s.cut_with(..2, options));
+        assert_eq!("cd", s.cut_with(2.., options));
+        assert_eq!("b\rc", s.cut_with(1..3, options));
+        assert_eq!("cd", s.cut_with(2.., options.minimal(true)));
+        for i in 0..=4 {
+            let cut = s.cut_with(..i, options) + &s.cut_with(i.., options);
+            assert_eq!(s, cut);
+        }
+
+        let s = "\u{1b}[31mab\u{7}\u{7}c\u{1b}[0md\u{8}";
+        assert_eq!("\u{1b}[31mb\u{7}\u{7}c\u{1b}[0m", s.cut_with(1..3, options));
+        assert_eq!("\u{1b}[31mc\u{1b}[0m", s.cut_with(2..3, options));
+        assert_eq!("d\u{8}", s.cut_with(3.., options.minimal(true)));
+        assert_eq!(
+            "\u{1b}[31mb\u{7}\u{1b}[39m",
+            s.cut_with(1..3, CutOptions::default())
+        );
+
+        // a tab is counted unless it's told otherwise
+        let s = "a\tb\nc";
+        assert_eq!("\tb\n", s.cut_with(1..3, options));
+        assert_eq!("b\n", s.cut_with(1..3, options.counted_controls(&['\n'])));
+        assert_eq!(
+            "\tb\nc",
+            s.cut_with(1.., options.counted_controls(&['\t', '\n', 'x']))
+        );
+
+        let s = "e\r\u{301}x";
+        let options = options.grapheme(GraphemePolicy::Snap);
+        assert_eq!("e\r\u{301}", s.cut_with(..1, options));
+        assert_eq!("x", s.cut_with(1.., options.minimal(true)));
+    }
+
+    #[cfg(feature = "unicode-width")]
+    #[test]
+    fn controls_width_test() {
+        assert_eq!(vec!["a\r\t", "b"], chunks_width("a\r\tb", 1));
+        assert_eq!(vec!["a\u{7}b"], chunks_width("a\u{7}b", 2));
+    }
+
+    #[test]
+    fn cut_with_grapheme_policy_test() {
+        let options = |policy| CutOptions::default().grapheme(policy);
+
+        let s = "e\u{301}x";
+        assert_eq!("e", s.cut_with(..1, options(GraphemePolicy::Allow)));
+        assert_eq!("\u{301}x", s.cut_with(1.., options(GraphemePolicy::Allow)));
+        assert_eq!("e\u{301}", s.cut_with(..1, options(GraphemePolicy::Snap)));
+        assert_eq!("x", s.cut_with(1.., options(GraphemePolicy::Snap)));
+        assert_eq!(
+            Err(AnsiCutError::NotAGraphemeBoundary { index: 1 }),
+            s.try_cut_with(..1, options(GraphemePolicy::Error))
+        );
+        assert_eq!(
+            Ok(String::from("e\u{301}")),
+            s.try_cut_with(..3, options(GraphemePolicy::Error))
+        );
+
+        // a char boundary error goes first
+        assert_eq!(
+            Err(AnsiCutError::NotACharBoundary {
+                index: 2,
+                ch: '\u{301}',
+                prev_boundary: 1,
+                next_boundary: 3
+            }),
+            s.try_cut_with(..2, options(GraphemePolicy::Error))
+        );
+
+        let s = "\u{1b}[31ma\u{1b}[1me\u{301}\u{302}b\u{1b}[0m";
+        assert_eq!(
+            "\u{1b}[31ma\u{1b}[1me\u{301}\u{302}\u{1b}[22;39m",
+            s.cut_with(..2, options(GraphemePolicy::Snap))
+        );
+        assert_eq!(
+            "\u{1b}[1;31mb\u{1b}[0m",
+            s.cut_with(2.., options(GraphemePolicy::Snap).minimal(true))
+        );
+
+        let s = "👩\u{200d}💻!";
+        assert_eq!("👩", s.cut_with(..4, options(GraphemePolicy::Allow)));
+        assert_eq!(
+            "👩\u{200d}💻",
+            s.cut_with(..4, options(GraphemePolicy::Snap))
+        );
+        assert_eq!(
+            "👩\u{200d}💻",
+            s.cut_with(..7, options(GraphemePolicy::Snap))
+        );
+        assert_eq!("!", s.cut_with(7.., options(GraphemePolicy::Snap)));
+        assert_eq!(
+            Err(AnsiCutError::NotAGraphemeBoundary { index: 7 }),
+            s.try_cut_with(7.., options(GraphemePolicy::Error))
+        );
+
+        let s = "When the night";
+        for i in 0..=s.len() {
+            assert_eq!(s.cut(i..), s.cut_with(i.., options(GraphemePolicy::Snap)));
+            assert!(s.try_cut_with(..i, options(GraphemePolicy::Error)).is_ok());
+        }
+    }
+
+    #[test]
+    fn cut_extended_colors_test() {
+        use owo_colors::{OwoColorize, XtermColors};
+
+        let s = "\u{1b}[38;5;208mX\u{1b}[0m";
+        assert_eq!("\u{1b}[38;5;208mX\u{1b}[0m", s.cut(..1));
+        assert_eq!(
+            "\u{1b}[38;5;208mXY\u{1b}[39m",
+            "\u{1b}[38;5;208mXYZ".cut(..2)
+        );
+        assert_eq!(
+            "\u{1b}[38;2;10;20;30mXY\u{1b}[39m",
+            "\u{1b}[38;2;10;20;30mXYZ".cut(..2)
+        );
+        assert_eq!(
+            "\u{1b}[48;2;10;20;30mY\u{1b}[49m",
+            "\u{1b}[48;2;10;20;30mXYZ".cut(1..2)
+        );
+        assert_eq!("\u{1b}[58;5;1mY\u{1b}[59m", "\u{1b}[58;5;1mXYZ".cut(1..2));
+
+        let s = "When the night".fg_rgb::<255, 128, 0>().to_string();
+        assert_eq!("\u{1b}[38;2;255;128;000mthe\u{1b}[39m", s.cut(5..8));
+        assert_eq!("\u{1b}[38;2;255;128;0mnight\u{1b}[39m", s.cut_minimal(9..));
+
+        let s = "When the night".bg_rgb::<1, 2, 3>().to_string();
+        assert_eq!("\u{1b}[48;2;001;002;003mthe\u{1b}[49m", s.cut(5..8));
+
+        let s = "When the night".color(XtermColors::FlushOrange).to_string();
+        assert_eq!(
+            "the".color(XtermColors::FlushOrange).to_string(),
+            s.cut(5..8)
+        );
+
+        let s = format!(
+            "{} {}",
+            "When".fg_rgb::<255, 128, 0>(),
+            "night".on_color(XtermColors::FlushOrange)
+        );
+        assert_eq!(
+            format!(
+                "\u{1b}[38;2;255;128;000men\u{1b}[39m {}",
+                "ni".on_color(XtermColors::FlushOrange)
+            ),
+            s.cut(2..7)
+        );
+
+        let style = AnsiIndexedStr::new(&s).style_at(6).unwrap();
+        assert_eq!(
+            Some(Color::Indexed(XtermColors::FlushOrange as u8)),
+            style.bg()
+        );
+        let style = AnsiIndexedStr::new(&s).style_at(0).unwrap();
+        assert_eq!(Some(Color::Rgb(255, 128, 0)), style.fg());
+    }
+
+    #[test]
+    fn cut_colored_bg_fg_test() {
+        let colored_s = "\u{1b}[31;40mTEXT\u{1b}[0m";
+        assert_eq!(colored_s, colored_s.cut(..));
+        assert_eq!(colored_s, colored_s.cut(0..4));
+        assert_eq!("\u{1b}[31;40mEXT\u{1b}[0m", colored_s.cut(1..));
+        assert_eq!("\u{1b}[31;40mTEX\u{1b}[49;39m", colored_s.cut(..3));
+        assert_eq!("\u{1b}[31;40mEX\u{1b}[49;39m", colored_s.cut(1..3));
+
+        assert_eq!("TEXT", srip_ansi_sequences(&colored_s.cut(..)));
+        assert_eq!("TEX", srip_ansi_sequences(&colored_s.cut(..3)));
+        assert_eq!("EX", srip_ansi_sequences(&colored_s.cut(1..3)));
+
+        let colored_s = "\u{1b}[31;40mTEXT\u{1b}[0m \u{1b}[34;42mTEXT\u{1b}[0m";
+        assert_eq!(colored_s, colored_s.cut(..));
+        assert_eq!(colored_s, colored_s.cut(0..9));
+        assert_eq!(
+            "\u{1b}[31;40mXT\u{1b}[0m \u{1b}[34;42mTEXT\u{1b}[0m",
+            colored_s.cut(2..)
+        );
+        assert_eq!(
+            "\u{1b}[31;40mTEXT\u{1b}[0m \u{1b}[34;42mT\u{1b}[49;39m",
+            colored_s.cut(..6)
+        );
+        assert_eq!(
+            "\u{1b}[31;40mXT\u{1b}[0m \u{1b}[34;42mT\u{1b}[49;39m",
+            colored_s.cut(2..6)
+        );
+
+        assert_eq!("TEXT TEXT", srip_ansi_sequences(&colored_s.cut(..)));
+        assert_eq!("TEXT T", srip_ansi_sequences(&colored_s.cut(..6)));
+        assert_eq!("XT T", srip_ansi_sequences(&colored_s.cut(2..6)));
+
+        assert_eq!("\u{1b}[40m\u{1b}[49m", cut("\u{1b}[40m\u{1b}[49m", ..));
+    }
+
+    #[test]
+    fn cut_keep_general_color_test() {
+        assert_eq!(
+            "\u{1b}[41m \u{1b}[34m12\u{1b}[39;49m",
+            "\u{1b}[41m\u{1b}[30msomething\u{1b}[39m \u{1b}[34m123123\u{1b}[39m\u{1b}[49m"
+                .cut(9..12)
+        );
+    }
+
+    #[test]
+    fn cut_no_colored_str() {
+        assert_eq!("something", cut("something", ..));
+        assert_eq!("som", cut("something", ..3));
+        assert_eq!("some", cut("something", ..=3));
+        assert_eq!("et", cut("something", 3..5));
+        assert_eq!("eth", cut("something", 3..=5));
+        assert_eq!("ething", cut("something", 3..));
+        assert_eq!("something", cut("something", ..));
+        assert_eq!("", cut("", ..));
+    }
+
+    #[test]
+    fn dont_panic_on_exceeding_upper_bound() {
+        assert_eq!("TEXT", cut("TEXT", ..50));
+        assert_eq!("EXT", cut("TEXT", 1..50));
+        assert_eq!(
+            "\u{1b}[31;40mTEXT\u{1b}[0m",
+            cut("\u{1b}[31;40mTEXT\u{1b}[0m", ..50)
+        );
+        assert_eq!(
+            "\u{1b}[31;40mEXT\u{1b}[0m",
+            cut("\u{1b}[31;40mTEXT\u{1b}[0m", 1..50)
+        );
+    }
+
+    #[test]
+    fn cut_with_excluded_start_bound_test() {
+        use std::ops::Bound;
+
+        let s = "\u{1b}[31;40mTEXT\u{1b}[0m";
+        assert_eq!(
+            "\u{1b}[31;40mXT\u{1b}[0m",
+            s.cut((Bound::Excluded(1), Bound::Unbounded))
+        );
+        assert_eq!(
+            "\u{1b}[31;40mEX\u{1b}[49;39m",
+            s.cut((Bound::Excluded(0), Bound::Excluded(3)))
+        );
+        assert_eq!(
+            "\u{1b}[31;40mEXT\u{1b}[0m",
+            s.cut((Bound::Excluded(0), Bound::Included(3)))
+        );
+        assert_eq!("", s.cut((Bound::Excluded(usize::MAX), Bound::Unbounded)));
+        assert_eq!(
+            "EXT",
+            "TEXT".cut_chars((Bound::Excluded(0), Bound::Unbounded))
+        );
+    }
+
+    #[test]
+    fn cut_with_max_upper_bound_test() {
+        use std::ops::Bound;
+
+        assert_eq!("TEXT", cut("TEXT", ..=usize::MAX));
+        assert_eq!("EXT", cut("TEXT", 1..=usize::MAX));
+        assert_eq!(
+            "TEXT",
+            cut("TEXT", (Bound::Unbounded, Bound::Included(usize::MAX)))
+        );
+        assert_eq!("TEXT", "TEXT".cut_chars(..=usize::MAX));
+    }
+
+    #[test]
+    fn dont_panic_on_exceeding_lower_bound() {
+        assert_eq!("", cut("TEXT", 10..));
+        assert_eq!("", cut("TEXT", 10..50));
+    }
+
+    #[test]
+    #[should_panic = "index 1 is not on a UTF-8 code point boundary, it's inside '😀' (valid boundaries: 0, 4)"]
+    fn cut_a_mid_of_emojie_2_test() {
+        cut("😀", 1..2);
+    }
+
+    #[test]
+    #[should_panic = "index 1 is not on a UTF-8 code point boundary, it's inside '😀' (valid boundaries: 0, 4)"]
+    fn cut_a_mid_of_emojie_1_test() {
+        cut("😀", 1..);
+    }
+
+    #[test]
+    #[should_panic = "index 1 is not on a UTF-8 code point boundary, it's inside '😀' (valid boundaries: 0, 4)"]
+    fn cut_a_mid_of_emojie_0_test() {
+        cut("😀", ..1);
+    }
+
+    #[test]
+    #[should_panic = "index 3 is not on a UTF-8 code point boundary, it's inside '😀' (valid boundaries: 1, 5)"]
+    fn cut_end_in_a_mid_of_emojie_test() {
+        cut("\u{1b}[31ma😀b\u{1b}[39m", ..3);
+    }
+
+    #[test]
+    #[should_panic = "index 7 is not on a UTF-8 code point boundary, it's inside 'é' (valid boundaries: 6, 8)"]
+    fn cut_start_in_a_mid_of_char_test() {
+        cut("\u{1b}[31mabc\u{1b}[39mdef\u{1b}[1mé\u{1b}[22m", 7..);
+    }
+
+    #[test]
+    fn cut_emojies_test() {
+        let emojes = "😀😃😄😁😆😅😂🤣🥲😊";
+        assert_eq!(emojes, emojes.cut(..));
+        assert_eq!("😀", emojes.cut(..4));
+        assert_eq!("😃😄", emojes.cut(4..12));
+        assert_eq!("🤣🥲😊", emojes.cut(emojes.find('🤣').unwrap()..));
+    }
+
+    #[test]
+    // it's kept for compatibility, see cut_strict_test
+    fn cut_colored_x_x_test() {
+        assert_ne!("", cut("\u{1b}[31;40mTEXT\u{1b}[0m", 3..3));
+        assert_ne!(
+            "",
+            cut(
+                "\u{1b}[31;40mTEXT\u{1b}[0m \u{1b}[34;42mTEXT\u{1b}[0m",
+                1..1
+            )
+        );
+        assert_ne!("", cut("\u{1b}[31;40mTEXT\u{1b}[0m", ..0));
+    }
+
+    #[test]
+    fn cut_partially_colored_str_test() {
+        let s = "zxc_\u{1b}[31;40mTEXT\u{1b}[0m_qwe";
+        assert_eq!("zxc", s.cut(..3));
+        assert_eq!("zxc_\u{1b}[31;40mT\u{1b}[49;39m", s.cut(..5));
+        assert_eq!("\u{1b}[31;40mEXT\u{1b}[0m_q", s.cut(5..10));
+        assert_eq!("", s.cut(12..));
+    }
+
+    #[test]
+    fn cut_doesnt_take_text_after_upper_bound_test() {
+        let s = "AB\u{1b}[31mCD\u{1b}[39m";
+        assert_eq!("AB\u{1b}[31m\u{1b}[39m", s.cut(..2));
+        assert_eq!("\u{1b}[31mCD\u{1b}[39m", s.cut(2..));
+
+        let s = "\u{1b}[31mTE\u{1b}[0mXT\u{1b}[32mYY\u{1b}[39m";
+        assert_eq!("\u{1b}[31mTE\u{1b}[0m", s.cut(..2));
+    }
+
+    #[test]
+    #[allow(clippy::reversed_empty_ranges)]
+    fn try_cut_test() {
+        let s = "\u{1b}[31;40mTEXT\u{1b}[0m \u{1b}[34m😀😃\u{1b}[39m";
+        for (start, end) in [
+            (0, 4),
+            (1, 3),
+            (2, 9),
+            (5, 13),
+            (0, 100),
+            (100, 200),
+            (3, 3),
+        ] {
+            assert_eq!(Ok(s.cut(start..end)), s.try_cut(start..end));
+        }
+
+        assert_eq!(Ok(s.cut(..)), s.try_cut(..));
+        assert_eq!(Ok(s.cut(5..)), s.try_cut(5..));
+
+        assert_eq!(
+            Err(AnsiCutError::NotACharBoundary {
+                index: 6,
+                ch: '😀',
+                prev_boundary: 5,
+                next_boundary: 9
+            }),
+            s.try_cut(6..)
+        );
+        assert_eq!(
+            Err(AnsiCutError::NotACharBoundary {
+                index: 11,
+                ch: '😃',
+                prev_boundary: 9,
+                next_boundary: 13
+            }),
+            s.try_cut(5..11)
+        );
+        assert_eq!(
+            Err(AnsiCutError::NotACharBoundary {
+                index: 6,
+                ch: '😀',
+                prev_boundary: 5,
+                next_boundary: 9
+            }),
+            s.try_cut(6..11)
+        );
+        assert_eq!(
+            Err(AnsiCutError::StartAfterEnd { start: 3, end: 1 }),
+            s.try_cut(3..1)
+        );
+        assert_eq!(
+            "index 6 is not on a UTF-8 code point boundary, it's inside '😀' (valid boundaries: 5, 9)",
+            s.try_cut(6..).unwrap_err().to_string()
+        );
+        assert_eq!(
+            "index 11 is not on a UTF-8 code point boundary, it's inside '😃' (valid boundaries: 9, 13)",
+            s.try_cut(..11).unwrap_err().to_string()
+        );
+    }
+
+    #[test]
+    fn cut_lossy_test() {
+        assert_eq!("", "😀".cut_lossy(1..3));
+        assert_eq!("", "😀".cut_lossy(1..));
+        assert_eq!("", "😀".cut_lossy(..3));
+        assert_eq!("😀", "😀".cut_lossy(..));
+
+        let emojes = "😀😃😄";
+        assert_eq!("😃", emojes.cut_lossy(1..9));
+        assert_eq!("😃", emojes.cut_lossy(3..11));
+        assert_eq!("😃😄", emojes.cut_lossy(2..));
+        assert_eq!("😀😃", emojes.cut_lossy(..11));
+        assert_eq!("😀😃😄", emojes.cut_lossy(..100));
+        assert_eq!("", emojes.cut_lossy(5..7));
+
+        let s = "\u{1b}[31m😀\u{1b}[39m\u{1b}[34m😃\u{1b}[39m";
+        assert_eq!(s, s.cut_lossy(..));
+        assert_eq!(
+            "\u{1b}[31m😀\u{1b}[39m\u{1b}[34m\u{1b}[39m",
+            s.cut_lossy(..7)
+        );
+        assert_eq!("\u{1b}[34m😃\u{1b}[39m", s.cut_lossy(1..));
+        assert_eq!("\u{1b}[34m\u{1b}[39m", s.cut_lossy(1..7));
+    }
+
+    #[test]
+    fn cut_cow_test() {
+        fn is_borrowed(cut: Cow<'_, str>) -> bool {
+            matches!(cut, Cow::Borrowed(_))
+        }
+
+        let s = "something";
+        for (start, end) in [(0, 9), (0, 100), (2, 5), (9, 9), (100, 200)] {
+            assert!(is_borrowed(s.cut_cow(start..end)));
+            assert_eq!(s.cut(start..end), s.cut_cow(start..end));
+        }
+
+        let s = "\u{1b}[31;40mTEXT\u{1b}[0m zxc";
+        for (range, borrowed) in [
+            (0..100, true),
+            (0..4, true),
+            (1..3, false),
+            (4..100, false),
+            (5..8, false),
+            (0..0, false),
+        ] {
+            assert_eq!(
+                borrowed,
+                is_borrowed(s.cut_cow(range.clone())),
+                "{:?}",
+                range
+            );
+            assert_eq!(s.cut(range.clone()), s.cut_cow(range));
+        }
+
+        let s = "zxc \u{1b}[31mTEXT\u{1b}[39m";
+        assert!(is_borrowed(s.cut_cow(..)));
+        assert!(is_borrowed(s.cut_cow(..3)));
+        assert!(is_borrowed(s.cut_cow(2..)));
+        assert!(!is_borrowed(s.cut_cow(2..6)));
+        assert_eq!("zx", s.cut_cow(..2));
+        assert_eq!("c \u{1b}[31mTEXT\u{1b}[39m", s.cut_cow(2..));
+
+        // escape sequences which are printed differently are not borrowed
+        let s = "\u{9b}1m\u{9b}0mTEXT";
+        assert!(!is_borrowed(s.cut_cow(..)));
+        assert_eq!(s.cut(..), s.cut_cow(..));
+
+        let s = "\u{1b}[01m\u{1b}[0mTEXT";
+        assert!(is_borrowed(s.cut_cow(..)));
+
+        let s = "\u{1b}[31m\u{1b}[mTEXT";
+        assert!(is_borrowed(s.cut_cow(..)));
+
+        assert!(is_borrowed(String::from("something").cut_cow(1..3)));
+    }
+
+    #[test]
+    #[should_panic = "index 1 is not on a UTF-8 code point boundary, it's inside '😀' (valid boundaries: 0, 4)"]
+    fn cut_cow_a_mid_of_emojie_test() {
+        "😀😃".cut_cow(1..4);
+    }
+
+    #[test]
+    fn split_at_visible_test() {
+        let emojes = "😀😃😄";
+        assert_eq!(
+            (String::new(), emojes.to_string()),
+            emojes.split_at_visible(0)
+        );
+        assert_eq!(
+            ("😀".to_string(), "😃😄".to_string()),
+            emojes.split_at_visible(4)
+        );
+        assert_eq!(
+            (emojes.to_string(), String::new()),
+            emojes.split_at_visible(12)
+        );
+        assert_eq!(
+            (emojes.to_string(), String::new()),
+            emojes.split_at_visible(100)
+        );
+
+        let s = "zxc_\u{1b}[31;40mTEXT\u{1b}[0m_qwe";
+        assert_eq!(("".to_string(), s.to_string()), s.split_at_visible(0));
+        assert_eq!(
+            (
+                "zxc_".to_string(),
+                "\u{1b}[31;40mTEXT\u{1b}[0m_qwe".to_string()
+            ),
+            s.split_at_visible(4)
+        );
+        assert_eq!(
+            (
+                "zxc_\u{1b}[31;40mTE\u{1b}[49;39m".to_string(),
+                "\u{1b}[31;40mXT\u{1b}[0m_qwe".to_string()
+            ),
+            s.split_at_visible(6)
+        );
+        assert_eq!(
+            (
+                "zxc_\u{1b}[31;40mTEXT\u{1b}[49;39m".to_string(),
+                "_qwe".to_string()
+            ),
+            s.split_at_visible(8)
+        );
+        assert_eq!((s.to_string(), String::new()), s.split_at_visible(12));
+
+        let s = "\u{1b}[31mTEXT\u{1b}[39m";
+        assert_eq!((s.to_string(), String::new()), s.split_at_visible(4));
+    }
+
+    #[test]
+    #[should_panic = "index 1 is not on a UTF-8 code point boundary, it's inside '😀' (valid boundaries: 0, 4)"]
+    fn split_at_visible_a_mid_of_emojie_test() {
+        "😀".split_at_visible(1);
+    }
+
+    #[cfg(feature = "unicode-width")]
+    #[test]
+    fn split_at_width_test() {
+        let s = "你好世界";
+        let split = |cols| {
+            let (l, r) = s.split_at_width(cols);
+            (l, r.to_string())
+        };
+        assert_eq!(("".to_string(), s.to_string()), split(0));
+        assert_eq!(("".to_string(), s.to_string()), split(1));
+        assert_eq!(("你".to_string(), "好世界".to_string()), split(2));
+        assert_eq!(("你".to_string(), "好世界".to_string()), split(3));
+        assert_eq!((s.to_string(), "".to_string()), split(8));
+        assert_eq!((s.to_string(), "".to_string()), split(100));
+
+        assert_eq!(
+            (" ".to_string(), s.to_string()),
+            s.split_at_width_with(1, WideCharPolicy::Pad)
+        );
+        assert_eq!(
+            ("你 ".to_string(), "好世界".to_string()),
+            s.split_at_width_with(3, WideCharPolicy::Pad)
+        );
+        assert_eq!(
+            (s.to_string(), "".to_string()),
+            s.split_at_width_with(100, WideCharPolicy::Pad)
+        );
+
+        let s = "a\u{1b}[31;40m你e\u{301}\u{1b}[0m好";
+        assert_eq!(
+            (
+                "a".to_string(),
+                "\u{1b}[31;40m你e\u{301}\u{1b}[0m好".to_string()
+            ),
+            s.split_at_width(2)
+        );
+        assert_eq!(
+            (
+                "a\u{1b}[31;40m你e\u{301}\u{1b}[49;39m".to_string(),
+                "好".to_string()
+            ),
+            s.split_at_width(4)
+        );
+    }
+
+    #[test]
+    fn cut_out_test() {
+        assert_eq!("sing", "something".cut_out(1..6));
+        assert_eq!("thing", "something".cut_out(..4));
+        assert_eq!("some", "something".cut_out(4..));
+        assert_eq!("", "something".cut_out(..));
+        assert_eq!("something", "something".cut_out(4..4));
+        assert_eq!("😀😄", "😀😃😄".cut_out(4..8));
+
+        let s = "\u{1b}[31mTEXT\u{1b}[39m \u{1b}[34mTEXT\u{1b}[39m";
+        assert_eq!("\u{1b}[31mTE\u{1b}[34mXT\u{1b}[39m", s.cut_out(2..7));
+        assert_eq!("\u{1b}[31mTE\u{1b}[39m", s.cut_out(2..));
+        assert_eq!("\u{1b}[34mXT\u{1b}[39m", s.cut_out(..7));
+        assert_eq!("\u{1b}[31mTEXT\u{1b}[34mTEXT\u{1b}[39m", s.cut_out(4..5));
+        assert_eq!("", s.cut_out(..));
+
+        let s = "\u{1b}[1;31mA\u{1b}[22mB\u{1b}[44mC\u{1b}[0mD";
+        assert_eq!(
+            "\u{1b}[1;31mA\u{1b}[22m\u{1b}[44mC\u{1b}[0mD",
+            s.cut_out(1..2)
+        );
+        assert_eq!("\u{1b}[1;31mA\u{1b}[39;22mD", s.cut_out(1..3));
+        assert_eq!("\u{1b}[1;31mA\u{1b}[39;22m", s.cut_out(1..));
+        assert_eq!("\u{1b}[31mB\u{1b}[44mC\u{1b}[0mD", s.cut_out(..1));
+    }
+
+    #[test]
+    fn replace_range_visible_test() {
+        assert_eq!("s---ing", "something".replace_range_visible(1..6, "---"));
+        assert_eq!("---thing", "something".replace_range_visible(..4, "---"));
+        assert_eq!("some---", "something".replace_range_visible(4.., "---"));
+        assert_eq!("---", "something".replace_range_visible(.., "---"));
+        assert_eq!(
+            "some---thing",
+            "something".replace_range_visible(4..4, "---")
+        );
+        assert_eq!(
+            "---something",
+            "something".replace_range_visible(0..0, "---")
+        );
+        assert_eq!(
+            "something---",
+            "something".replace_range_visible(9..9, "---")
+        );
+        assert_eq!("---", "".replace_range_visible(.., "---"));
+
+        let s = "\u{1b}[31mTEXT\u{1b}[39m \u{1b}[34mTEXT\u{1b}[39m";
+        assert_eq!(
+            "\u{1b}[31mTE--\u{1b}[34mXT\u{1b}[39m",
+            s.replace_range_visible(2..7, "--")
+        );
+        assert_eq!(
+            "\u{1b}[31mTE\u{1b}[42m--\u{1b}[49m\u{1b}[34mXT\u{1b}[39m",
+            s.replace_range_visible(2..7, "\u{1b}[42m--\u{1b}[49m")
+        );
+        assert_eq!(
+            "\u{1b}[31mTE\u{1b}[32m--\u{1b}[34mXT\u{1b}[39m",
+            s.replace_range_visible(2..7, "\u{1b}[32m--")
+        );
+        assert_eq!(
+            "\u{1b}[31mTE\u{1b}[1m--\u{1b}[22mXT\u{1b}[39m \u{1b}[34mTEXT\u{1b}[39m",
+            s.replace_range_visible(2..2, "\u{1b}[1m--")
+        );
+        assert_eq!(
+            "\u{1b}[31mTE--\u{1b}[39m",
+            s.replace_range_visible(2.., "--")
+        );
+        assert_eq!(
+            "\u{1b}[31mTEXT\u{1b}[39m \u{1b}[34mTEXT\u{1b}[1m--\u{1b}[22;39m",
+            s.replace_range_visible(9.., "\u{1b}[1m--")
+        );
+        assert_eq!(
+            "\u{1b}[31m--TEXT\u{1b}[39m \u{1b}[34mTEXT\u{1b}[39m",
+            s.replace_range_visible(0..0, "--")
+        );
+    }
+
+    #[test]
+    fn insert_str_visible_test() {
+        assert_eq!("|something", "something".insert_str_visible(0, "|"));
+        assert_eq!("some|thing", "something".insert_str_visible(4, "|"));
+        assert_eq!("something|", "something".insert_str_visible(9, "|"));
+        assert_eq!("something|", "something".insert_str_visible(100, "|"));
+        assert_eq!("😀|😃😄", "😀😃😄".insert_str_visible(1, "|"));
+
+        let s = "\u{1b}[31mTEXT\u{1b}[39m";
+        assert_eq!(
+            "\u{1b}[31mTE|XT\u{1b}[39m",
+            s.insert_str_visible_with(2, "|", InsertStyle::Inherit)
+        );
+        assert_eq!(
+            "\u{1b}[31mTE\u{1b}[39m|\u{1b}[31mXT\u{1b}[39m",
+            s.insert_str_visible_with(2, "|", InsertStyle::Isolate)
+        );
+        assert_eq!(
+            "\u{1b}[31mTE\u{1b}[39m\u{1b}[1m|\u{1b}[22m\u{1b}[31mXT\u{1b}[39m",
+            s.insert_str_visible_with(2, "\u{1b}[1m|", InsertStyle::Isolate)
+        );
+        assert_eq!(
+            "|\u{1b}[31mTEXT\u{1b}[39m",
+            s.insert_str_visible_with(0, "|", InsertStyle::Isolate)
+        );
+        assert_eq!(
+            "\u{1b}[31mTEXT\u{1b}[39m|",
+            s.insert_str_visible_with(4, "|", InsertStyle::Isolate)
+        );
+
+        let s = "AB\u{1b}[31mCD\u{1b}[39m";
+        assert_eq!("AB\u{1b}[31m|CD\u{1b}[39m", s.insert_str_visible(2, "|"));
+        assert_eq!(
+            "AB|\u{1b}[31mCD\u{1b}[39m",
+            s.insert_str_visible_with(2, "|", InsertStyle::Isolate)
+        );
+    }
+
+    #[test]
+    #[should_panic = "index 1 is not on a UTF-8 code point boundary, it's inside '😀' (valid boundaries: 0, 4)"]
+    fn cut_out_a_mid_of_emojie_test() {
+        "😀".cut_out(1..);
+    }
+
+    #[test]
+    fn take_visible_test() {
+        let emojes = "😀😃😄";
+        assert_eq!("", emojes.take_visible(0));
+        assert_eq!("😀", emojes.take_visible(1));
+        assert_eq!(emojes, emojes.take_visible(3));
+        assert_eq!(emojes, emojes.take_visible(100));
+
+        let s = "zxc_\u{1b}[31;40mTEXT\u{1b}[0m_qwe";
+        assert_eq!("zxc", s.take_visible(3));
+        assert_eq!("zxc_\u{1b}[31;40mT\u{1b}[49;39m", s.take_visible(5));
+        assert_eq!(s, s.take_visible(100));
+    }
+
+    #[test]
+    fn skip_visible_test() {
+        let emojes = "😀😃😄";
+        assert_eq!(emojes, emojes.skip_visible(0));
+        assert_eq!("😃😄", emojes.skip_visible(1));
+        assert_eq!("", emojes.skip_visible(3));
+        assert_eq!("", emojes.skip_visible(100));
+
+        let s = "zxc_\u{1b}[31;40mTEXT\u{1b}[0m_qwe";
+        assert_eq!(s, s.skip_visible(0));
+        assert_eq!("xc_\u{1b}[31;40mTEXT\u{1b}[0m_qwe", s.skip_visible(1));
+        assert_eq!("\u{1b}[31;40mTEXT\u{1b}[0m_qwe", s.skip_visible(4));
+        assert_eq!("\u{1b}[31;40mXT\u{1b}[0m_qwe", s.skip_visible(6));
+        assert_eq!("_qwe", s.skip_visible(8));
+        assert_eq!("we", s.skip_visible(10));
+        assert_eq!("", s.skip_visible(12));
+    }
+
+    #[test]
+    fn tail_visible_test() {
+        let emojes = "😀😃😄😁😆";
+        assert_eq!("😁😆", emojes.tail_visible(2));
+        assert_eq!(emojes, emojes.tail_visible(5));
+        assert_eq!(emojes, emojes.tail_visible(100));
+        assert_eq!("", emojes.tail_visible(0));
+        assert_eq!("", "".tail_visible(3));
+
+        let s = "\u{1b}[31;40mTEXT\u{1b}[0m \u{1b}[34mTEXT\u{1b}[39m";
+        assert_eq!("\u{1b}[34mXT\u{1b}[39m", s.tail_visible(2));
+        assert_eq!("\u{1b}[34mTEXT\u{1b}[39m", s.tail_visible(4));
+        assert_eq!(" \u{1b}[34mTEXT\u{1b}[39m", s.tail_visible(5));
+        assert_eq!(
+            "\u{1b}[31;40mT\u{1b}[0m \u{1b}[34mTEXT\u{1b}[39m",
+            s.tail_visible(6)
+        );
+        assert_eq!(s, s.tail_visible(9));
+        assert_eq!(s, s.tail_visible(10));
+        assert_eq!("", s.tail_visible(0));
+
+        let s = "\u{1b}[1m\u{1b}[31mTEXT";
+        assert_eq!("\u{1b}[1;31mXT\u{1b}[39;22m", s.tail_visible(2));
+    }
+
+    #[test]
+    fn cut_chars_test() {
+        let emojes = "😀😃😄😁😆😅😂🤣🥲😊";
+        assert_eq!(emojes, emojes.cut_chars(..));
+        assert_eq!("😀", emojes.cut_chars(..1));
+        assert_eq!("😃", emojes.cut_chars(1..2));
+        assert_eq!("😃😄", emojes.cut_chars(1..=2));
+        assert_eq!("🤣🥲😊", emojes.cut_chars(7..));
+        assert_eq!("🤣🥲😊", emojes.cut_chars(7..100));
+        assert_eq!("", emojes.cut_chars(100..));
+        assert_eq!("", emojes.cut_chars(3..3));
+
+        // a reversed range is empty
+        #[allow(clippy::reversed_empty_ranges)]
+        let reversed = "😀😃😄".cut_chars(2..1);
+        assert_eq!("", reversed);
+
+        let s = "\u{1b}[31;40m😀😃😄\u{1b}[0m";
+        assert_eq!(s, s.cut_chars(..));
+        assert_eq!("\u{1b}[31;40m😃\u{1b}[49;39m", s.cut_chars(1..2));
+        assert_eq!("\u{1b}[31;40m😃😄\u{1b}[0m", s.cut_chars(1..));
+        assert_eq!("\u{1b}[31;40m\u{1b}[49;39m", s.cut_chars(1..1));
+    }
+
+    #[test]
+    fn cut_chars_partially_colored_str_test() {
+        let s = "zxc_\u{1b}[31;40mпривет\u{1b}[0m_qwe";
+        assert_eq!("zxc", s.cut_chars(..3));
+        assert_eq!("zxc_\u{1b}[31;40mп\u{1b}[49;39m", s.cut_chars(..5));
+        assert_eq!("\u{1b}[31;40mрив\u{1b}[49;39m", s.cut_chars(5..8));
+        assert_eq!("\u{1b}[31;40mет\u{1b}[0m_q", s.cut_chars(8..12));
+    }
+
+    #[cfg(feature = "unicode-width")]
+    #[test]
+    fn cut_width_test() {
+        let s = "你好世界";
+        assert_eq!(s, s.cut_width(..));
+        assert_eq!("你好", s.cut_width(..4));
+        assert_eq!("好世", s.cut_width(2..6));
+        assert_eq!("好", s.cut_width(1..5));
+        assert_eq!("你", s.cut_width(..3));
+        assert_eq!("", s.cut_width(..1));
+        assert_eq!("", s.cut_width(1..2));
+        assert_eq!("界", s.cut_width(5..));
+        assert_eq!("界", s.cut_width(6..100));
+        assert_eq!("", s.cut_width(100..));
+
+        assert_eq!(" 好 ", s.cut_width_with(1..5, WideCharPolicy::Pad));
+        assert_eq!("你 ", s.cut_width_with(..3, WideCharPolicy::Pad));
+        assert_eq!(" ", s.cut_width_with(1..2, WideCharPolicy::Pad));
+        assert_eq!(" 界", s.cut_width_with(5..100, WideCharPolicy::Pad));
+        assert_eq!("", s.cut_width_with(100.., WideCharPolicy::Pad));
+
+        // empty ranges
+        assert_eq!("", s.cut_width_with(1..1, WideCharPolicy::Pad));
+        assert_eq!("", s.cut_width_with(5..5, WideCharPolicy::Pad));
+        assert_eq!("", s.cut_width_with(2..2, WideCharPolicy::Pad));
+        assert_eq!("", s.cut_width_with(100..100, WideCharPolicy::Pad));
+        #[allow(clippy::reversed_empty_ranges)]
+        let reversed = s.cut_width_with(5..1, WideCharPolicy::Pad);
+        assert_eq!("", reversed);
+        // a range which starts and ends inside of the same wide char
+        assert_eq!(" ", "你".cut_width_with(0..1, WideCharPolicy::Pad));
+        assert_eq!(" ", "你".cut_width_with(1..2, WideCharPolicy::Pad));
+        assert_eq!(" ", "😀".cut_width_with(1..2, WideCharPolicy::Pad));
+
+        let s = "abc你好";
+        assert_eq!("c你", s.cut_width(2..5));
+        assert_eq!("c", s.cut_width(2..4));
+    }
+
+    #[cfg(feature = "unicode-width")]
+    #[test]
+    fn cut_width_zero_width_chars_test() {
+        let s = "e\u{301}e\u{301}e\u{301}";
+        assert_eq!("e\u{301}", s.cut_width(..1));
+        assert_eq!("e\u{301}", s.cut_width(1..2));
+        assert_eq!("e\u{301}e\u{301}", s.cut_width(1..));
+        assert_eq!("", s.cut_width(3..));
+    }
+
+    #[cfg(feature = "unicode-width")]
+    #[test]
+    fn cut_width_colored_test() {
+        let s = "\u{1b}[31;40m你好\u{1b}[0m \u{1b}[34m世界\u{1b}[39m";
+        assert_eq!(s, s.cut_width(..));
+        assert_eq!("\u{1b}[31;40m你\u{1b}[49;39m", s.cut_width(..2));
+        assert_eq!("\u{1b}[31;40m你\u{1b}[49;39m", s.cut_width(..3));
+        assert_eq!(
+            "\u{1b}[31;40m好\u{1b}[0m \u{1b}[34m世\u{1b}[39m",
+            s.cut_width(1..7)
+        );
+        assert_eq!(
+            " \u{1b}[31;40m好\u{1b}[0m \u{1b}[34m世\u{1b}[39m ",
+            s.cut_width_with(1..8, WideCharPolicy::Pad)
+        );
+    }
+
+    #[cfg(feature = "unicode-segmentation")]
+    #[test]
+    fn cut_graphemes_test() {
+        let s = "👨‍👩‍👧‍👦👍🏽e\u{301}🇺🇦";
+        assert_eq!(s, s.cut_graphemes(..));
+        assert_eq!("👨‍👩‍👧‍👦", s.cut_graphemes(..1));
+        assert_eq!("👍🏽", s.cut_graphemes(1..2));
+        assert_eq!("e\u{301}", s.cut_graphemes(2..3));
+        assert_eq!("🇺🇦", s.cut_graphemes(3..));
+        assert_eq!("👍🏽e\u{301}🇺🇦", s.cut_graphemes(1..100));
+        assert_eq!("", s.cut_graphemes(4..));
+        assert_eq!("", s.cut_graphemes(2..2));
+    }
+
+    #[cfg(feature = "unicode-segmentation")]
+    #[test]
+    fn cut_graphemes_colored_test() {
+        let s = "\u{1b}[31;40m👨‍👩‍👧‍👦👍🏽\u{1b}[0m \u{1b}[34me\u{301}🇺🇦\u{1b}[39m";
+        assert_eq!(s, s.cut_graphemes(..));
+        assert_eq!("\u{1b}[31;40m👨‍👩‍👧‍👦\u{1b}[49;39m", s.cut_graphemes(..1));
+        assert_eq!(
+            "\u{1b}[31;40m👍🏽\u{1b}[0m \u{1b}[34me\u{301}\u{1b}[39m",
+            s.cut_graphemes(1..4)
+        );
+        assert_eq!("\u{1b}[34m🇺🇦\u{1b}[39m", s.cut_graphemes(4..));
+    }
+
+    #[test]
+    fn cut_display_test() {
+        assert_eq!("eth", cut_display("something", 3..6));
+        assert_eq!("123", cut_display(12345, ..3));
+        assert_eq!(
+            "\u{1b}[31;40mEX\u{1b}[49;39m",
+            cut_display(format_args!("\u{1b}[31;40m{}\u{1b}[0m", "TEXT"), 1..3)
+        );
+
+        // a value which is written in pieces splitting escape sequences
+        struct Pieces<'a>(&'a str, usize);
+
+        impl fmt::Display for Pieces<'_> {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                let mut rest = self.0;
+                while !rest.is_empty() {
+                    let mut end = std::cmp::min(self.1, rest.len());
+                    while !rest.is_char_boundary(end) {
+                        end += 1;
+                    }
+
+                    f.write_str(&rest[..end])?;
+                    rest = &rest[end..];
+                }
+
+                Ok(())
+            }
+        }
+
+        let strings = [
+            "\u{1b}[31;40mTEXT\u{1b}[0m \u{1b}[34m😀😃\u{1b}[1m zxc",
+            "\u{1b}]8;;http://example.com\u{1b}\\link\u{1b}]8;;\u{1b}\\ \u{9b}32mgreen\u{9b}0m",
+            "\u{1b}Pq#0\u{1b}\\te\u{1b}\u{1b}[4mxt\u{1b}[24m\u{1b}[3",
+        ];
+        for s in strings {
+            let len = srip_ansi_sequences(s).len();
+            for size in [1, 2, 3, 7, 100] {
+                for i in 0..=len {
+                    for j in i..=len + 1 {
+                        if s.try_cut(i..j).is_ok() {
+                            assert_eq!(
+                                s.cut(i..j),
+                                cut_display(Pieces(s, size), i..j),
+                                "{:?} {} {}..{}",
+                                s,
+                                size,
+                                i,
+                                j
+                            );
+                        }
+                    }
+                }
             }
-            _ => {
-                state.unknown = true;
+        }
+
+        // formatting is stopped after the range
+        struct Endless;
+
+        impl fmt::Display for Endless {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                loop {
+                    f.write_str("\u{1b}[31mTEXT\u{1b}[39m")?;
+                }
             }
         }
 
-        ptr = &ptr[1..];
+        assert_eq!(
+            "\u{1b}[31mXT\u{1b}[39m\u{1b}[31mT\u{1b}[39m",
+            cut_display(Endless, 2..5)
+        );
     }
-}
 
-fn parse_ansi_color(buf: &[u8]) -> Option<(AnsiColor, usize)> {
-    match buf {
-        [b'2', b';', index, ..] => Some((AnsiColor::Bit8 { index: *index }, 3)),
-        [b'5', b';', r, b';', g, b';', b, ..] => Some((
-            AnsiColor::Bit24 {
-                r: *r,
-                g: *g,
-                b: *b,
-            },
-            7,
-        )),
-        _ => None,
+    #[test]
+    #[should_panic]
+    fn cut_display_not_a_char_boundary_test() {
+        cut_display("😀😃", 1..);
     }
-}
 
-fn complete_ansi_sequences(state: &AnsiState, buf: &mut String) {
-    macro_rules! emit_static {
-        ($s:expr) => {
-            buf.push_str(concat!("\u{1b}[", $s, "m"))
-        };
-    }
+    #[test]
+    fn cut_to_test() {
+        let s = "\u{1b}[31;40mTEXT\u{1b}[0m \u{1b}[34m😀😃\u{1b}[1m zxc";
+        for range in [0..4, 1..3, 2..9, 5..13, 9..100, 0..100, 3..3, 100..200] {
+            let mut buf = String::from("|");
+            cut_to(s, range.clone(), &mut buf).unwrap();
+            assert_eq!(format!("|{}", s.cut(range.clone())), buf);
 
-    if state.unknown && state.reset {
-        emit_static!("0");
-    }
+            let mut buf = Vec::new();
+            cut_to_io(s, range.clone(), &mut buf).unwrap();
+            assert_eq!(s.cut(range).as_bytes(), buf);
+        }
+
+        struct FailingWriter;
+
+        impl fmt::Write for FailingWriter {
+            fn write_str(&mut self, _: &str) -> fmt::Result {
+                Err(fmt::Error)
+            }
+        }
+
+        assert_eq!(Err(fmt::Error), cut_to(s, 1..3, &mut FailingWriter));
+        assert_eq!(Ok(()), cut_to("something", 100.., &mut FailingWriter));
 
-    if state.font.is_some() {
-        emit_static!("10");
+        let mut buf = [0; 4];
+        let err = cut_to_io(s, .., &mut buf.as_mut()).unwrap_err();
+        assert_eq!(io::ErrorKind::WriteZero, err.kind());
     }
 
-    if state.bold || state.faint {
-        emit_static!("22");
+    #[test]
+    #[should_panic = "index 1 is not on a UTF-8 code point boundary, it's inside '😀' (valid boundaries: 0, 4)"]
+    fn cut_to_a_mid_of_emojie_test() {
+        cut_to("😀😃", 1..4, &mut String::new()).unwrap();
     }
 
-    if state.italic {
-        emit_static!("23");
+    #[test]
+    fn cut_lazy_test() {
+        let s = "\u{1b}[31;40mTEXT\u{1b}[0m \u{1b}[34m😀😃\u{1b}[1m zxc";
+        for range in [0..4, 1..3, 2..9, 5..13, 9..100, 0..100, 3..3, 100..200] {
+            let cut = s.cut_lazy(range.clone());
+            assert_eq!(s.cut(range.clone()), cut.to_string());
+            assert_eq!(s.cut(range), cut.to_string());
+        }
+
+        assert_eq!(s.cut(2..), s.cut_lazy(2..).to_string());
+        assert_eq!(s.cut(..), String::from(s).cut_lazy(..).to_string());
+        assert_eq!(
+            format!("[{}]", s.cut(1..3)),
+            format!("[{}]", s.cut_lazy(1..3))
+        );
     }
 
-    if state.underline || state.double_underline {
-        emit_static!("24");
+    #[test]
+    fn cut_minimal_test() {
+        // a style of each visible character
+        let s = concat!(
+            "\u{1b}[1mA\u{1b}[31mB\u{1b}[44mC\u{1b}[22mD\u{1b}[0mE",
+            "\u{1b}[3m\u{1b}[4m\u{1b}[32mF\u{1b}[24mG\u{1b}[39m\u{1b}[23mH",
+        );
+        for start in 0..=9 {
+            for end in start..=9 {
+                let cut = s.cut(start..end);
+                let minimal = s.cut_minimal(start..end);
+                assert_eq!(rendered(&cut), rendered(&minimal), "{}..{}", start, end);
+                assert!(minimal.len() <= cut.len());
+                assert_eq!(
+                    unclosed_styles(&cut).to_string(),
+                    unclosed_styles(&minimal).to_string()
+                );
+            }
+        }
+
+        assert_eq!(
+            "\u{1b}[1;31;44mC\u{1b}[22m\u{1b}[49;39m",
+            s.cut_minimal(2..3)
+        );
+        assert_eq!(
+            "\u{1b}[3;4;32mF\u{1b}[24mG\u{1b}[39m\u{1b}[23m",
+            s.cut_minimal(5..7)
+        );
+        assert_eq!("", s.cut_minimal(3..3));
+        assert_eq!("", s.cut_minimal(100..));
+        assert_eq!("H", s.cut_minimal(7..));
+        assert_eq!(s.cut(..), s.cut_minimal(..));
     }
 
-    if state.slow_blink || state.rapid_blink {
-        emit_static!("25");
+    // chars of a string together with their styles
+    pub(crate) fn rendered(s: &str) -> Vec<(char, String)> {
+        let indexed = AnsiIndexedStr::new(s);
+        srip_ansi_sequences(s)
+            .char_indices()
+            .map(|(i, c)| (c, indexed.style_at(i).unwrap().to_string()))
+            .collect()
     }
 
-    if state.inverse {
-        emit_static!("28");
+    #[test]
+    fn cut_prune_escapes_test() {
+        let s = "\u{1b}[31mAB\u{1b}[2J\u{1b}[0m\u{1b}[32mCD\u{1b}[1m\u{1b}[34mEF\u{1b}[0m";
+        assert_eq!("\u{1b}[32mC\u{1b}[39m", s.cut(2..3));
+        assert_eq!("\u{1b}[1m\u{1b}[34mEF\u{1b}[0m", s.cut(4..));
+        assert_eq!("\u{1b}[1m\u{1b}[34mF\u{1b}[0m", s.cut(5..));
+        assert_eq!(s, s.cut(..));
+
+        // a hyperlink is not closed by a reset
+        let s = "\u{1b}]8;;http://a\u{1b}\\\u{1b}[31mAB\u{1b}[0mCD\u{1b}]8;;\u{1b}\\";
+        assert_eq!("\u{1b}]8;;http://a\u{1b}\\CD\u{1b}]8;;\u{1b}\\", s.cut(2..));
+
+        // escapes other than styles after the last reset are kept
+        let s = "\u{1b}[0m\u{1b}[2JAB\u{1b}[1mC";
+        assert_eq!("\u{1b}[2JB\u{1b}[1mC\u{1b}[22m", s.cut(1..));
+
+        let s = "\u{1b}[31mA\u{1b}[0m".repeat(100);
+        assert_eq!("\u{1b}[31mA\u{1b}[0m", s.cut(99..));
+
+        let s = "\u{1b}[31mA\u{1b}[1mB\u{1b}[32mC\u{1b}[22mD\u{1b}[4mE\u{1b}[0mF\u{1b}[44mGH";
+        assert_eq!("\u{1b}[44mGH\u{1b}[49m", s.cut(6..));
+        assert_eq!("\u{1b}[32m\u{1b}[4mE\u{1b}[0m", s.cut(4..5));
+        for start in 1..8 {
+            assert_eq!(rendered(s)[start..], rendered(&s.cut(start..))[..]);
+        }
+
+        assert_eq!(s.cut(3..), s.cut_cow(3..));
+        assert_eq!(Cow::Borrowed("F\u{1b}[44mGH\u{1b}[49m"), s.cut_cow(5..));
     }
 
-    if state.crossedout {
-        emit_static!("29");
+    #[test]
+    fn closers_order_test() {
+        let s = "\u{1b}[1mA\u{1b}[31mB\u{1b}[4mCD";
+        assert_eq!(
+            "\u{1b}[1mA\u{1b}[31mB\u{1b}[4mC\u{1b}[24;39;22m",
+            s.cut(..3)
+        );
+
+        // a style which is set again is the last one to be opened
+        let s = "\u{1b}[31m\u{1b}[1mA\u{1b}[32mBC";
+        assert_eq!("\u{1b}[31m\u{1b}[1mA\u{1b}[32mB\u{1b}[39;22m", s.cut(..2));
+
+        let s = "\u{1b}[31;1mA\u{1b}[39mB\u{1b}[34mCD";
+        assert_eq!("\u{1b}[1;34mC\u{1b}[39;22m", s.cut_minimal(2..3));
+
+        let s = "\u{1b}[31;40mTEXT\u{1b}[0m";
+        let parts = chunks(s, 2);
+        assert_eq!(
+            parts,
+            ["\u{1b}[31;40mTE\u{1b}[49;39m", "\u{1b}[31;40mXT\u{1b}[0m"]
+        );
+        assert_eq!(rendered(s), rendered(&parts.concat()));
+
+        // a closer the source has at the seam goes to the next chunk
+        let s = "\u{1b}[31;40mAB\u{1b}[39mCD";
+        let parts = chunks(s, 2);
+        assert_eq!(
+            parts,
+            ["\u{1b}[31;40mAB\u{1b}[49;39m", "\u{1b}[40mCD\u{1b}[49m"]
+        );
+        assert_eq!(rendered(s), rendered(&parts.concat()));
     }
 
-    if state.fg_color.is_some() {
-        emit_static!("39");
+    #[test]
+    fn cut_with_reset_policy_test() {
+        let options = |reset| CutOptions::default().reset(reset);
+
+        let s = "\u{1b}[31;40mTEXT\u{1b}[0m \u{1b}[1mzxc\u{1b}[22m qwe";
+        for range in [0..4, 0..100, 5..6, 9..12, 3..3, 100..200] {
+            assert_eq!(
+                s.cut(range.clone()),
+                s.cut_with(range, options(ResetPolicy::PerAttribute))
+            );
+        }
+
+        assert_eq!(
+            "\u{1b}[31;40mTE\u{1b}[0m",
+            s.cut_with(..2, options(ResetPolicy::FullReset))
+        );
+        assert_eq!(
+            "\u{1b}[31;40mTE",
+            s.cut_with(..2, options(ResetPolicy::None))
+        );
+        assert_eq!(
+            "\u{1b}[31;40mTE\u{1b}[0m",
+            s.cut_with(..2, options(ResetPolicy::ReuseOriginal))
+        );
+        assert_eq!(
+            " \u{1b}[1mzx\u{1b}[22m",
+            s.cut_with(4..7, options(ResetPolicy::ReuseOriginal))
+        );
+        assert_eq!(
+            "\u{1b}[31;40mTEXT\u{1b}[0m",
+            s.cut_with(..4, options(ResetPolicy::FullReset))
+        );
+        assert_eq!(
+            " \u{1b}[1mzxc\u{1b}[22m q",
+            s.cut_with(4..10, options(ResetPolicy::FullReset))
+        );
+
+        // the following sequence closes only a background, a color is closed after it
+        let s = "\u{1b}[31;40mTEXT\u{1b}[49mzxc";
+        assert_eq!(
+            "\u{1b}[31;40mTE\u{1b}[49m\u{1b}[39m",
+            s.cut_with(..2, options(ResetPolicy::ReuseOriginal))
+        );
+
+        let s = "\u{1b}[1;31;40mTEXT\u{1b}[39;22m more";
+        assert_eq!(
+            "\u{1b}[1;31;40mTE\u{1b}[39;22m\u{1b}[49m",
+            s.cut_with(..2, options(ResetPolicy::ReuseOriginal))
+        );
+        assert_eq!(
+            "\u{1b}[1;31;40mE\u{1b}[39;22m\u{1b}[49m",
+            s.cut_with(1..2, options(ResetPolicy::ReuseOriginal).minimal(true))
+        );
+
+        // the following sequence opens a style
+        let s = "\u{1b}[31;40mTEXT\u{1b}[49;32mzxc";
+        assert_eq!(
+            "\u{1b}[31;40mTE\u{1b}[49;39m",
+            s.cut_with(..2, options(ResetPolicy::ReuseOriginal))
+        );
+
+        // the following sequence has nothing to do with styles
+        let s = "\u{1b}[31;40mTEXT\u{1b}]0;title\u{7}zxc";
+        assert_eq!(
+            "\u{1b}[31;40mTE\u{1b}[49;39m",
+            s.cut_with(..2, options(ResetPolicy::ReuseOriginal))
+        );
+
+        // nothing follows the cut
+        let s = "\u{1b}[31;40mTEXT";
+        assert_eq!(
+            "\u{1b}[31;40mTE\u{1b}[49;39m",
+            s.cut_with(..2, options(ResetPolicy::ReuseOriginal))
+        );
+
+        let minimal = CutOptions::default().minimal(true);
+        assert_eq!(
+            "\u{1b}[1mzx\u{1b}[0m",
+            "\u{1b}[31;40mTEXT\u{1b}[0m \u{1b}[1mzxc\u{1b}[22m qwe"
+                .cut_with(5..7, minimal.reset(ResetPolicy::FullReset))
+        );
+
+        // all styles are closed by a single sequence
+        let s = "\u{1b}[1;3;4;31;40;9;7mTEXT\u{1b}[0m";
+        assert_eq!(
+            "\u{1b}[1;3;4;31;40;9;7mTE\u{1b}[27;29;49;39;24;23;22m",
+            s.cut(..2)
+        );
+        assert_eq!(
+            "\u{1b}[1;3;4;31;40;9;7mTE\u{1b}[27;29;49m\u{1b}[39;24;23m\u{1b}[22m",
+            s.cut_with(..2, options(ResetPolicy::ParserCompatible))
+        );
+        assert_eq!(
+            "\u{1b}[4;31mTE\u{1b}[39;24m",
+            "\u{1b}[4;31mTEXT".cut_with(..2, options(ResetPolicy::ParserCompatible))
+        );
+        assert_eq!(
+            "\u{1b}[1;3;4;31mTE\u{1b}[39;24;23;22m",
+            "\u{1b}[1;3;4;31mTEXT".cut(..2)
+        );
+        assert_eq!(
+            "\u{1b}[1;3;4;31mTE\u{1b}[39;24;23m\u{1b}[22m",
+            "\u{1b}[1;3;4;31mTEXT".cut_with(..2, options(ResetPolicy::ParserCompatible))
+        );
     }
 
-    if state.bg_color.is_some() {
-        emit_static!("49");
+    #[test]
+    fn cut_open_test() {
+        let s = "\u{1b}[31;40mTEXT\u{1b}[0m \u{1b}[1mzxc\u{1b}[22m qwe";
+
+        let (cut, style) = s.cut_open(..2);
+        assert_eq!("\u{1b}[31;40mTE", cut);
+        assert_eq!("\u{1b}[31;40m", style.to_string());
+
+        let (cut, style) = s.cut_open(..4);
+        assert_eq!("\u{1b}[31;40mTEXT\u{1b}[0m", cut);
+        assert_eq!("", style.to_string());
+
+        let (cut, style) = s.cut_open(5..7);
+        assert_eq!("\u{1b}[1mzx", cut);
+        assert_eq!("\u{1b}[1m", style.to_string());
+
+        let (cut, style) = s.cut_open(..);
+        assert_eq!(s, cut);
+        assert_eq!("", style.to_string());
+
+        // pieces joined together are rendered as the original string
+        let (head, _) = s.cut_open(..6);
+        let tail = s.cut_minimal(6..);
+        assert_eq!(
+            "\u{1b}[31;40mTEXT\u{1b}[0m \u{1b}[1mz\u{1b}[1mxc\u{1b}[22m qwe",
+            head + &tail
+        );
     }
 
-    if state.proportional_spacing {
-        emit_static!("50");
+    #[test]
+    fn cut_strict_test() {
+        let s = "\u{1b}[40mTEXT\u{1b}[49m";
+        assert_eq!("", s.cut_strict(3..3));
+        assert_eq!("", s.cut_strict(0..0));
+        assert_eq!("", s.cut_strict(4..));
+        assert_eq!("", s.cut_strict(100..));
+        assert_eq!(s, s.cut_strict(..));
+        assert_eq!("\u{1b}[40mEX\u{1b}[49m", s.cut_strict(1..3));
+        assert_eq!("\u{1b}[40mT\u{1b}[49m", s.cut_strict(3..));
+
+        let s = "\u{1b}[31;40mTEXT\u{1b}[0m \u{1b}[34;42mTEXT\u{1b}[0m";
+        assert_eq!("", s.cut_strict(1..1));
+        assert_eq!("", s.cut_strict(4..4));
+        assert_eq!("", s.cut_strict(9..));
+        assert_eq!(s.cut(4..5), s.cut_strict(4..5));
+        assert_eq!(s.cut(2..7), s.cut_strict(2..7));
+
+        let options = CutOptions::default().strict(true);
+        assert_eq!("", s.cut_with(1..1, options.minimal(true)));
+        assert_eq!(
+            "\u{1b}[34;42mE\u{1b}[0m",
+            s.cut_with(6..7, options.minimal(true).reset(ResetPolicy::FullReset))
+        );
+
+        let s = "zxc_\u{1b}[31;40mTEXT\u{1b}[0m_qwe";
+        assert_eq!("\u{1b}[31;40m\u{1b}[49;39m", s.cut(5..5));
+        assert_eq!("", s.cut_strict(5..5));
     }
 
-    if state.encircled || state.framed {
-        emit_static!("54");
+    #[test]
+    fn cut_many_test() {
+        let s = "\u{1b}[31;40mTEXT\u{1b}[0m \u{1b}[34m😀😃\u{1b}[39m zxc";
+        let ranges = [
+            0..4,
+            1..3,
+            2..9,
+            5..13,
+            9..100,
+            0..100,
+            3..3,
+            13..16,
+            100..200,
+        ];
+        let expected = ranges.iter().map(|r| s.cut(r.clone())).collect::<Vec<_>>();
+        assert_eq!(expected, cut_many(s, &ranges));
+
+        let mut reversed = ranges.to_vec();
+        reversed.reverse();
+        let mut expected = expected;
+        expected.reverse();
+        assert_eq!(expected, cut_many(s, &reversed));
+
+        assert_eq!(Vec::<String>::new(), cut_many(s, &[]));
+        assert_eq!(vec!["", ""], cut_many("", &[0..1, 1..3]));
     }
 
-    if state.overlined {
-        emit_static!("55");
+    #[test]
+    #[should_panic = "index 1 is not on a UTF-8 code point boundary, it's inside '😀' (valid boundaries: 0, 4)"]
+    fn cut_many_a_mid_of_emojie_test() {
+        cut_many("😀😃", &[0..4, 1..4]);
     }
 
-    if state.igrm_underline
-        || state.igrm_double_underline
-        || state.igrm_overline
-        || state.igrm_double_overline
-        || state.igrm_stress_marking
-    {
-        emit_static!("65");
+    #[test]
+    fn indexed_str_cut_test() {
+        let strings = [
+            "",
+            "something",
+            "\u{1b}[31;40mTEXT\u{1b}[0m \u{1b}[34m😀😃\u{1b}[39m zxc",
+            "\u{1b}[1m\u{1b}[31mAB\u{1b}[0m\u{1b}[0m\u{1b}[32m\u{1b}[0mC\u{1b}[33m",
+            "\u{1b}[41mx\u{1b}[49m",
+        ];
+
+        for s in strings {
+            let indexed = AnsiIndexedStr::new(s);
+            assert_eq!(s, indexed.as_str());
+
+            let stripped = srip_ansi_sequences(s);
+            let mut indexes = stripped.char_indices().map(|(i, _)| i).collect::<Vec<_>>();
+            indexes.extend([stripped.len(), stripped.len() + 1]);
+
+            for &start in &indexes {
+                assert_eq!(s.cut(start..), indexed.cut(start..));
+                assert_eq!(s.cut(..start), indexed.cut(..start));
+
+                for &end in indexes.iter().filter(|&&end| end >= start) {
+                    assert_eq!(s.cut(start..end), indexed.cut(start..end));
+                }
+            }
+
+            for start in 0..8 {
+                for end in start..8 {
+                    assert_eq!(s.cut_chars(start..end), indexed.cut_chars(start..end));
+                }
+            }
+        }
     }
 
-    if state.undr_color.is_some() {
-        emit_static!("59");
+    #[test]
+    #[should_panic = "index 1 is not on a UTF-8 code point boundary, it's inside '😀' (valid boundaries: 0, 4)"]
+    fn indexed_str_cut_a_mid_of_emojie_test() {
+        AnsiIndexedStr::new("😀😃").cut(1..4);
     }
 
-    if state.subscript || state.superscript {
-        emit_static!("75");
+    #[cfg(feature = "unicode-width")]
+    #[test]
+    fn indexed_str_cut_width_test() {
+        let strings = [
+            "你好世界",
+            "\u{1b}[31mab\u{1b}[32mc你\u{1b}[0m好e\u{301}\u{1b}[1m\u{301}世界",
+        ];
+
+        for s in strings {
+            let indexed = AnsiIndexedStr::new(s);
+            for start in 0..12 {
+                assert_eq!(s.cut_width(start..), indexed.cut_width(start..));
+                for end in 0..12 {
+                    assert_eq!(s.cut_width(start..end), indexed.cut_width(start..end));
+                }
+            }
+        }
     }
 
-    if state.unknown {
-        emit_static!("0");
+    #[test]
+    fn indexed_str_style_at_test() {
+        let s = "\u{1b}[31;40mTEXT\u{1b}[0m \u{1b}[34m😀😃\u{1b}[1m zxc";
+        let indexed = AnsiIndexedStr::new(s);
+        let styles = (0..20)
+            .map(|i| indexed.style_at(i).map(|style| style.to_string()))
+            .collect::<Vec<_>>();
+
+        assert_eq!(Some("\u{1b}[31;40m"), styles[0].as_deref());
+        assert_eq!(Some("\u{1b}[31;40m"), styles[3].as_deref());
+        assert_eq!(Some(""), styles[4].as_deref());
+        assert_eq!(Some("\u{1b}[34m"), styles[5].as_deref());
+        assert_eq!(Some("\u{1b}[34m"), styles[12].as_deref());
+        assert_eq!(Some("\u{1b}[1;34m"), styles[13].as_deref());
+        assert_eq!(Some("\u{1b}[1;34m"), styles[16].as_deref());
+        assert_eq!(None, styles[17]);
+        assert_eq!(
+            None,
+            AnsiIndexedStr::new("").style_at(0).map(|s| s.to_string())
+        );
     }
-}
 
-fn bounds_to_usize(left: Bound<&usize>, right: Bound<&usize>) -> (usize, Option<usize>) {
-    match (left, right) {
-        (Bound::Included(x), Bound::Included(y)) => (*x, Some(y + 1)),
-        (Bound::Included(x), Bound::Excluded(y)) => (*x, Some(*y)),
-        (Bound::Included(x), Bound::Unbounded) => (*x, None),
-        (Bound::Unbounded, Bound::Unbounded) => (0, None),
-        (Bound::Unbounded, Bound::Included(y)) => (0, Some(y + 1)),
-        (Bound::Unbounded, Bound::Excluded(y)) => (0, Some(*y)),
-        (Bound::Excluded(_), Bound::Unbounded)
-        | (Bound::Excluded(_), Bound::Included(_))
-        | (Bound::Excluded(_), Bound::Excluded(_)) => {
-            unreachable!("A start bound can't be excluded")
+    #[test]
+    fn lines_test() {
+        let lines = |s| super::lines(s).collect::<Vec<_>>();
+
+        assert_eq!(Vec::<String>::new(), lines(""));
+        assert_eq!(vec!["some", "", "thing"], lines("some\n\nthing"));
+        assert_eq!(vec!["some", "thing"], lines("some\r\nthing\r\n"));
+        assert_eq!(vec!["", ""], lines("\n\n"));
+
+        let s = "\u{1b}[31mwhen\nthe night\u{1b}[1m\nhas\u{1b}[39m come\nand\u{1b}[0m the";
+        assert_eq!(
+            vec![
+                "\u{1b}[31mwhen\u{1b}[39m",
+                "\u{1b}[31mthe night\u{1b}[1m\u{1b}[22;39m",
+                "\u{1b}[1;31mhas\u{1b}[39m come\u{1b}[22m",
+                "\u{1b}[1mand\u{1b}[0m the",
+            ],
+            lines(s)
+        );
+
+        // escapes right at line boundaries
+        let s = "\u{1b}[31mred\n\u{1b}[0mplain\n\u{1b}[34m\nblue\u{1b}[39m\n\u{1b}[0m";
+        assert_eq!(
+            vec![
+                "\u{1b}[31mred\u{1b}[39m",
+                "plain",
+                "",
+                "\u{1b}[34mblue\u{1b}[39m"
+            ],
+            lines(s)
+        );
+        assert_eq!(
+            vec!["\u{1b}[31mred\u{1b}[39m", "\u{1b}[31mtext\u{1b}[0m"],
+            lines("\u{1b}[31mred\r\ntext\u{1b}[0m\r\n")
+        );
+
+        for line in lines("\u{1b}[31;40m😀\n😃\u{1b}[1m\r\nzx\u{1b}[0mc\n") {
+            assert!(unclosed_styles(&line).is_empty(), "{:?}", line);
         }
     }
-}
 
-fn srip_ansi_sequences(string: &str) -> String {
-    let tokens = string.ansi_parse();
-    let mut buf = String::new();
-    for token in tokens {
-        match token {
-            Output::TextBlock(text) => {
-                buf.push_str(text);
-            }
-            Output::Escape(_) => {}
-        }
+    #[test]
+    fn lines_with_endings_test() {
+        let lines = |s| super::lines_with_endings(s).collect::<Vec<_>>();
+
+        assert_eq!(Vec::<(String, LineEnding)>::new(), lines(""));
+        assert_eq!(
+            vec![
+                ("some".to_string(), LineEnding::CrLf),
+                ("".to_string(), LineEnding::Lf),
+                ("thing".to_string(), LineEnding::None),
+            ],
+            lines("some\r\n\nthing")
+        );
+        assert_eq!(
+            vec![("some\r\u{1b}[31mx\u{1b}[39m".to_string(), LineEnding::Lf)],
+            lines("some\r\u{1b}[31mx\n")
+        );
+
+        // escapes between `\r` and `\n` go to the next line
+        assert_eq!(
+            vec![
+                ("\u{1b}[31mred\u{1b}[39m".to_string(), LineEnding::CrLf),
+                ("plain".to_string(), LineEnding::CrLf),
+                (
+                    "\u{1b}[34m\u{1b}[2Jblue\u{1b}[39m".to_string(),
+                    LineEnding::None
+                ),
+            ],
+            lines("\u{1b}[31mred\r\u{1b}[0m\nplain\r\u{1b}[34m\u{1b}[2J\nblue\u{1b}[39m")
+        );
+        assert_eq!(
+            vec![("\u{1b}[31mred\u{1b}[39m".to_string(), LineEnding::CrLf)],
+            lines("\u{1b}[31mred\r\u{1b}[0m\n")
+        );
+
+        let s = "\u{1b}[31;40m😀\r\u{1b}[1m\n😃\n\nzx\u{1b}[0mc\r\n\r\u{1b}[4m\nend";
+        let joined = super::lines_with_endings(s)
+            .map(|(line, ending)| line + ending.as_str())
+            .collect::<String>();
+        assert_eq!(srip_ansi_sequences(s), srip_ansi_sequences(&joined));
     }
 
-    buf
-}
+    #[cfg(feature = "unicode-width")]
+    #[test]
+    fn resolve_cr_wide_chars_test() {
+        assert_eq!("X 好", resolve_cr("你好\rX"));
+        assert_eq!("XY好", resolve_cr("你好\rXY"));
+        assert_eq!("aX 好", resolve_cr("a你好\raX"));
+        assert_eq!("你c", resolve_cr("abc\r你"));
+        assert_eq!("a你 ", resolve_cr("你好\ra你"));
+        assert_eq!("ex好", resolve_cr("你好\re\u{301}x\re"));
+        assert_eq!("e\u{301}x好", resolve_cr("你好\re\u{301}x"));
+        assert_eq!(
+            "\u{1b}[31mX\u{1b}[39m\u{1b}[44m 好\u{1b}[49m",
+            resolve_cr("\u{1b}[44m你好\u{1b}[49m\r\u{1b}[31mX\u{1b}[39m")
+        );
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+        let str_width = |s: &str| {
+            srip_ansi_sequences(s)
+                .chars()
+                .map(char_width)
+                .sum::<usize>()
+        };
+        for s in ["你好\rX", "a你好\raX", "😀😃\r😄x", "ab你\rxyz"] {
+            let before = s.split('\r').map(str_width).max().unwrap();
+            assert_eq!(before, str_width(&resolve_cr(s)), "{:?}", s);
+        }
+    }
 
     #[test]
-    fn parse_ansi_color_test() {
-        let tests: Vec<(&[u8], _)> = vec![
-            (&[b'2', b';', 200], Some(AnsiColor::Bit8 { index: 200 })),
-            (
-                &[b'2', b';', 100, b';', 123, b';', 39],
-                Some(AnsiColor::Bit8 { index: 100 }),
-            ),
-            (
-                &[b'2', b';', 100, 1, 2, 3],
-                Some(AnsiColor::Bit8 { index: 100 }),
-            ),
-            (&[b'2', b';'], None),
-            (&[b'2', 1, 2, 3], None),
-            (&[b'2'], None),
-            (
-                &[b'5', b';', 100, b';', 123, b';', 39],
-                Some(AnsiColor::Bit24 {
-                    r: 100,
-                    g: 123,
-                    b: 39,
-                }),
-            ),
-            (
-                &[b'5', b';', 100, b';', 123, b';', 39, 1, 2, 3],
-                Some(AnsiColor::Bit24 {
-                    r: 100,
-                    g: 123,
-                    b: 39,
-                }),
-            ),
-            (
-                &[b'5', b';', 100, b';', 123, b';', 39, 1, 2, 3],
-                Some(AnsiColor::Bit24 {
-                    r: 100,
-                    g: 123,
-                    b: 39,
-                }),
-            ),
-            (&[b'5', b';', 100, b';', 123, b';'], None),
-            (&[b'5', b';', 100, b';', 123], None),
-            (&[b'5', b';', 100, b';'], None),
-            (&[b'5', b';', 100], None),
-            (&[b'5', b';'], None),
-            (&[b'5'], None),
-            (&[], None),
-        ];
+    fn resolve_cr_test() {
+        assert_eq!("", resolve_cr(""));
+        assert_eq!("abc", resolve_cr("abc"));
+        assert_eq!(
+            "done        55%",
+            resolve_cr("downloading 10%\rdownloading 55%\rdone        ")
+        );
+        assert_eq!("xyc", resolve_cr("abc\rxy"));
+        assert_eq!("abc", resolve_cr("abc\r"));
+        assert_eq!("abc", resolve_cr("\rabc"));
+        assert_eq!("zyc", resolve_cr("abc\rxy\rz"));
+        assert_eq!("ab\r\ncd\nef", resolve_cr("ab\r\ncd\nef"));
+        assert_eq!("xb\r\nzd", resolve_cr("ab\rx\r\ncd\rz"));
+
+        // styles of the overwriting text win
+        let s = "\u{1b}[31mabcd\u{1b}[39m\r\u{1b}[44mxy\u{1b}[49m";
+        assert_eq!(
+            "\u{1b}[44mxy\u{1b}[49m\u{1b}[31mcd\u{1b}[39m",
+            resolve_cr(s)
+        );
+        assert_eq!(rendered(&resolve_cr(s))[2..], rendered(s)[2..4]);
+
+        // a style which is open at \r continues
+        let s = "\u{1b}[1mabcd\rxy\u{1b}[22m";
+        assert_eq!("\u{1b}[1mxycd\u{1b}[22m", resolve_cr(s));
+
+        // a style open across lines is reopened
+        let s = "\u{1b}[31mab\nc\rd\u{1b}[39m";
+        assert_eq!(
+            "\u{1b}[31mab\u{1b}[39m\n\u{1b}[31md\u{1b}[39m",
+            resolve_cr(s)
+        );
 
-        for (i, (bytes, expected)) in tests.into_iter().enumerate() {
-            assert_eq!(parse_ansi_color(bytes).map(|a| a.0), expected, "test={}", i);
-        }
+        let s = resolve_cr("\u{1b}[31mloading 10%\r\u{1b}[32mdone\u{1b}[39m");
+        assert_eq!("doneing 10%", srip_ansi_sequences(&s));
+        assert_eq!("\u{1b}[32mdone\u{1b}[31ming 10%\u{1b}[39m", s);
     }
 
     #[test]
-    fn cut_colored_fg_test() {
-        let colored_s = "\u{1b}[30mTEXT\u{1b}[39m";
-        assert_eq!(colored_s, colored_s.cut(..));
-        assert_eq!(colored_s, colored_s.cut(0..4));
-        assert_eq!("\u{1b}[30mEXT\u{1b}[39m", colored_s.cut(1..));
-        assert_eq!("\u{1b}[30mTEX\u{1b}[39m", colored_s.cut(..3));
-        assert_eq!("\u{1b}[30mEX\u{1b}[39m", colored_s.cut(1..3));
+    fn convert_overstrike_test() {
+        assert_eq!("", convert_overstrike(""));
+        assert_eq!("abc", convert_overstrike("abc"));
+        assert_eq!(
+            "\u{1b}[1mab\u{1b}[22mc",
+            convert_overstrike("a\x08ab\x08bc")
+        );
+        assert_eq!(
+            "\u{1b}[4mab\u{1b}[24mc",
+            convert_overstrike("_\x08a_\x08bc")
+        );
+        assert_eq!("\u{1b}[4ma\u{1b}[24m", convert_overstrike("a\x08_"));
+        assert_eq!("\u{1b}[1m_\u{1b}[22m", convert_overstrike("_\x08_"));
+        assert_eq!(
+            "\u{1b}[1ma\u{1b}[4mb\u{1b}[24m\u{1b}[22m",
+            convert_overstrike("a\x08a_\x08b\x08b")
+        );
+        assert_eq!(
+            "\u{1b}[4ma\u{1b}[1mb\u{1b}[24m\u{1b}[22m",
+            convert_overstrike("_\x08a_\x08b\x08b")
+        );
+        assert_eq!(
+            "\u{1b}[1mab\u{1b}[22m\n\u{1b}[1mc\u{1b}[22m",
+            convert_overstrike("a\x08ab\x08b\nc\x08c")
+        );
 
-        assert_eq!("TEXT", srip_ansi_sequences(&colored_s.cut(..)));
-        assert_eq!("TEX", srip_ansi_sequences(&colored_s.cut(..3)));
-        assert_eq!("EX", srip_ansi_sequences(&colored_s.cut(1..3)));
+        // stray backspaces
+        assert_eq!("b", convert_overstrike("a\x08b"));
+        assert_eq!("ab", convert_overstrike("\x08ab\x08"));
+        assert_eq!("a\nb", convert_overstrike("a\n\x08b"));
+        assert_eq!("a\n", convert_overstrike("a\x08\n"));
+        assert_eq!("\u{1b}[1ma\u{1b}[22m", convert_overstrike("a\x08\x08a"));
 
-        let colored_s = "\u{1b}[30mTEXT\u{1b}[39m \u{1b}[31mTEXT\u{1b}[39m";
-        assert_eq!(colored_s, colored_s.cut(..));
-        assert_eq!(colored_s, colored_s.cut(0..9));
+        // escape sequences are kept
         assert_eq!(
-            "\u{1b}[30mXT\u{1b}[39m \u{1b}[31mTEXT\u{1b}[39m",
-            colored_s.cut(2..)
+            "\u{1b}[31m\u{1b}[1mab\u{1b}[39m\u{1b}[22m",
+            convert_overstrike("\u{1b}[31ma\x08ab\x08b\u{1b}[39m")
         );
+
+        // styles which are closed by escape sequences are not closed again
+        assert_eq!("\u{1b}[1ma\u{1b}[0m", convert_overstrike("a\x08a\u{1b}[0m"));
         assert_eq!(
-            "\u{1b}[30mTEXT\u{1b}[39m \u{1b}[31mT\u{1b}[39m",
-            colored_s.cut(..6)
+            "\u{1b}[1m\u{1b}[4ma\u{1b}[22m\u{1b}[24m",
+            convert_overstrike("_\x08a\x08a\u{1b}[22m")
         );
         assert_eq!(
-            "\u{1b}[30mXT\u{1b}[39m \u{1b}[31mT\u{1b}[39m",
-            colored_s.cut(2..6)
+            "\u{1b}[1ma\u{1b}[0m\u{1b}[1mb\u{1b}[22m",
+            convert_overstrike("a\x08a\u{1b}[0mb\x08b")
         );
 
-        assert_eq!("TEXT TEXT", srip_ansi_sequences(&colored_s.cut(..)));
-        assert_eq!("TEXT T", srip_ansi_sequences(&colored_s.cut(..6)));
-        assert_eq!("XT T", srip_ansi_sequences(&colored_s.cut(2..6)));
+        let s = convert_overstrike("N\x08NA\x08AM\x08ME\x08E ls");
+        assert_eq!("\u{1b}[1mAM\u{1b}[22m", s.cut(1..3));
+        assert_eq!(7, srip_ansi_sequences(&s).chars().count());
+    }
 
-        assert_eq!("\u{1b}[30m\u{1b}[39m", cut("\u{1b}[30m\u{1b}[39m", ..));
+    #[test]
+    fn cut_plain_test() {
+        let s = "你好 😀 e\u{301}\ttext";
+        for i in 0..=s.len() + 1 {
+            for j in i..=s.len() + 1 {
+                let plain = s.try_cut(i..j).map_err(|err| err.to_string());
+                // an escape sequence makes it take the general way
+                let general = format!("{}\u{1b}[0m", s)
+                    .try_cut(i..j)
+                    .map(|s| strip(&s).into_owned())
+                    .map_err(|err| err.to_string());
+                assert_eq!(plain, general, "{}..{}", i, j);
+            }
+        }
     }
 
     #[test]
-    fn cut_colored_bg_test() {
-        let colored_s = "\u{1b}[40mTEXT\u{1b}[49m";
-        assert_eq!(colored_s, colored_s.cut(..));
-        assert_eq!(colored_s, colored_s.cut(0..4));
-        assert_eq!("\u{1b}[40mEXT\u{1b}[49m", colored_s.cut(1..));
-        assert_eq!("\u{1b}[40mTEX\u{1b}[49m", colored_s.cut(..3));
-        assert_eq!("\u{1b}[40mEX\u{1b}[49m", colored_s.cut(1..3));
+    fn measure_test() {
+        let strings = [
+            "",
+            "something",
+            "\n",
+            "a\n",
+            "a\r\n\nb",
+            "\u{1b}[31m\u{1b}[0m",
+            "\u{1b}[31;40mTEXT\u{1b}[0m \u{1b}[34m😀😃\u{1b}[39m zxc",
+            "\u{1b}[1m\u{1b}[31m你b\u{1b}[4m\u{1b}[44mcd\u{1b}[0m\n\u{1b}[32m好f\u{1b}[39m\u{1b}[22m",
+            "👩\u{200d}💻 e\u{301}\t\u{1b}]8;;http://a\u{1b}\\link\u{1b}]8;;\u{1b}\\\n世界\n",
+        ];
 
-        // todo: determine if this is the right behaviour
-        assert_eq!("\u{1b}[40m\u{1b}[49m", colored_s.cut(3..3));
+        for s in strings {
+            let stripped = srip_ansi_sequences(s);
+            let metrics = measure(s);
+            assert_eq!(stripped.len(), metrics.bytes);
+            assert_eq!(stripped.chars().count(), metrics.chars);
+            assert_eq!(stripped.lines().count(), metrics.lines);
+            assert_eq!(stripped.len(), visible_len(s));
+            assert_eq!(stripped.chars().count(), visible_chars(s));
 
-        assert_eq!("TEXT", srip_ansi_sequences(&colored_s.cut(..)));
-        assert_eq!("TEX", srip_ansi_sequences(&colored_s.cut(..3)));
-        assert_eq!("EX", srip_ansi_sequences(&colored_s.cut(1..3)));
+            #[cfg(feature = "unicode-width")]
+            {
+                let width = |s: &str| s.chars().map(char_width).sum::<usize>();
+                assert_eq!(width(&stripped), metrics.width);
+                assert_eq!(width(&stripped), visible_width(s));
+                assert_eq!(
+                    stripped.lines().map(width).max().unwrap_or(0),
+                    metrics.max_line_width
+                );
+            }
+        }
 
-        let colored_s = "\u{1b}[40mTEXT\u{1b}[49m \u{1b}[41mTEXT\u{1b}[49m";
-        assert_eq!(colored_s, colored_s.cut(..));
-        assert_eq!(colored_s, colored_s.cut(0..9));
+        #[cfg(feature = "unicode-width")]
+        {
+            let metrics = measure("\u{1b}[31mab\u{1b}[0m\n你好世界\n😀");
+            assert_eq!(12, metrics.width);
+            assert_eq!(8, metrics.max_line_width);
+        }
+    }
+
+    #[test]
+    fn ansi_repeat_test() {
+        assert_eq!("", ansi_repeat("", 3));
+        assert_eq!("", ansi_repeat("ab", 0));
+        assert_eq!("", ansi_repeat("\u{1b}[31m\u{1b}[0m", 3));
+        assert_eq!("ababab", ansi_repeat("ab", 3));
         assert_eq!(
-            "\u{1b}[40mXT\u{1b}[49m \u{1b}[41mTEXT\u{1b}[49m",
-            colored_s.cut(2..)
+            "\u{1b}[2m───\u{1b}[22m",
+            ansi_repeat("\u{1b}[2m─\u{1b}[0m", 3)
         );
+        assert_eq!("\u{1b}[2m───\u{1b}[22m", ansi_repeat("\u{1b}[2m─", 3));
         assert_eq!(
-            "\u{1b}[40mTEXT\u{1b}[49m \u{1b}[41mT\u{1b}[49m",
-            colored_s.cut(..6)
+            "\u{1b}[1;31m────\u{1b}[22;39m",
+            ansi_repeat("\u{1b}[31m\u{1b}[1m─\u{1b}[31m─\u{1b}[0m", 2)
         );
+
+        // the style changes within the string
+        let s = "\u{1b}[31ma\u{1b}[1mb";
         assert_eq!(
-            "\u{1b}[40mXT\u{1b}[49m \u{1b}[41mT\u{1b}[49m",
-            colored_s.cut(2..6)
+            "\u{1b}[31ma\u{1b}[1mb\u{1b}[22;39m\u{1b}[31ma\u{1b}[1mb\u{1b}[22;39m",
+            ansi_repeat(s, 2)
         );
+        let s = "a\u{1b}[31mb\u{1b}[0m";
+        assert_eq!(s.repeat(3), ansi_repeat(s, 3));
+        let s = "\u{1b}[31mab\u{1b}[2Jc\u{1b}[0m";
+        assert_eq!(s.repeat(2), ansi_repeat(s, 2));
 
-        assert_eq!("TEXT TEXT", srip_ansi_sequences(&colored_s.cut(..)));
-        assert_eq!("TEXT T", srip_ansi_sequences(&colored_s.cut(..6)));
-        assert_eq!("XT T", srip_ansi_sequences(&colored_s.cut(2..6)));
-
-        assert_eq!("\u{1b}[40m\u{1b}[49m", cut("\u{1b}[40m\u{1b}[49m", ..));
+        for s in [
+            "\u{1b}[2m─\u{1b}[0m",
+            "\u{1b}[31;40mab\u{1b}[1mc\u{1b}[0m d",
+            "\u{1b}[44m😀\u{1b}[49m",
+        ] {
+            let repeated = ansi_repeat(s, 5);
+            assert_eq!(rendered(&s.repeat(5)), rendered(&repeated));
+            assert!(repeated.len() <= s.repeat(5).len());
+        }
     }
 
     #[test]
-    fn cut_colored_bg_fg_test() {
-        let colored_s = "\u{1b}[31;40mTEXT\u{1b}[0m";
-        assert_eq!(colored_s, colored_s.cut(..));
-        assert_eq!(colored_s, colored_s.cut(0..4));
-        assert_eq!("\u{1b}[31;40mEXT\u{1b}[0m", colored_s.cut(1..));
-        assert_eq!("\u{1b}[31;40mTEX\u{1b}[39m\u{1b}[49m", colored_s.cut(..3));
-        assert_eq!("\u{1b}[31;40mEX\u{1b}[39m\u{1b}[49m", colored_s.cut(1..3));
+    fn fill_line_test() {
+        assert_eq!("", fill_line("", FillMode::EraseToEol));
+        assert_eq!("some", fill_line("some", FillMode::EraseToEol));
 
-        assert_eq!("TEXT", srip_ansi_sequences(&colored_s.cut(..)));
-        assert_eq!("TEX", srip_ansi_sequences(&colored_s.cut(..3)));
-        assert_eq!("EX", srip_ansi_sequences(&colored_s.cut(1..3)));
-
-        let colored_s = "\u{1b}[31;40mTEXT\u{1b}[0m \u{1b}[34;42mTEXT\u{1b}[0m";
-        assert_eq!(colored_s, colored_s.cut(..));
-        assert_eq!(colored_s, colored_s.cut(0..9));
-        assert_eq!(
-            "\u{1b}[31;40mXT\u{1b}[0m \u{1b}[34;42mTEXT\u{1b}[0m",
-            colored_s.cut(2..)
-        );
+        let s = "\u{1b}[31mred\u{1b}[0m";
+        assert_eq!(s, fill_line(s, FillMode::EraseToEol));
+        let s = "\u{1b}[44mblue\u{1b}[0m plain";
+        assert_eq!(s, fill_line(s, FillMode::EraseToEol));
+        let s = "\u{1b}[31mred \u{1b}[44mblue\u{1b}[1m\u{1b}[0m";
         assert_eq!(
-            "\u{1b}[31;40mTEXT\u{1b}[0m \u{1b}[34;42mT\u{1b}[39m\u{1b}[49m",
-            colored_s.cut(..6)
+            "\u{1b}[31mred \u{1b}[44mblue\u{1b}[K\u{1b}[1m\u{1b}[0m",
+            fill_line(s, FillMode::EraseToEol)
         );
+        let s = "\u{1b}[44mblue";
+        assert_eq!("\u{1b}[44mblue\u{1b}[K", fill_line(s, FillMode::EraseToEol));
         assert_eq!(
-            "\u{1b}[31;40mXT\u{1b}[0m \u{1b}[34;42mT\u{1b}[39m\u{1b}[49m",
-            colored_s.cut(2..6)
+            srip_ansi_sequences(s),
+            srip_ansi_sequences(&fill_line(s, FillMode::EraseToEol))
         );
+    }
 
-        assert_eq!("TEXT TEXT", srip_ansi_sequences(&colored_s.cut(..)));
-        assert_eq!("TEXT T", srip_ansi_sequences(&colored_s.cut(..6)));
-        assert_eq!("XT T", srip_ansi_sequences(&colored_s.cut(2..6)));
+    #[cfg(feature = "unicode-width")]
+    #[test]
+    fn fill_line_pad_spaces_test() {
+        assert_eq!("some  ", fill_line("some", FillMode::PadSpaces(6)));
+        assert_eq!("some", fill_line("some", FillMode::PadSpaces(2)));
 
-        assert_eq!("\u{1b}[40m\u{1b}[49m", cut("\u{1b}[40m\u{1b}[49m", ..));
+        let s = "\u{1b}[31mred \u{1b}[44mblue\u{1b}[0m";
+        assert_eq!(
+            format!("{}\u{1b}[44m  \u{1b}[49m", s),
+            fill_line(s, FillMode::PadSpaces(10))
+        );
+        let s = "\u{1b}[44mblue\u{1b}[0m plain";
+        assert_eq!(format!("{}  ", s), fill_line(s, FillMode::PadSpaces(12)));
     }
 
     #[test]
-    fn cut_keep_general_color_test() {
+    fn head_lines_test() {
+        assert_eq!("", head_lines("", 2));
+        assert_eq!("", head_lines("a\nb", 0));
+        assert_eq!("a\n", head_lines("a\nb\n", 1));
+        assert_eq!("a\nb\n", head_lines("a\nb\n", 2));
+        assert_eq!("a\nb\n", head_lines("a\nb\n", 5));
+        assert_eq!("a\r\n\n", head_lines("a\r\n\n\nb", 2));
+
+        let s = "\u{1b}[31mred\nred \u{1b}[1mbold\r\nbold\u{1b}[0m plain\n";
+        assert_eq!("\u{1b}[31mred\u{1b}[39m\n", head_lines(s, 1));
         assert_eq!(
-            "\u{1b}[41m\u{1b}[30m\u{1b}[39m \u{1b}[34m12\u{1b}[39m\u{1b}[49m",
-            "\u{1b}[41m\u{1b}[30msomething\u{1b}[39m \u{1b}[34m123123\u{1b}[39m\u{1b}[49m"
-                .cut(9..12)
+            "\u{1b}[31mred\nred \u{1b}[1mbold\u{1b}[22;39m\r\n",
+            head_lines(s, 2)
         );
+        assert_eq!(s, head_lines(s, 3));
     }
 
     #[test]
-    fn cut_no_colored_str() {
-        assert_eq!("something", cut("something", ..));
-        assert_eq!("som", cut("something", ..3));
-        assert_eq!("some", cut("something", ..=3));
-        assert_eq!("et", cut("something", 3..5));
-        assert_eq!("eth", cut("something", 3..=5));
-        assert_eq!("ething", cut("something", 3..));
-        assert_eq!("something", cut("something", ..));
-        assert_eq!("", cut("", ..));
+    fn tail_lines_test() {
+        assert_eq!("", tail_lines("", 2));
+        assert_eq!("", tail_lines("a\nb", 0));
+        assert_eq!("b", tail_lines("a\nb", 1));
+        assert_eq!("b\n", tail_lines("a\nb\n", 1));
+        assert_eq!("a\nb\n", tail_lines("a\nb\n", 2));
+        assert_eq!("a\nb\n", tail_lines("a\nb\n", 5));
+        assert_eq!("\n", tail_lines("a\n\n", 1));
+        assert_eq!("\n\n", tail_lines("a\n\n\n", 2));
+
+        let s = "\u{1b}[31mred\nred \u{1b}[1mbold\r\nbold\u{1b}[0m plain\n";
+        assert_eq!("\u{1b}[1;31mbold\u{1b}[0m plain\n", tail_lines(s, 1));
+        assert_eq!(
+            "\u{1b}[31mred \u{1b}[1mbold\r\nbold\u{1b}[0m plain\n",
+            tail_lines(s, 2)
+        );
+        assert_eq!(s, tail_lines(s, 3));
+
+        let s = "\u{1b}[31mred\nred \u{1b}[1mbold\n\u{1b}[0m";
+        assert_eq!("\u{1b}[31mred \u{1b}[1mbold\n\u{1b}[0m", tail_lines(s, 1));
+        let s = "\u{1b}[31mred\nred\n";
+        assert_eq!("\u{1b}[31mred\u{1b}[39m\n", tail_lines(s, 1));
     }
 
     #[test]
-    fn dont_panic_on_exceeding_upper_bound() {
-        assert_eq!("TEXT", cut("TEXT", ..50));
-        assert_eq!("EXT", cut("TEXT", 1..50));
+    fn indent_test() {
+        assert_eq!("", indent("", "> "));
+        assert_eq!("> a\n> b\n", indent("a\nb\n", "> "));
+        assert_eq!("> a\r\n \n> b", indent("a\r\n \nb", "> "));
         assert_eq!(
-            "\u{1b}[31;40mTEXT\u{1b}[0m",
-            cut("\u{1b}[31;40mTEXT\u{1b}[0m", ..50)
+            "> a\r\n>  \n> b",
+            indent_with("a\r\n \nb", "> ", BlankLinePolicy::Indent)
         );
+
+        let s = "\u{1b}[31mred\n\nred \u{1b}[1mbold\nbold\u{1b}[0m plain";
         assert_eq!(
-            "\u{1b}[31;40mEXT\u{1b}[0m",
-            cut("\u{1b}[31;40mTEXT\u{1b}[0m", 1..50)
+            concat!(
+                "│ \u{1b}[31mred\u{1b}[39m\n",
+                "\n",
+                "│ \u{1b}[31mred \u{1b}[1mbold\u{1b}[22;39m\n",
+                "│ \u{1b}[1;31mbold\u{1b}[0m plain",
+            ),
+            indent(s, "│ ")
+        );
+        assert_eq!(
+            concat!(
+                "\u{1b}[34m│\u{1b}[39m \u{1b}[31mred\u{1b}[39m\n",
+                "\u{1b}[34m│\u{1b}[39m \n",
+                "\u{1b}[34m│\u{1b}[39m \u{1b}[31mred \u{1b}[1mbold\u{1b}[22;39m\n",
+                "\u{1b}[34m│\u{1b}[39m \u{1b}[1;31mbold\u{1b}[0m plain",
+            ),
+            indent_with(s, "\u{1b}[34m│\u{1b}[39m ", BlankLinePolicy::Indent)
         );
     }
 
     #[test]
-    fn dont_panic_on_exceeding_lower_bound() {
-        assert_eq!("", cut("TEXT", 10..));
-        assert_eq!("", cut("TEXT", 10..50));
-    }
+    fn number_lines_test() {
+        assert_eq!("", number_lines("", 1, ""));
+        assert_eq!("1 │ a\n2 │ \n3 │ b\n", number_lines("a\n\nb\n", 1, ""));
+        assert_eq!(" 9 │ a\r\n10 │ b", number_lines("a\r\nb", 9, ""));
+        assert_eq!("0 │ a", number_lines("a", 0, ""));
 
-    #[test]
-    #[should_panic = "One of indexes are not on a UTF-8 code point boundary"]
-    fn cut_a_mid_of_emojie_2_test() {
-        cut("😀", 1..2);
+        let s = "\u{1b}[31mred\nred \u{1b}[1mbold\u{1b}[0m";
+        assert_eq!(
+            concat!(
+                "\u{1b}[2m1 │\u{1b}[22m \u{1b}[31mred\u{1b}[39m\n",
+                "\u{1b}[2m2 │\u{1b}[22m \u{1b}[31mred \u{1b}[1mbold\u{1b}[0m",
+            ),
+            number_lines(s, 1, "\u{1b}[2m")
+        );
     }
 
+    #[cfg(feature = "unicode-width")]
     #[test]
-    #[should_panic = "One of indexes are not on a UTF-8 code point boundary"]
-    fn cut_a_mid_of_emojie_1_test() {
-        cut("😀", 1..);
-    }
+    fn number_lines_wrapped_test() {
+        assert_eq!("", number_lines_wrapped("", 1, "", 3));
+        assert_eq!(
+            "1 │ a b\n  │ c\n2 │ \n3 │ d\n",
+            number_lines_wrapped("a b c\n\nd\n", 1, "", 3)
+        );
 
-    #[test]
-    #[should_panic = "One of indexes are not on a UTF-8 code point boundary"]
-    fn cut_a_mid_of_emojie_0_test() {
-        cut("😀", ..1);
+        let s = "\u{1b}[31mred \u{1b}[1mbold\u{1b}[0m";
+        assert_eq!(
+            concat!(
+                "\u{1b}[2m1 │\u{1b}[22m \u{1b}[31mred\u{1b}[39m\n",
+                "\u{1b}[2m  │\u{1b}[22m \u{1b}[1;31mbold\u{1b}[0m",
+            ),
+            number_lines_wrapped(s, 1, "\u{1b}[2m", 4)
+        );
     }
 
     #[test]
-    fn cut_emojies_test() {
-        let emojes = "😀😃😄😁😆😅😂🤣🥲😊";
-        assert_eq!(emojes, emojes.cut(..));
-        assert_eq!("😀", emojes.cut(..4));
-        assert_eq!("😃😄", emojes.cut(4..12));
-        assert_eq!("🤣🥲😊", emojes.cut(emojes.find('🤣').unwrap()..));
+    fn dedent_test() {
+        assert_eq!("", dedent("", 2));
+        assert_eq!("a\n b\nc\n", dedent("  a\n   b\nc\n", 2));
+        assert_eq!("a\r\n\nb", dedent(" a\r\n \n\tb", 4));
+        assert_eq!("  a", dedent("  a", 0));
+
+        let s = "\u{1b}[31m  red\n   \u{1b}[1mbold\n \u{1b}[0m plain";
+        assert_eq!(
+            concat!(
+                "\u{1b}[31mred\u{1b}[39m\n",
+                "\u{1b}[31m \u{1b}[1mbold\u{1b}[22;39m\n",
+                "plain",
+            ),
+            dedent(s, 2)
+        );
     }
 
     #[test]
-    // todo: We probably need to fix it.
-    fn cut_colored_x_x_test() {
-        assert_ne!("", cut("\u{1b}[31;40mTEXT\u{1b}[0m", 3..3));
-        assert_ne!(
-            "",
-            cut(
-                "\u{1b}[31;40mTEXT\u{1b}[0m \u{1b}[34;42mTEXT\u{1b}[0m",
-                1..1
-            )
+    fn cut_lines_test() {
+        assert_eq!("om\nhe\n", cut_lines("some\nthe\n", 1..3));
+        assert_eq!("om\r\nhe\n\r\n", cut_lines("some\r\nthe\n\r\n", 1..3));
+        assert_eq!("\n\n", cut_lines("some\na\nthing", 2..2));
+        assert_eq!("me\n\ning", cut_lines("some\na\nthing", 2..));
+        assert_eq!("", cut_lines("", 1..3));
+
+        let s = "\u{1b}[31mwhen\nthe night\u{1b}[1m\nhas\u{1b}[39m come\nand\u{1b}[0m the";
+        assert_eq!(
+            concat!(
+                "\u{1b}[31mhe\u{1b}[39m\n",
+                "\u{1b}[31mhe\u{1b}[39m\n",
+                "\u{1b}[1;31mas\u{1b}[39m\u{1b}[22m\n",
+                "\u{1b}[1mnd\u{1b}[0m",
+            ),
+            cut_lines(s, 1..3)
+        );
+
+        assert_eq!(
+            concat!(
+                "\u{1b}[31mn\u{1b}[39m\n",
+                "\u{1b}[31m night\u{1b}[1m\u{1b}[22;39m\n",
+                "\u{1b}[1;31m\u{1b}[39m come\u{1b}[22m\n",
+                " the",
+            ),
+            cut_lines(s, 3..)
         );
-        assert_ne!("", cut("\u{1b}[31;40mTEXT\u{1b}[0m", ..0));
     }
 
     #[test]
-    fn cut_partially_colored_str_test() {
-        let s = "zxc_\u{1b}[31;40mTEXT\u{1b}[0m_qwe";
-        assert_eq!("zxc", s.cut(..3));
-        assert_eq!("zxc_\u{1b}[31;40mT\u{1b}[39m\u{1b}[49m", s.cut(..5));
-        assert_eq!("\u{1b}[31;40mEXT\u{1b}[0m_q", s.cut(5..10));
-        assert_eq!("\u{1b}[31;40m\u{1b}[0m", s.cut(12..));
+    fn cut_lines_short_line_test() {
+        let s = "\u{1b}[31mabc\nd\nefg\u{1b}[0m";
+        assert_eq!(
+            "\u{1b}[31mc\u{1b}[39m\n\n\u{1b}[31mg\u{1b}[0m",
+            cut_lines(s, 2..3)
+        );
+        assert_eq!("\n\n", cut_lines(s, 3..));
+        assert_eq!("\n\n", cut_lines("\u{1b}[31m\n\u{1b}[1m\n\u{1b}[0m", ..));
     }
 
+    #[cfg(feature = "unicode-width")]
     #[test]
-    fn chunks_not_colored_test() {
+    fn cut_block_test() {
+        let s = "some\nthing\n\nmore\r\nand more";
+        assert_eq!("hin\n\nore", cut_block(s, 1..4, 1..4));
+        assert_eq!("ome\nhing\n\nore\nnd more", cut_block(s, 1.., ..));
+        assert_eq!("and more", cut_block(s, .., 4..100));
+        assert_eq!("", cut_block(s, .., 100..));
+        assert_eq!("", cut_block(s, .., 2..2));
+        assert_eq!(
+            "ome   \nhing  \n      \nore   \nnd mor",
+            cut_block_with(s, 1..7, .., ShortLinePolicy::Pad)
+        );
+
+        let s = "你好世界\nab你好";
         assert_eq!(
-            vec!["som".to_string(), "eth".to_string(), "ing".to_string()],
-            chunks("something", 3)
+            "好\nb你",
+            cut_block_with(s, 1..5, .., ShortLinePolicy::Keep)
         );
         assert_eq!(
-            vec![
-                "so".to_string(),
-                "me".to_string(),
-                "th".to_string(),
-                "in".to_string(),
-                "g".to_string()
-            ],
-            chunks("something", 2)
+            " 好世\nb你好",
+            cut_block_with(s, 1..6, .., ShortLinePolicy::Pad)
+        );
+
+        let s = "\u{1b}[31mwhen\nthe night\u{1b}[1m\nhas\u{1b}[39m come\nand\u{1b}[0m the";
+        assert_eq!(
+            concat!(
+                "\u{1b}[31mhe\u{1b}[39m\n",
+                "\u{1b}[1;31mas\u{1b}[39m\u{1b}[22m",
+            ),
+            cut_block(s, 1..3, 1..3)
         );
         assert_eq!(
-            vec!["a".to_string(), "b".to_string(), "c".to_string()],
-            chunks("abc", 1)
+            concat!("\u{1b}[31mn\u{1b}[39m   \n", "\u{1b}[31m nig\u{1b}[39m",),
+            cut_block_with(s, 3..7, ..2, ShortLinePolicy::Pad)
         );
-        assert_eq!(vec!["something".to_string()], chunks("something", 99));
+
+        // short lines
+        let s = "\u{1b}[31mabc\nd\nefg\u{1b}[0m";
+        assert_eq!(
+            "\u{1b}[31mc\u{1b}[39m\n\n\u{1b}[31mg\u{1b}[0m",
+            cut_block(s, 2..3, ..)
+        );
+        assert_eq!(
+            "\u{1b}[31mc\u{1b}[39m \n  \n\u{1b}[31mg\u{1b}[0m ",
+            cut_block_with(s, 2..4, .., ShortLinePolicy::Pad)
+        );
+        assert_eq!("\n\n", cut_block(s, 3.., ..));
+        assert_eq!(
+            " \n ",
+            cut_block_with("\u{1b}[31m你\n\u{1b}[0m", 1..2, .., ShortLinePolicy::Pad)
+        );
+    }
+
+    // a string without escape sequences after its last text
+    pub(crate) fn trim_trailing_escapes(s: &str) -> &str {
+        let end = parse_ansi(s)
+            .filter_map(|token| match token {
+                Token::Text(text) => {
+                    Some(text.as_ptr() as usize - s.as_ptr() as usize + text.len())
+                }
+                Token::Escape(_) => None,
+            })
+            .last()
+            .unwrap_or(0);
+
+        &s[..end]
     }
 
     #[test]
-    #[should_panic]
-    fn chunks_panic_when_n_is_zero() {
-        chunks("something", 0);
+    fn style_colors_test() {
+        let style = |s: &str| AnsiIndexedStr::new(s).style_at(0).unwrap();
+        assert_eq!(Some(Color::Basic(15)), style("\u{1b}[97mA").fg());
+        assert_eq!(Some(Color::Basic(8)), style("\u{1b}[100mA").bg());
+        assert_eq!(Some(Color::Basic(7)), style("\u{1b}[47mA").bg());
+        assert!(style("\u{1b}[3;7;8;9;53mA").is_italic());
+        assert!(style("\u{1b}[7mA").is_inverse());
+        assert!(style("\u{1b}[8mA").is_hidden());
+        assert!(style("\u{1b}[9mA").is_crossed_out());
+        assert!(style("\u{1b}[53mA").is_overlined());
+        assert!(style("\u{1b}[5mA").is_blink());
+        assert!(style("\u{1b}[21mA").is_double_underline());
+        assert!(style("\u{1b}[2mA").is_faint());
+        assert!(style("A").is_empty());
     }
 
     #[test]
-    fn chunks_colored() {
-        let text = "\u{1b}[31;40mTEXT\u{1b}[0m";
+    fn close_test() {
+        let policies = [
+            ResetPolicy::PerAttribute,
+            ResetPolicy::FullReset,
+            ResetPolicy::None,
+            ResetPolicy::ReuseOriginal,
+            ResetPolicy::ParserCompatible,
+        ];
+
+        for s in [
+            "",
+            "text",
+            "\u{1b}[31;40mTEXT\u{1b}[0m \u{1b}[34m😀😃\u{1b}[39m zxc",
+            "\u{1b}]8;;http://example.com\u{1b}\\link\u{1b}]8;;\u{1b}\\",
+            "\u{1b}[31m\u{1b}[m",
+            "\u{1b}]0;title\u{7}\u{1b}[2J",
+        ] {
+            assert_eq!(close(s), s);
+            for policy in policies {
+                assert_eq!(close_with(s, policy), s, "{:?}", policy);
+            }
+
+            let mut buf = s.to_owned();
+            close_in_place(&mut buf);
+            assert_eq!(buf, s);
+        }
+
+        let s = "\u{1b}[31;40mTEXT\u{1b}[49m \u{1b}[1;4m😀";
+        assert_eq!(close(s), format!("{}\u{1b}[24;22;39m", s));
         assert_eq!(
-            vec![
-                "\u{1b}[31;40mT\u{1b}[39m\u{1b}[49m",
-                "\u{1b}[31;40mE\u{1b}[39m\u{1b}[49m",
-                "\u{1b}[31;40mX\u{1b}[39m\u{1b}[49m",
-                "\u{1b}[31;40mT\u{1b}[0m"
-            ],
-            chunks(text, 1)
+            close_with(s, ResetPolicy::FullReset),
+            format!("{}\u{1b}[0m", s)
         );
+        assert_eq!(close_with(s, ResetPolicy::None), s);
+        assert_eq!(close_with(s, ResetPolicy::ReuseOriginal), close(s));
         assert_eq!(
-            vec![
-                "\u{1b}[31;40mTE\u{1b}[39m\u{1b}[49m",
-                "\u{1b}[31;40mXT\u{1b}[0m"
-            ],
-            chunks(text, 2)
+            close_with("\u{1b}[1;3;4;5;7;31;42mA", ResetPolicy::ParserCompatible),
+            "\u{1b}[1;3;4;5;7;31;42mA\u{1b}[49;39;27m\u{1b}[25;24;23m\u{1b}[22m"
+        );
+
+        let s = "\u{1b}]8;;http://a\u{7}link";
+        assert_eq!(close(s), "\u{1b}]8;;http://a\u{7}link\u{1b}]8;;\u{1b}\\");
+
+        // a truncated escape sequence at the end is dropped
+        assert_eq!(close("text\u{1b}"), "text");
+        assert_eq!(
+            close("\u{1b}[31mtext\u{1b}[0m\u{1b}[3"),
+            "\u{1b}[31mtext\u{1b}[0m"
         );
+        assert_eq!(close("\u{1b}[31mtext\u{1b}[3"), "\u{1b}[31mtext\u{1b}[39m");
         assert_eq!(
-            vec![
-                "\u{1b}[31;40mTEX\u{1b}[39m\u{1b}[49m",
-                "\u{1b}[31;40mT\u{1b}[0m"
-            ],
-            chunks(text, 3)
+            close_with("\u{1b}[31mtext\u{1b}]8;;http", ResetPolicy::FullReset),
+            "\u{1b}[31mtext\u{1b}[0m"
         );
+        let mut buf = String::from("\u{1b}[1mtext\u{1b}[");
+        close_in_place(&mut buf);
+        assert_eq!(buf, "\u{1b}[1mtext\u{1b}[22m");
+
+        let mut buf = String::from(s);
+        close_in_place_with(&mut buf, ResetPolicy::PerAttribute);
+        assert_eq!(buf, close(s));
+
+        for policy in policies {
+            let s = "\u{1b}[31;40mTEXT\u{1b}[0m \u{1b}[34m😀😃\u{1b}[4m zxc";
+            let closed = close_with(s, policy);
+            assert!(closed.starts_with(s));
+            assert_eq!(has_unclosed_styles(&closed), policy == ResetPolicy::None);
+        }
     }
 
     #[test]
-    fn chunk_emojies_test() {
-        let emojes = "😀😃😄😁😆😅😂🤣🥲😊";
+    fn trailing_style_test() {
+        assert_eq!(Style::default(), trailing_style(""));
+        assert_eq!(Style::default(), trailing_style("text"));
+        assert_eq!(
+            Style::default(),
+            trailing_style("\u{1b}[1;31mtext\u{1b}[0m")
+        );
+        assert_eq!(
+            Style::default(),
+            trailing_style("\u{1b}[1;31mtext\u{1b}[22;39m")
+        );
+
+        let s = "\u{1b}[38;5;100;48;2;1;2;3;58;5;7mA\u{1b}[2;3;4:3;5;7;8;9;53mB";
+        let style = trailing_style(s);
+        assert_eq!(Some(Color::Indexed(100)), style.fg());
+        assert_eq!(Some(Color::Rgb(1, 2, 3)), style.bg());
+        assert_eq!(Some(Color::Indexed(7)), style.underline_color());
+        assert!(style.is_faint());
+        assert!(style.is_italic());
+        assert!(style.is_underline());
+        assert!(style.is_blink());
+        assert!(style.is_inverse());
+        assert!(style.is_hidden());
+        assert!(style.is_crossed_out());
+        assert!(style.is_overlined());
+        assert!(!style.is_bold());
+
+        // reopening a trailing style gives the same style
+        assert_eq!(style, trailing_style(&style.to_string()));
+        assert_eq!(style, trailing_style(&format!("{}text", style)));
+
+        // the order styles are opened in doesn't matter
         assert_eq!(
-            vec!["😀", "😃", "😄", "😁", "😆", "😅", "😂", "🤣", "🥲", "😊",],
-            chunks(emojes, 1)
+            trailing_style("\u{1b}[1m\u{1b}[31m"),
+            trailing_style("\u{1b}[31;1m")
         );
         assert_eq!(
-            vec!["😀😃", "😄😁", "😆😅", "😂🤣", "🥲😊",],
-            chunks(emojes, 2)
+            trailing_style("\u{1b}[0m\u{1b}[31m"),
+            trailing_style("\u{1b}[31m")
         );
-        assert_eq!(vec!["😀😃😄", "😁😆😅", "😂🤣🥲", "😊",], chunks(emojes, 3));
-        assert_eq!(vec!["😀😃😄😁", "😆😅😂🤣", "🥲😊",], chunks(emojes, 4));
-        assert_eq!(vec!["😀😃😄😁😆", "😅😂🤣🥲😊",], chunks(emojes, 5));
-        assert_eq!(vec!["😀😃😄😁😆😅", "😂🤣🥲😊",], chunks(emojes, 6));
-        assert_eq!(vec!["😀😃😄😁😆😅😂", "🤣🥲😊",], chunks(emojes, 7));
-        assert_eq!(vec!["😀😃😄😁😆😅😂🤣", "🥲😊",], chunks(emojes, 8));
-        assert_eq!(vec!["😀😃😄😁😆😅😂🤣🥲", "😊",], chunks(emojes, 9));
-        assert_eq!(vec!["😀😃😄😁😆😅😂🤣🥲😊"], chunks(emojes, 10));
-        assert_eq!(vec!["😀😃😄😁😆😅😂🤣🥲😊"], chunks(emojes, 11));
+        assert_ne!(trailing_style("\u{1b}[31m"), trailing_style("\u{1b}[32m"));
+        assert_ne!(trailing_style("\u{1b}[31m"), trailing_style("\u{1b}[31;4m"));
+        assert_ne!(
+            trailing_style("\u{1b}]8;;http://a\u{7}"),
+            trailing_style("\u{1b}]8;;http://b\u{7}")
+        );
+
+        let style = trailing_style("\u{1b}[1;31mA");
+        assert_eq!(
+            style,
+            AnsiIndexedStr::new("\u{1b}[1;31mA").style_at(0).unwrap()
+        );
+
+        // a style is opened the same way a minimal cut opens it
+        let s = concat!(
+            "\u{1b}[1mA\u{1b}[31mB\u{1b}[44mC\u{1b}[4:3mD\u{1b}[38;5;100mE",
+            "\u{1b}[48;2;1;2;3mF\u{1b}]8;;http://a\u{7}G\u{1b}[0mH",
+        );
+        let indexed = AnsiIndexedStr::new(s);
+        for (i, c) in srip_ansi_sequences(s).char_indices() {
+            let cut = s.cut_minimal(i..i + 1);
+            let prefix = &cut[..cut.find(c).unwrap()];
+            assert_eq!(indexed.style_at(i).unwrap().to_string(), prefix, "{}", c);
+        }
+    }
+
+    #[test]
+    fn unclosed_styles_test() {
+        for s in [
+            "",
+            "text",
+            "\u{1b}[31;40mTEXT\u{1b}[0m \u{1b}[34m😀😃\u{1b}[39m zxc",
+            "\u{1b}[1m\u{1b}[31m你b\u{1b}[4m\u{1b}[44mcd\u{1b}[0m\n\u{1b}[32m好f\u{1b}[39m\u{1b}[22m",
+            "\u{1b}]8;;http://example.com\u{1b}\\link\u{1b}]8;;\u{1b}\\",
+            "\u{1b}[31m\u{1b}[m",
+            "\u{1b}]0;title\u{7}\u{1b}[2J",
+        ] {
+            assert!(!has_unclosed_styles(s), "{:?}", s);
+            assert!(unclosed_styles(s).is_empty(), "{:?}", s);
+        }
+
+        let style = unclosed_styles("\u{1b}[31;40mTEXT\u{1b}[49m \u{1b}[1;4m😀");
+        assert!(has_unclosed_styles(
+            "\u{1b}[31;40mTEXT\u{1b}[49m \u{1b}[1;4m😀"
+        ));
+        assert_eq!(Some(Color::Basic(1)), style.fg());
+        assert_eq!(None, style.bg());
+        assert!(style.is_bold());
+        assert!(style.is_underline());
+        assert!(!style.is_italic());
+        assert_eq!(None, style.hyperlink());
+
+        let style = unclosed_styles("\u{1b}[31mA\u{1b}[39m\u{1b}]8;id=1;http://a\u{7}link");
+        assert!(has_unclosed_styles("\u{1b}]8;id=1;http://a\u{7}link"));
+        assert_eq!(None, style.fg());
+        assert_eq!(Some("http://a"), style.hyperlink());
+        assert!(!style.is_empty());
+
+        // a cut always closes what it opens
+        let s = "\u{1b}[31;40mTEXT\u{1b}[0m \u{1b}[34m😀😃\u{1b}[4m zxc";
+        assert!(has_unclosed_styles(s));
+        for (i, _) in strip(s).char_indices() {
+            assert!(!has_unclosed_styles(&s.cut(i..)), "{}..", i);
+        }
+    }
+
+    #[test]
+    fn chunks_method_test() {
+        let s = "\u{1b}[31;40mTEXT\u{1b}[0m \u{1b}[34m😀😃\u{1b}[39m zxc";
+        for n in 1..10 {
+            assert_eq!(chunks(s, n), s.chunks(n));
+            assert_eq!(chunks(s, n), s.to_string().chunks(n));
+        }
+
+        fn chunks_generic<T: AnsiCut>(text: T) -> Vec<String> {
+            text.chunks(3)
+        }
+
+        assert_eq!(vec!["som", "eth", "ing"], chunks_generic("something"));
     }
 }
@@ -0,0 +1,348 @@
+//! Removing escape sequences from a string, or only the ones which are not safe to print.
+
+use crate::{parse_ansi, Token};
+use std::borrow::Cow;
+
+/// Removes all escape sequences from a string except SGR ones, which set colors and styles.
+///
+/// It's a recommended step to take before displaying or processing untrusted data,
+/// like commit messages or chat messages,
+/// so they can't move a cursor, set a window title, write into a clipboard (OSC 52) and so on.
+///
+/// CSI, OSC, DCS, APC, PM and SOS sequences are removed together with their content,
+/// as well as single char escapes (like `\x1b7`) and C1 controls.
+/// Hyperlinks are removed too.
+/// A SGR sequence with a C1 introducer is written in its 7-bit form,
+/// and a SGR one with more than 256 bytes of parameters is removed.
+///
+/// A sequence which is malformed is removed up to the char which breaks it,
+/// and a string sequence (like OSC) which is never terminated is removed till the end of a string,
+/// the same way a terminal would swallow it.
+/// Other control chars, like `\n`, `\r` or `\t`, are kept as they are.
+///
+/// # Examples
+///
+/// ```rust
+/// let s = "\u{1b}]0;pwned\u{7}\u{1b}[31mred\u{1b}[2J\u{1b}]52;c;Y2F0\u{7}\u{1b}[0m";
+/// assert_eq!(ansi_cut::sanitize(s), "\u{1b}[31mred\u{1b}[0m");
+/// ```
+pub fn sanitize(s: &str) -> String {
+    let mut buf = String::with_capacity(s.len());
+    let mut rest = s;
+    while let Some(i) = rest.find(|c| matches!(c, '\u{1b}' | '\u{80}'..='\u{9f}')) {
+        buf.push_str(&rest[..i]);
+
+        let (sgr, len) = scan_control_sequence(&rest[i..]);
+        if let Some(params) = sgr {
+            buf.push_str("\u{1b}[");
+            buf.push_str(params);
+            buf.push('m');
+        }
+
+        rest = &rest[i + len..];
+    }
+
+    buf.push_str(rest);
+
+    buf
+}
+
+// A SGR sequence with longer parameters is not kept by `sanitize`.
+const MAX_SGR_PARAMS_LEN: usize = 256;
+
+// Scans an escape sequence or a C1 control at the beginning of a string,
+// returns parameters of it if it's a SGR sequence and its length.
+fn scan_control_sequence(s: &str) -> (Option<&str>, usize) {
+    let mut chars = s.chars();
+    let start = match chars.next() {
+        Some('\u{1b}') => match chars.next() {
+            Some('[') => return scan_csi(s, 2),
+            Some(']' | 'P' | '_' | '^' | 'X') => return (None, scan_string_sequence(s, 2)),
+            // intermediate bytes followed by a final one
+            Some('\u{20}'..='\u{2f}') => 2,
+            Some('\u{30}'..='\u{7e}') => return (None, 2),
+            // a lone ESC, the char after it is left as it is
+            _ => return (None, 1),
+        },
+        Some('\u{9b}') => return scan_csi(s, 2),
+        Some('\u{90}' | '\u{98}' | '\u{9d}' | '\u{9e}' | '\u{9f}') => {
+            return (None, scan_string_sequence(s, 2))
+        }
+        Some(c) => return (None, c.len_utf8()),
+        None => return (None, 0),
+    };
+
+    let end = s[start..]
+        .find(|c| !matches!(c, '\u{20}'..='\u{2f}'))
+        .map_or(s.len(), |i| start + i);
+    match s[end..].chars().next() {
+        Some('\u{30}'..='\u{7e}') => (None, end + 1),
+        _ => (None, end),
+    }
+}
+
+// Scans a CSI sequence which parameters start at `start`,
+// returns its parameters if it's a SGR sequence and its length.
+fn scan_csi(s: &str, start: usize) -> (Option<&str>, usize) {
+    let params = &s[start..];
+    // parameter and intermediate bytes followed by a final one
+    let end = match params.find(|c| !matches!(c, '\u{20}'..='\u{3f}')) {
+        Some(end) => end,
+        None => return (None, s.len()),
+    };
+
+    match params[end..].chars().next() {
+        Some('m')
+            if end <= MAX_SGR_PARAMS_LEN
+                && params[..end]
+                    .bytes()
+                    .all(|b| matches!(b, b'0'..=b'9' | b';' | b':')) =>
+        {
+            (Some(&params[..end]), start + end + 1)
+        }
+        Some('\u{40}'..='\u{7e}') => (None, start + end + 1),
+        // a sequence is broken by the char, which is left as it is
+        _ => (None, start + end),
+    }
+}
+
+// Scans a string sequence (OSC, DCS, APC, PM or SOS) which content starts at `start`,
+// returns its length.
+//
+// It's terminated by ST or BEL,
+// while an ESC which doesn't start ST aborts it and it's left as it is.
+fn scan_string_sequence(s: &str, start: usize) -> usize {
+    let body = &s[start..];
+    match body.find(['\u{7}', '\u{9c}', '\u{1b}']) {
+        Some(end) if body[end..].starts_with("\u{1b}\\") => start + end + 2,
+        Some(end) if body[end..].starts_with('\u{1b}') => start + end,
+        Some(end) => start + end + body[end..].chars().next().map_or(0, char::len_utf8),
+        None => s.len(),
+    }
+}
+
+/// Checks whether a string has ANSI escape sequences.
+///
+/// The string is not parsed, it's only scanned for ESC and C1 introducers (CSI, OSC, DCS, SOS, PM and APC).
+/// So it's a conservative check, a lone ESC which is not followed by a sequence makes it return `true`.
+/// It's fine as such an ESC is dropped by [`AnsiCut::cut`](crate::AnsiCut::cut) and [`strip`] anyway,
+/// so if it returns `false` the string can be used as it is.
+///
+/// # Examples
+///
+/// ```rust
+/// use owo_colors::{OwoColorize, colors::*};
+/// assert!(ansi_cut::contains_ansi(&"When the night".fg::<Red>().to_string()));
+/// assert!(ansi_cut::contains_ansi("\u{9b}31mWhen the night"));
+/// assert!(!ansi_cut::contains_ansi("When the night"));
+/// ```
+pub fn contains_ansi(s: &str) -> bool {
+    // C1 chars are encoded as 0xC2 0x80..=0x9F
+    let bytes = s.as_bytes();
+    bytes.iter().enumerate().any(|(i, &b)| {
+        b == 0x1b || b == 0xc2 && matches!(bytes.get(i + 1), Some(0x90 | 0x98 | 0x9b | 0x9d..=0x9f))
+    })
+}
+
+/// Checks whether a string has no ANSI escape sequences, see [`contains_ansi`].
+///
+/// # Examples
+///
+/// ```rust
+/// assert!(ansi_cut::is_plain("When the night"));
+/// assert!(!ansi_cut::is_plain("\u{1b}[31mWhen the night\u{1b}[39m"));
+/// ```
+pub fn is_plain(s: &str) -> bool {
+    !contains_ansi(s)
+}
+
+/// Removes ANSI control sequences from a string, leaving only its visible text.
+///
+/// The string is borrowed if it has no escape sequences.
+/// Payloads of OSC, DCS, SOS, PM and APC sequences (like a window title or a hyperlink URL)
+/// are removed together with the sequences, and a sequence which is never terminated
+/// is removed till the end of the string.
+///
+/// It's what indexes of [`AnsiCut::cut`](crate::AnsiCut::cut) are defined in.
+///
+/// # Examples
+///
+/// ```rust
+/// use std::borrow::Cow;
+/// use owo_colors::{OwoColorize, colors::*};
+/// let colored_text = format!("\u{1b}]0;title\u{7}{}", "When the night".fg::<Red>());
+/// assert_eq!(ansi_cut::strip(&colored_text), "When the night");
+/// assert!(matches!(ansi_cut::strip("When the night"), Cow::Borrowed(_)));
+/// ```
+pub fn strip(s: &str) -> Cow<'_, str> {
+    if is_plain(s) {
+        return Cow::Borrowed(s);
+    }
+
+    let mut buf = String::with_capacity(s.len());
+    strip_into(s, &mut buf);
+    Cow::Owned(buf)
+}
+
+/// The same as [`strip`] but the visible text is appended to a buffer,
+/// so the buffer can be reused for many strings.
+///
+/// # Examples
+///
+/// ```rust
+/// let mut buf = String::new();
+/// for line in ["\u{1b}[31mWhen\u{1b}[0m", "\u{1b}[32mthe night\u{1b}[0m"] {
+///     buf.clear();
+///     ansi_cut::strip_into(line, &mut buf);
+///     println!("{}", buf);
+/// }
+/// assert_eq!(buf, "the night");
+/// ```
+pub fn strip_into(s: &str, buf: &mut String) {
+    for token in parse_ansi(s) {
+        if let Token::Text(text) = token {
+            buf.push_str(text);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{srip_ansi_sequences, AnsiCut, ESCAPE_INTRODUCERS};
+
+    #[test]
+    fn contains_ansi_test() {
+        for c in ESCAPE_INTRODUCERS {
+            let s = format!("text{}", c);
+            assert!(contains_ansi(&s), "{:?}", s);
+            assert!(!is_plain(&s), "{:?}", s);
+        }
+
+        for s in [
+            "",
+            "plain text",
+            "你好 😀 e\u{301}\t\n",
+            "\u{85}\u{9c}\u{a0}\u{c2}",
+            "[31mtext[0m",
+        ] {
+            assert!(!contains_ansi(s), "{:?}", s);
+            assert!(is_plain(s), "{:?}", s);
+        }
+
+        assert!(contains_ansi("\u{1b}[31mtext\u{1b}[0m"));
+        assert!(contains_ansi("\u{1b}]0;title\u{7}"));
+        assert!(contains_ansi("text\u{1b}"));
+    }
+
+    #[test]
+    fn strip_test() {
+        assert!(matches!(strip(""), Cow::Borrowed("")));
+        assert!(matches!(strip("plain text"), Cow::Borrowed("plain text")));
+        assert!(matches!(strip("你好 😀 e\u{301}\n"), Cow::Borrowed(_)));
+        assert!(matches!(strip("\u{1b}[31mred\u{1b}[0m"), Cow::Owned(_)));
+
+        assert_eq!(strip("\u{1b}[31;40mTEXT\u{1b}[0m zxc"), "TEXT zxc");
+        assert_eq!(
+            strip("\u{1b}]8;;http://example.com\u{1b}\\link\u{1b}]8;;\u{1b}\\ text"),
+            "link text"
+        );
+        assert_eq!(
+            strip("\u{1b}]8;;http://example.com\u{7}link\u{1b}]8;;\u{7}"),
+            "link"
+        );
+        assert_eq!(strip("\u{1b}]0;title\u{7}text"), "text");
+        assert_eq!(strip("\u{1b}]0;title\u{1b}\\text"), "text");
+        assert_eq!(strip("\u{9d}0;title\u{9c}text"), "text");
+        assert_eq!(strip("\u{9b}31mtext\u{9b}0m"), "text");
+
+        // string sequences
+        assert_eq!(strip("\u{1b}Pq#0;2;0;0;0#1~~@@vv\u{1b}\\text"), "text");
+        assert_eq!(strip("\u{1b}_Gf=100;AAAA\u{1b}\\text"), "text");
+        assert_eq!(strip("\u{1b}^private\u{1b}\\text"), "text");
+        assert_eq!(strip("\u{1b}Xstring\u{7}text"), "text");
+        assert_eq!(strip("\u{90}q#0\u{9c}text"), "text");
+        assert_eq!(
+            strip("\u{9f}Gf=100\u{9c}te\u{9e}pm\u{9c}xt\u{98}sos\u{9c}"),
+            "text"
+        );
+
+        // unterminated
+        assert_eq!(strip("text\u{1b}]0;title"), "text");
+        assert_eq!(strip("text\u{1b}Pq#0;2;0;0;0"), "text");
+        assert_eq!(strip("text\u{1b}[31"), "text");
+        assert_eq!(strip("text\u{1b}"), "text");
+
+        let mut buf = String::from("prefix ");
+        strip_into("\u{1b}[31mred\u{1b}[0m", &mut buf);
+        strip_into(" plain", &mut buf);
+        assert_eq!(buf, "prefix red plain");
+
+        let s = "\u{1b}[1m\u{1b}[31m你b\u{1b}[4m\u{1b}[44mcd\u{1b}[0m\n\u{1b}[32m好f\u{1b}[39m\u{1b}[22m";
+        assert_eq!(strip(s), srip_ansi_sequences(s));
+    }
+
+    #[test]
+    fn sanitize_test() {
+        assert_eq!("", sanitize(""));
+        assert_eq!("abc\r\n\tdef", sanitize("abc\r\n\tdef"));
+        assert_eq!(
+            "\u{1b}[31;1mab\u{1b}[38:5:1mc\u{1b}[m",
+            sanitize("\u{1b}[31;1mab\u{1b}[38:5:1mc\u{1b}[m")
+        );
+
+        // CSI
+        assert_eq!("ab", sanitize("a\u{1b}[2Jb"));
+        assert_eq!("ab", sanitize("a\u{1b}[10;20Hb"));
+        assert_eq!("ab", sanitize("a\u{1b}[?25lb"));
+        assert_eq!("ab", sanitize("a\u{1b}[>1mb"));
+        assert_eq!("ab", sanitize("a\u{1b}[1 mb"));
+        assert_eq!("\u{1b}[31mab", sanitize("\u{9b}31ma\u{9b}2Jb"));
+
+        // OSC, DCS, APC, PM and SOS
+        assert_eq!("ab", sanitize("a\u{1b}]0;title\u{7}b"));
+        assert_eq!("ab", sanitize("a\u{1b}]52;c;Y2F0\u{1b}\\b"));
+        assert_eq!(
+            "link",
+            sanitize("\u{1b}]8;;https://example.com\u{1b}\\link\u{1b}]8;;\u{1b}\\")
+        );
+        assert_eq!("ab", sanitize("a\u{1b}P1$r0m\u{1b}\\b"));
+        assert_eq!("ab", sanitize("a\u{1b}_payload\u{1b}\\b"));
+        assert_eq!("ab", sanitize("a\u{1b}^payload\u{1b}\\b"));
+        assert_eq!("ab", sanitize("a\u{1b}Xpayload\u{1b}\\b"));
+        assert_eq!("ab", sanitize("a\u{9d}0;title\u{9c}b"));
+        assert_eq!("ab", sanitize("a\u{90}payload\u{9c}b"));
+
+        // single escapes and C1 controls
+        assert_eq!("ab", sanitize("a\u{1b}7b"));
+        assert_eq!("ab", sanitize("a\u{1b}cb"));
+        assert_eq!("ab", sanitize("a\u{1b}(Bb"));
+        assert_eq!("ab", sanitize("a\u{85}\u{8d}b"));
+
+        // malformed and truncated sequences
+        assert_eq!("a", sanitize("a\u{1b}"));
+        assert_eq!("a", sanitize("a\u{1b}[31"));
+        assert_eq!("a", sanitize("a\u{1b}]0;title"));
+        assert_eq!("a", sanitize("a\u{1b}("));
+        assert_eq!("a", sanitize("a\u{1b}\u{1b}b"));
+        assert_eq!("a\nb", sanitize("a\u{1b}\nb"));
+        assert_eq!("a\nb", sanitize("a\u{1b}[31\nb"));
+        assert_eq!("a\u{1b}[31mb", sanitize("a\u{1b}[2\u{1b}[31mb"));
+        assert_eq!("a\u{1b}[31mb", sanitize("a\u{1b}]0;\u{1b}[31mb"));
+        assert_eq!("aüb", sanitize("a\u{1b}[2üb"));
+
+        // absurdly long parameters
+        let params = "1;".repeat(10_000);
+        assert_eq!("ab", sanitize(&format!("a\u{1b}[{}mb", params)));
+        assert_eq!("ab", sanitize(&format!("a\u{1b}[{}Hb", params)));
+        let params = "1;".repeat(100);
+        assert_eq!(
+            format!("a\u{1b}[{}mb", params),
+            sanitize(&format!("a\u{1b}[{}mb", params))
+        );
+
+        let s = sanitize("\u{1b}]0;x\u{7}\u{1b}[31mhello\u{1b}[2K world\u{1b}[39m");
+        assert_eq!("\u{1b}[31mhello\u{1b}[39m", s.cut(..5));
+        assert_eq!("\u{1b}[31m world\u{1b}[39m", s.cut(5..));
+    }
+}